@@ -0,0 +1,47 @@
+use crate::test_support::make_accessor;
+use crate::{decode, SegmentAccess, SEGMENT_SS, VECTOR_GP};
+
+#[test]
+fn effective_offset_covers_sib_rip_and_wraparound() {
+    let (_memory, mut acc) = make_accessor();
+    acc.write_by_size(0, 0x1000, 64); // rax
+    acc.write_by_size(3, 0xFFFF_FFF0, 64); // rbx
+    acc.write_by_size(17, 0x20, 64); // r9
+
+    // mov rdx, [rax + r9*8 + 0x10]
+    let d = decode(&[0x4A, 0x8B, 0x54, 0xC8, 0x10], 64);
+    assert_eq!(acc.effective_offset(&d, 0), 0x1000 + 0x20 * 8 + 0x10);
+
+    // mov eax, [rip - 0x10]
+    let d = decode(&[0x8B, 0x05, 0xF0, 0xFF, 0xFF, 0xFF], 64);
+    assert_eq!(acc.effective_offset(&d, 0x40_0006), 0x3F_FFF6);
+
+    // 67: mov eax, [ebx + 0x20] wraps at 4 GiB.
+    let d = decode(&[0x67, 0x8B, 0x43, 0x20], 64);
+    assert_eq!(acc.effective_offset(&d, 0), 0x10);
+
+    // 16-bit: mov ax, [bx + si - 1] wraps at 64 KiB.
+    acc.write_by_size(3, 0, 64);
+    acc.write_by_size(6, 0, 64);
+    let d = decode(&[0x8B, 0x40, 0xFF], 16);
+    assert_eq!(acc.effective_offset(&d, 0), 0xFFFF);
+}
+
+#[test]
+fn effective_address_applies_default_segment() {
+    let (_memory, mut acc) = make_accessor();
+    acc.load_segment_real_mode(SEGMENT_SS, 0x2000);
+    acc.write_by_size(5, 0x10, 64); // bp
+    acc.write_by_size(7, 0x4, 64); // di
+
+    // mov ax, [bp + di + 2] is SS-relative.
+    let d = decode(&[0x8B, 0x43, 0x02], 16);
+    let ea = acc.effective_address(&d, 0, 16, SegmentAccess::Read);
+    assert_eq!((ea.segment, ea.offset, ea.linear, ea.fault), (SEGMENT_SS as u32, 0x16, 0x20016, 0));
+
+    // A 32-bit offset past the real-mode limit faults.
+    acc.write_by_size(0, 0x10000, 64);
+    let d = decode(&[0x67, 0x8B, 0x00], 16);
+    let ea = acc.effective_address(&d, 0, 16, SegmentAccess::Read);
+    assert_eq!((ea.offset, ea.fault >> 16), (0x10000, VECTOR_GP));
+}
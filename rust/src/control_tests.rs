@@ -0,0 +1,100 @@
+use crate::test_support::make_accessor;
+use crate::{SegmentCache, CR0_PE, CR0_PG, CR3_NO_FLUSH, CR4_LA57, CR4_PAE, CR4_PCIDE, CR4_SMAP, CR4_SMEP, EFER_LMA, EFER_LME, SEGMENT_CS};
+
+const GP: u32 = 0x0D << 16;
+
+#[test]
+fn cr0_rejects_invalid_combinations() {
+    let (_memory, mut acc) = make_accessor();
+    let cr0 = acc.read_control_register(0);
+
+    assert_eq!(acc.load_control_register(0, cr0 | CR0_PG), GP);
+    assert_eq!(acc.load_control_register(0, cr0 | (1 << 29)), GP);
+    assert_eq!(acc.load_control_register(0, cr0 | (1 << 32)), GP);
+    assert_eq!(acc.read_control_register(0), cr0);
+
+    // Undefined low bits are ignored rather than faulting.
+    assert_eq!(acc.load_control_register(0, cr0 | CR0_PE | (1 << 8)), 0);
+    assert_eq!(acc.read_control_register(0), cr0 | CR0_PE);
+}
+
+#[test]
+fn enabling_paging_with_lme_activates_long_mode() {
+    let (_memory, mut acc) = make_accessor();
+    let cr0 = acc.read_control_register(0) | CR0_PE;
+    acc.write_efer(EFER_LME);
+
+    // LME without PAE cannot turn paging on.
+    assert_eq!(acc.load_control_register(0, cr0 | CR0_PG), GP);
+
+    assert_eq!(acc.load_control_register(4, CR4_PAE), 0);
+    assert_eq!(acc.load_control_register(3, 0x1000), 0);
+    assert_eq!(acc.load_control_register(0, cr0 | CR0_PG), 0);
+    assert_ne!(acc.read_efer() & EFER_LMA, 0);
+
+    // CPUID reports SMEP and SMAP; other reserved bits still fault.
+    assert_eq!(acc.load_control_register(4, CR4_PAE | CR4_SMEP | CR4_SMAP), 0);
+    assert_eq!(acc.load_control_register(4, CR4_PAE | (1 << 22)), GP);
+    assert_eq!(acc.load_control_register(4, CR4_PAE), 0);
+
+    // PAE stays on in IA-32e mode, CR3 must fit MAXPHYADDR.
+    assert_eq!(acc.load_control_register(4, 0), GP);
+    assert_eq!(acc.load_control_register(3, 1 << 40), GP);
+
+    // Paging cannot be disabled from 64-bit code, but can from compatibility mode.
+    acc.load_segment_cache(
+        SEGMENT_CS,
+        SegmentCache { selector: 0x08, attributes: 0x209B, limit: 0, base: 0 },
+    );
+    assert_eq!(acc.load_control_register(0, cr0), GP);
+    acc.load_segment_cache(
+        SEGMENT_CS,
+        SegmentCache { selector: 0x10, attributes: 0x409B, limit: 0xFFFF_FFFF, base: 0 },
+    );
+    assert_eq!(acc.load_control_register(0, cr0), 0);
+    assert_eq!(acc.read_efer() & EFER_LMA, 0);
+}
+
+#[test]
+fn pcide_requires_long_mode_and_clear_pcid() {
+    let (_memory, mut acc) = make_accessor();
+    let cr0 = acc.read_control_register(0) | CR0_PE;
+
+    assert_eq!(acc.load_control_register(4, CR4_PAE | CR4_PCIDE), GP);
+
+    acc.write_efer(EFER_LME);
+    assert_eq!(acc.load_control_register(4, CR4_PAE), 0);
+    assert_eq!(acc.load_control_register(3, 0x1005), 0);
+    assert_eq!(acc.load_control_register(0, cr0 | CR0_PG), 0);
+    assert_eq!(acc.load_control_register(4, CR4_PAE | CR4_PCIDE), GP);
+    assert_eq!(acc.load_control_register(3, 0x1000), 0);
+    assert_eq!(acc.load_control_register(4, CR4_PAE | CR4_PCIDE), 0);
+
+    // The paging depth cannot change while IA-32e mode is active.
+    assert_eq!(acc.load_control_register(4, CR4_PAE | CR4_LA57), GP);
+
+    // Bit 63 is accepted as the no-flush hint and never stored.
+    assert_eq!(acc.load_control_register(3, 0x2007 | CR3_NO_FLUSH), 0);
+    assert_eq!(acc.read_control_register(3), 0x2007);
+    assert_eq!(acc.current_pcid(), 7);
+
+    // Paging cannot be turned off with PCIDE set, even outside 64-bit code.
+    acc.load_segment_cache(
+        SEGMENT_CS,
+        SegmentCache { selector: 0x10, attributes: 0x409B, limit: 0xFFFF_FFFF, base: 0 },
+    );
+    assert_eq!(acc.load_control_register(0, cr0), GP);
+}
+
+#[test]
+fn cr8_maps_to_task_priority() {
+    let (_memory, mut acc) = make_accessor();
+
+    assert_eq!(acc.load_control_register(8, 0x1F), GP);
+    assert_eq!(acc.load_control_register(8, 0x9), 0);
+    assert_eq!(acc.task_priority(), 0x90);
+
+    acc.set_task_priority(0x4C);
+    assert_eq!(acc.read_control_register(8), 0x4);
+    assert_eq!(acc.load_control_register(5, 0), 0x06 << 16);
+}
@@ -0,0 +1,84 @@
+use crate::test_support::make_accessor;
+use crate::{DR6_BD, DR6_BS, EFLAGS_RF, EFLAGS_TF};
+
+const DB: u32 = 0x01 << 16;
+
+#[test]
+fn data_breakpoints_trap_after_the_access() {
+    let (_memory, mut acc) = make_accessor();
+
+    // DR1: 4-byte write breakpoint at 0x1002 (aligned down to 0x1000), locally enabled.
+    assert_eq!(acc.write_debug_register(1, 0x1002), 0);
+    assert_eq!(acc.write_debug_register(7, (1 << 2) | (0b1101 << 20)), 0);
+
+    // Reads do not match a write breakpoint.
+    assert_eq!(acc.debug_instruction_begin(0x500), 0);
+    assert_eq!(acc.read_memory_32(0x1000, false, false, 0xFFFF_FFFF).1, 0);
+    assert_eq!(acc.debug_instruction_end(), 0);
+
+    // An overlapping 2-byte write completes and then reports B1.
+    assert_eq!(acc.debug_instruction_begin(0x504), 0);
+    assert_eq!(acc.write_memory_16(0x0FFF, 0xABCD, false, false, 0xFFFF_FFFF), 0);
+    assert_eq!(acc.read_memory_16(0x0FFF, false, false, 0xFFFF_FFFF).0, 0xABCD);
+    assert_eq!(acc.debug_instruction_end(), DB);
+    assert_eq!(acc.read_debug_register(6).0 & 0xF, 0b0010);
+}
+
+#[test]
+fn instruction_breakpoints_fault_unless_rf_is_set() {
+    let (_memory, mut acc) = make_accessor();
+    acc.write_debug_register(0, 0x7C00);
+    acc.write_debug_register(7, 1 << 1);
+
+    assert_eq!(acc.debug_instruction_begin(0x7C00), DB);
+    assert_eq!(acc.read_debug_register(6).0 & 0xF, 0b0001);
+
+    // The handler returns with RF set: the instruction runs and RF is cleared afterwards.
+    acc.write_eflags(acc.read_eflags() | EFLAGS_RF);
+    assert_eq!(acc.debug_instruction_begin(0x7C00), 0);
+    assert_eq!(acc.debug_instruction_end(), 0);
+    assert_eq!(acc.read_eflags() & EFLAGS_RF, 0);
+}
+
+#[test]
+fn single_step_and_general_detect() {
+    let (_memory, mut acc) = make_accessor();
+
+    acc.write_eflags(acc.read_eflags() | EFLAGS_TF);
+    assert_eq!(acc.debug_instruction_begin(0x100), 0);
+    assert_eq!(acc.debug_instruction_end(), DB);
+    assert_ne!(acc.read_debug_register(6).0 & DR6_BS, 0);
+
+    // DR7.GD turns the next MOV DR into a #DB with BD set and GD cleared.
+    acc.write_debug_register(7, 1 << 13);
+    assert_eq!(acc.read_debug_register(0).1, DB);
+    assert_ne!(acc.read_debug_register(6).0 & DR6_BD, 0);
+    assert_eq!(acc.read_debug_register(7), (0x400, 0));
+
+    // DR4/DR5 alias DR6/DR7 unless CR4.DE is set.
+    assert_eq!(acc.read_debug_register(5), (0x400, 0));
+    acc.write_control_register(4, 1 << 3);
+    assert_eq!(acc.read_debug_register(5).1, 0x06 << 16);
+}
+
+#[test]
+fn instructions_need_the_bracket_only_while_armed() {
+    let (_memory, mut acc) = make_accessor();
+    assert!(!acc.debug_armed());
+
+    // Flag images from POPF/IRET hand over TF, RF and AC only.
+    acc.write_system_flags(EFLAGS_TF | 0x1);
+    assert!(acc.debug_armed());
+    assert_eq!(acc.read_eflags() & 0x1, 0);
+    assert_eq!(acc.debug_instruction_begin(0x100), 0);
+    assert_eq!(acc.finish_instruction(), DB);
+    assert_eq!(acc.instruction_count(), 1);
+
+    // A stale RF is cleared by the MOV DR7 that arms a breakpoint.
+    acc.write_system_flags(EFLAGS_RF);
+    assert!(!acc.debug_armed());
+    acc.write_debug_register(0, 0x200);
+    acc.write_debug_register(7, 1 << 1);
+    assert!(acc.debug_armed());
+    assert_eq!(acc.debug_instruction_begin(0x200), DB);
+}
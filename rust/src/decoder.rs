@@ -0,0 +1,150 @@
+//! Table-driven x86 instruction decoder.
+//!
+//! `decode` parses one instruction (prefixes, REX, opcode map, ModRM, SIB,
+//! displacement and immediates) in 16-, 32- or 64-bit mode and returns a
+//! `#[repr(C)]` `DecodedInstruction`. Per-opcode attributes live in the
+//! tables of `tables.rs`; VEX/EVEX encodings are reported as invalid.
+//!
+//! This is a decoder only. PHP still dispatches on opcode byte patterns and
+//! tracks prefix state in RuntimeCPUContext; it uses the struct just for
+//! ModRM memory operands (`x86_resolve_memory_operand` and the effective
+//! address helpers), so `x86_decode` is not part of the PHP FFI header.
+
+mod decode;
+mod ffi;
+mod tables;
+
+pub use decode::*;
+pub use ffi::*;
+
+/// Opcode maps.
+pub const MAP_LEGACY: u8 = 0;
+pub const MAP_0F: u8 = 1;
+pub const MAP_0F38: u8 = 2;
+pub const MAP_0F3A: u8 = 3;
+
+/// Decode status.
+pub const DECODE_OK: u8 = 0;
+/// The bytes ended before the instruction did.
+pub const DECODE_TRUNCATED: u8 = 1;
+/// Undefined opcode (#UD), including VEX/EVEX encodings.
+pub const DECODE_INVALID: u8 = 2;
+/// Longer than 15 bytes (#GP).
+pub const DECODE_TOO_LONG: u8 = 3;
+
+/// `DecodedInstruction::prefixes` bits.
+pub const PREFIX_OPERAND_SIZE: u8 = 1 << 0;
+pub const PREFIX_ADDRESS_SIZE: u8 = 1 << 1;
+pub const PREFIX_LOCK: u8 = 1 << 2;
+pub const PREFIX_REP: u8 = 1 << 3;
+pub const PREFIX_REPNE: u8 = 1 << 4;
+pub const PREFIX_SEGMENT: u8 = 1 << 5;
+pub const PREFIX_REX: u8 = 1 << 6;
+
+/// REX bits in `DecodedInstruction::rex`.
+pub const REX_B: u8 = 1 << 0;
+pub const REX_X: u8 = 1 << 1;
+pub const REX_R: u8 = 1 << 2;
+pub const REX_W: u8 = 1 << 3;
+
+/// `base`/`index` when absent.
+pub const REGISTER_NONE: u8 = 0xFF;
+/// `base` of RIP/EIP-relative addressing.
+pub const REGISTER_RIP: u8 = 0x10;
+
+/// Operand kinds.
+pub const OPERAND_NONE: u8 = 0;
+pub const OPERAND_REGISTER: u8 = 1;
+/// The ModRM (or moffs) memory operand described by the instruction's
+/// `segment`, `base`, `index`, `scale` and `displacement`.
+pub const OPERAND_MEMORY: u8 = 2;
+pub const OPERAND_IMMEDIATE: u8 = 3;
+/// Branch displacement, relative to the next instruction.
+pub const OPERAND_RELATIVE: u8 = 4;
+/// ptr16:16/ptr16:32 in `immediate` (offset) and `immediate2` (selector).
+pub const OPERAND_FAR_POINTER: u8 = 5;
+/// Implicit string operand: `register` is rSI (DS-relative, overridable)
+/// or rDI (ES-relative).
+pub const OPERAND_STRING: u8 = 6;
+
+/// Register classes.
+pub const REGISTER_CLASS_GPR: u8 = 0;
+/// AH, CH, DH, BH (register 0-3).
+pub const REGISTER_CLASS_HIGH_BYTE: u8 = 1;
+pub const REGISTER_CLASS_SEGMENT: u8 = 2;
+pub const REGISTER_CLASS_CONTROL: u8 = 3;
+pub const REGISTER_CLASS_DEBUG: u8 = 4;
+pub const REGISTER_CLASS_XMM: u8 = 5;
+pub const REGISTER_CLASS_MMX: u8 = 6;
+pub const REGISTER_CLASS_X87: u8 = 7;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodedOperand {
+    pub kind: u8,
+    /// `REGISTER_CLASS_*`, for register operands.
+    pub register_class: u8,
+    /// Register number (REX-extended), or rSI/rDI for string operands.
+    pub register: u8,
+    /// Size in bytes; 0 when the instruction defines it (x87, LGDT, ...).
+    pub size: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    /// Sign-extended displacement, or the moffs address.
+    pub displacement: i64,
+    /// First immediate. Sign-extended for imm8 forms that the
+    /// architecture sign-extends, branch displacements, and imm32 with a
+    /// 64-bit operand size; zero-extended otherwise.
+    pub immediate: u64,
+    /// Second immediate (ENTER's nesting level, far pointer selector).
+    pub immediate2: u64,
+    pub operands: [DecodedOperand; 4],
+    pub status: u8,
+    pub map: u8,
+    pub opcode: u8,
+    pub length: u8,
+    /// `PREFIX_*` bits.
+    pub prefixes: u8,
+    /// Low four bits of the REX prefix.
+    pub rex: u8,
+    /// 0x66, 0xF2 or 0xF3 for 0F-map instructions that use one to select
+    /// the operation, else 0.
+    pub mandatory_prefix: u8,
+    /// Effective segment of the memory operand (`SEGMENT_*`): the override,
+    /// or SS for rSP/rBP-based addressing and DS otherwise.
+    pub segment: u8,
+    /// Effective operand and address size in bits.
+    pub operand_size: u8,
+    pub address_size: u8,
+    pub has_modrm: bool,
+    pub modrm: u8,
+    pub has_sib: bool,
+    pub sib: u8,
+    /// ModRM.reg and ModRM.rm extended by REX.R/REX.B.
+    pub reg: u8,
+    pub rm: u8,
+    /// Memory operand: base and index register (`REGISTER_NONE` when
+    /// absent, `REGISTER_RIP` for RIP-relative) and scale (1, 2, 4 or 8).
+    pub base: u8,
+    pub index: u8,
+    pub scale: u8,
+    pub displacement_size: u8,
+    pub immediate_size: u8,
+    pub immediate2_size: u8,
+}
+
+impl DecodedInstruction {
+    /// ModRM.mod; 3 means the rm operand is a register.
+    #[inline(always)]
+    pub fn modrm_mod(&self) -> u8 {
+        self.modrm >> 6
+    }
+
+    /// The instruction has a ModRM memory or moffs operand.
+    pub fn has_memory_operand(&self) -> bool {
+        self.operands.iter().any(|operand| operand.kind == OPERAND_MEMORY)
+    }
+}
@@ -0,0 +1,433 @@
+use crate::memory_accessor::{SEGMENT_CS, SEGMENT_DS, SEGMENT_ES, SEGMENT_FS, SEGMENT_GS, SEGMENT_SS};
+
+use super::tables::*;
+use super::*;
+
+/// Longest legal instruction.
+const MAX_LENGTH: usize = 15;
+
+/// Decoding ended early with a `DECODE_*` status.
+type Stop = u8;
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn next(&mut self) -> Result<u8, Stop> {
+        if self.pos >= MAX_LENGTH {
+            return Err(DECODE_TOO_LONG);
+        }
+        let byte = *self.bytes.get(self.pos).ok_or(DECODE_TRUNCATED)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn peek(&self) -> Result<u8, Stop> {
+        if self.pos >= MAX_LENGTH {
+            return Err(DECODE_TOO_LONG);
+        }
+        self.bytes.get(self.pos).copied().ok_or(DECODE_TRUNCATED)
+    }
+
+    /// Little-endian value of `size` bytes.
+    fn value(&mut self, size: u8) -> Result<u64, Stop> {
+        (0..size).try_fold(0u64, |value, i| Ok(value | ((self.next()? as u64) << (i * 8))))
+    }
+}
+
+#[inline(always)]
+fn sign_extend(value: u64, size: u8) -> u64 {
+    match size {
+        1 => value as i8 as i64 as u64,
+        2 => value as i16 as i64 as u64,
+        4 => value as i32 as i64 as u64,
+        _ => value,
+    }
+}
+
+/// Decode the instruction at the start of `bytes`. `mode` is the code
+/// segment's default size in bits: 16, 32, or 64 for 64-bit mode.
+///
+/// The result's `status` says whether decoding succeeded; `length` is the
+/// number of bytes consumed so far when it did not.
+pub fn decode(bytes: &[u8], mode: u8) -> DecodedInstruction {
+    let mut d = DecodedInstruction {
+        segment: SEGMENT_DS as u8,
+        base: REGISTER_NONE,
+        index: REGISTER_NONE,
+        scale: 1,
+        ..Default::default()
+    };
+    let mut cursor = Cursor { bytes, pos: 0 };
+    if let Err(status) = decode_into(&mut d, &mut cursor, mode) {
+        d.status = status;
+    }
+    d.length = cursor.pos as u8;
+    d
+}
+
+fn decode_into(d: &mut DecodedInstruction, cursor: &mut Cursor, mode: u8) -> Result<(), Stop> {
+    let long = mode == 64;
+
+    // Legacy prefixes, then REX. A REX followed by another prefix is ignored.
+    let mut segment_override = None;
+    let mut last_repeat = 0;
+    let mut opcode = loop {
+        let byte = cursor.next()?;
+        if d.prefixes & PREFIX_REX != 0 && !(long && (0x40..=0x4F).contains(&byte)) && is_legacy_prefix(byte) {
+            d.prefixes &= !PREFIX_REX;
+            d.rex = 0;
+        }
+        match byte {
+            0x66 => d.prefixes |= PREFIX_OPERAND_SIZE,
+            0x67 => d.prefixes |= PREFIX_ADDRESS_SIZE,
+            0xF0 => d.prefixes |= PREFIX_LOCK,
+            0xF2 => {
+                d.prefixes = (d.prefixes & !PREFIX_REP) | PREFIX_REPNE;
+                last_repeat = byte;
+            }
+            0xF3 => {
+                d.prefixes = (d.prefixes & !PREFIX_REPNE) | PREFIX_REP;
+                last_repeat = byte;
+            }
+            0x26 => segment_override = Some(SEGMENT_ES),
+            0x2E => segment_override = Some(SEGMENT_CS),
+            0x36 => segment_override = Some(SEGMENT_SS),
+            0x3E => segment_override = Some(SEGMENT_DS),
+            0x64 => segment_override = Some(SEGMENT_FS),
+            0x65 => segment_override = Some(SEGMENT_GS),
+            0x40..=0x4F if long => {
+                d.prefixes |= PREFIX_REX;
+                d.rex = byte & 0x0F;
+            }
+            _ => break byte,
+        }
+    };
+    if segment_override.is_some() {
+        d.prefixes |= PREFIX_SEGMENT;
+    }
+
+    let mut map = MAP_LEGACY;
+    if opcode == 0x0F {
+        opcode = cursor.next()?;
+        map = MAP_0F;
+        if opcode == 0x38 || opcode == 0x3A {
+            map = if opcode == 0x38 { MAP_0F38 } else { MAP_0F3A };
+            opcode = cursor.next()?;
+        }
+    }
+    d.map = map;
+    d.opcode = opcode;
+    if map != MAP_LEGACY {
+        d.mandatory_prefix = match (last_repeat, d.prefixes & PREFIX_OPERAND_SIZE != 0) {
+            (0, true) => 0x66,
+            (repeat, _) => repeat,
+        };
+    }
+
+    let mut entry = match map {
+        MAP_LEGACY => LEGACY_MAP[opcode as usize],
+        MAP_0F => MAP_0F_TABLE[opcode as usize],
+        MAP_0F38 => MAP_0F38_TABLE[opcode as usize],
+        _ => MAP_0F3A_TABLE[opcode as usize],
+    };
+    if map == MAP_LEGACY {
+        match opcode {
+            0x63 if long => entry.operands = [GV, ED, NO],
+            // VEX: always in 64-bit mode, otherwise when ModRM.mod is 3.
+            0xC4 | 0xC5 if !long && cursor.peek()? >> 6 == 3 => return Err(DECODE_INVALID),
+            _ => {}
+        }
+    }
+    if map == MAP_0F38 && (opcode == 0xF0 || opcode == 0xF1) && last_repeat == 0xF2 {
+        // CRC32 Gd, Eb/Ev.
+        entry.operands = [GD, if opcode == 0xF0 { EB } else { EV }, NO];
+    }
+    if entry.flags & INVALID != 0 || (long && entry.flags & INVALID_64 != 0) {
+        return Err(DECODE_INVALID);
+    }
+
+    // ModRM and group-specific adjustments.
+    if entry.flags & MODRM != 0 {
+        d.has_modrm = true;
+        d.modrm = cursor.next()?;
+        let reg = (d.modrm >> 3) & 7;
+        if map == MAP_LEGACY {
+            match (opcode, reg) {
+                (0xF6, 0 | 1) => entry.operands[1] = IB,
+                (0xF7, 0 | 1) => entry.operands[1] = IZ,
+                (0xFE, 2..=7) | (0xFF, 7) | (0x8F, 1..=7) | (0xC6 | 0xC7, 1..=7) => return Err(DECODE_INVALID),
+                (0xFF, 2 | 4) => entry.flags |= FORCE_64,
+                (0xFF, 6) => entry.flags |= DEFAULT_64,
+                (0xFF, 3 | 5) => entry.operands[0] = M,
+                _ => {}
+            }
+        }
+    }
+    if entry.flags & MMX_FORM != 0 && d.mandatory_prefix == 0 {
+        for operand in entry.operands.iter_mut() {
+            *operand = match *operand {
+                VX => PQ,
+                WX => QQ,
+                UX => NQ,
+                other => other,
+            };
+        }
+    }
+
+    // Effective operand and address size.
+    let rex_w = d.rex & REX_W != 0;
+    // In the MMX/SSE forms 66 selects the operation rather than the size.
+    let operand_override = d.prefixes & PREFIX_OPERAND_SIZE != 0 && entry.flags & MMX_FORM == 0;
+    d.operand_size = if long {
+        if entry.flags & FORCE_64 != 0 || rex_w {
+            64
+        } else if operand_override {
+            16
+        } else if entry.flags & DEFAULT_64 != 0 {
+            64
+        } else {
+            32
+        }
+    } else {
+        match (mode, operand_override) {
+            (16, false) | (32, true) => 16,
+            _ => 32,
+        }
+    };
+    let address_override = d.prefixes & PREFIX_ADDRESS_SIZE != 0;
+    d.address_size = match (mode, address_override) {
+        (64, false) => 64,
+        (64, true) | (32, false) | (16, true) => 32,
+        _ => 16,
+    };
+
+    d.reg = ((d.modrm >> 3) & 7) | if d.rex & REX_R != 0 { 8 } else { 0 };
+    d.rm = (d.modrm & 7) | if d.rex & REX_B != 0 { 8 } else { 0 };
+    if d.has_modrm && d.modrm_mod() != 3 {
+        decode_memory(d, cursor, long)?;
+    }
+    let uses_memory = d.has_modrm && d.modrm_mod() != 3;
+    let default_segment = d.segment as usize;
+    d.segment = match segment_override {
+        // 64-bit mode ignores ES, CS, SS and DS overrides.
+        Some(segment) if !long || segment == SEGMENT_FS || segment == SEGMENT_GS => segment as u8,
+        _ => default_segment as u8,
+    };
+
+    let mut immediates = 0;
+    for (slot, &spec) in entry.operands.iter().enumerate() {
+        d.operands[slot] = decode_operand(d, cursor, spec, opcode, long, uses_memory, &mut immediates)?;
+    }
+    Ok(())
+}
+
+fn is_legacy_prefix(byte: u8) -> bool {
+    matches!(byte, 0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 | 0x66 | 0x67 | 0xF0 | 0xF2 | 0xF3)
+}
+
+/// ModRM/SIB memory addressing and displacement.
+fn decode_memory(d: &mut DecodedInstruction, cursor: &mut Cursor, long: bool) -> Result<(), Stop> {
+    if d.address_size != 16 && d.modrm & 7 == 4 {
+        d.has_sib = true;
+        d.sib = cursor.next()?;
+    }
+    resolve_memory_operand(d, long);
+
+    let raw = cursor.value(d.displacement_size)?;
+    d.displacement = sign_extend(raw, d.displacement_size) as i64;
+    Ok(())
+}
+
+/// Base, index, scale, default segment and displacement size of the memory
+/// operand given by `modrm`, `sib` (read when `has_sib`), `rex` and
+/// `address_size`. Lets callers that read the addressing bytes themselves
+/// share the decoder's rules.
+pub fn resolve_memory_operand(d: &mut DecodedInstruction, long: bool) {
+    let mode = d.modrm_mod();
+    let rm = d.modrm & 7;
+    d.base = REGISTER_NONE;
+    d.index = REGISTER_NONE;
+    d.scale = 1;
+    d.segment = SEGMENT_DS as u8;
+
+    if d.address_size == 16 {
+        const BASE: [u8; 8] = [3, 3, 5, 5, 6, 7, 5, 3];
+        const INDEX: [u8; 8] = [6, 7, 6, 7, REGISTER_NONE, REGISTER_NONE, REGISTER_NONE, REGISTER_NONE];
+        if mode == 0 && rm == 6 {
+            d.displacement_size = 2;
+        } else {
+            d.base = BASE[rm as usize];
+            d.index = INDEX[rm as usize];
+            d.displacement_size = [0, 1, 2][mode as usize];
+        }
+        if d.base == 5 {
+            d.segment = SEGMENT_SS as u8;
+        }
+    } else {
+        let rex_b = if d.rex & REX_B != 0 { 8 } else { 0 };
+        d.displacement_size = [0, 1, 4][mode as usize];
+        if rm == 4 {
+            d.scale = 1 << (d.sib >> 6);
+            let index = ((d.sib >> 3) & 7) | if d.rex & REX_X != 0 { 8 } else { 0 };
+            d.index = if index == 4 { REGISTER_NONE } else { index };
+            if d.sib & 7 == 5 && mode == 0 {
+                d.displacement_size = 4;
+            } else {
+                d.base = (d.sib & 7) | rex_b;
+            }
+        } else if rm == 5 && mode == 0 {
+            d.displacement_size = 4;
+            if long {
+                d.base = REGISTER_RIP;
+            }
+        } else {
+            d.base = rm | rex_b;
+        }
+        // Only RSP and RBP default to SS; R12 and R13 share their low bits but use DS.
+        if matches!(d.base, 4 | 5) {
+            d.segment = SEGMENT_SS as u8;
+        }
+    }
+}
+
+fn register(class: u8, register: u8, size: u8) -> DecodedOperand {
+    DecodedOperand { kind: OPERAND_REGISTER, register_class: class, register, size }
+}
+
+/// General register `number` of `size` bytes; without REX, byte registers
+/// 4-7 are AH, CH, DH and BH.
+fn gpr(d: &DecodedInstruction, number: u8, size: u8) -> DecodedOperand {
+    if size == 1 && d.prefixes & PREFIX_REX == 0 && (4..8).contains(&number) {
+        register(REGISTER_CLASS_HIGH_BYTE, number - 4, 1)
+    } else {
+        register(REGISTER_CLASS_GPR, number, size)
+    }
+}
+
+fn memory(size: u8) -> DecodedOperand {
+    DecodedOperand { kind: OPERAND_MEMORY, size, ..Default::default() }
+}
+
+fn decode_operand(
+    d: &mut DecodedInstruction,
+    cursor: &mut Cursor,
+    spec: u8,
+    opcode: u8,
+    long: bool,
+    uses_memory: bool,
+    immediates: &mut u8,
+) -> Result<DecodedOperand, Stop> {
+    let v = d.operand_size / 8;
+    let y = if long && d.rex & REX_W != 0 { 8 } else { 4 };
+    let z = if v == 2 { 2 } else { 4 };
+    let rm_or_memory = |d: &DecodedInstruction, class: u8, size: u8| {
+        if uses_memory {
+            memory(size)
+        } else if class == REGISTER_CLASS_GPR {
+            gpr(d, d.rm, size)
+        } else if class == REGISTER_CLASS_MMX || class == REGISTER_CLASS_X87 {
+            register(class, d.rm & 7, size)
+        } else {
+            register(class, d.rm, size)
+        }
+    };
+
+    let operand = match spec {
+        NO => DecodedOperand::default(),
+        EB => rm_or_memory(d, REGISTER_CLASS_GPR, 1),
+        EV => rm_or_memory(d, REGISTER_CLASS_GPR, v),
+        EW => rm_or_memory(d, REGISTER_CLASS_GPR, 2),
+        ED => rm_or_memory(d, REGISTER_CLASS_GPR, 4),
+        EY => rm_or_memory(d, REGISTER_CLASS_GPR, y),
+        E0 => rm_or_memory(d, REGISTER_CLASS_GPR, 0),
+        M if !uses_memory => return Err(DECODE_INVALID),
+        M => memory(v),
+        GB => gpr(d, d.reg, 1),
+        GV => gpr(d, d.reg, v),
+        GW => gpr(d, d.reg, 2),
+        GD => gpr(d, d.reg, 4),
+        GY => gpr(d, d.reg, y),
+        IB | IBS | IW | IZ | IV | JB | JZ => {
+            let size = match spec {
+                IB | IBS | JB => 1,
+                IW => 2,
+                IV => v,
+                _ => z,
+            };
+            let raw = cursor.value(size)?;
+            let signed = matches!(spec, IBS | JB | JZ) || (spec == IZ && v == 8);
+            let value = if signed { sign_extend(raw, size) } else { raw };
+            if *immediates == 0 {
+                d.immediate = value;
+                d.immediate_size = size;
+            } else {
+                d.immediate2 = value;
+                d.immediate2_size = size;
+            }
+            *immediates += 1;
+            let kind = if matches!(spec, JB | JZ) { OPERAND_RELATIVE } else { OPERAND_IMMEDIATE };
+            DecodedOperand { kind, size, ..Default::default() }
+        }
+        AL => register(REGISTER_CLASS_GPR, 0, 1),
+        RAX => register(REGISTER_CLASS_GPR, 0, v),
+        EAX => register(REGISTER_CLASS_GPR, 0, z),
+        DX => register(REGISTER_CLASS_GPR, 2, 2),
+        CL => register(REGISTER_CLASS_GPR, 1, 1),
+        ONE => {
+            d.immediate = 1;
+            DecodedOperand { kind: OPERAND_IMMEDIATE, size: 1, ..Default::default() }
+        }
+        ZB | ZV => {
+            let number = (opcode & 7) | if d.rex & REX_B != 0 { 8 } else { 0 };
+            gpr(d, number, if spec == ZB { 1 } else { v })
+        }
+        SW => {
+            let number = (d.modrm >> 3) & 7;
+            if number > 5 {
+                return Err(DECODE_INVALID);
+            }
+            register(REGISTER_CLASS_SEGMENT, number, 2)
+        }
+        SEG => register(REGISTER_CLASS_SEGMENT, (opcode >> 3) & 7, 2),
+        OB | OV => {
+            d.displacement_size = d.address_size / 8;
+            d.displacement = cursor.value(d.displacement_size)? as i64;
+            memory(if spec == OB { 1 } else { v })
+        }
+        AP => {
+            d.immediate = cursor.value(z)?;
+            d.immediate_size = z;
+            d.immediate2 = cursor.value(2)?;
+            d.immediate2_size = 2;
+            DecodedOperand { kind: OPERAND_FAR_POINTER, size: z + 2, ..Default::default() }
+        }
+        CD => {
+            if !matches!(d.reg, 0 | 2 | 3 | 4 | 8) {
+                return Err(DECODE_INVALID);
+            }
+            register(REGISTER_CLASS_CONTROL, d.reg, v)
+        }
+        DD => register(REGISTER_CLASS_DEBUG, d.reg, v),
+        RY => register(REGISTER_CLASS_GPR, d.rm, v),
+        VX => register(REGISTER_CLASS_XMM, d.reg, 16),
+        WX => rm_or_memory(d, REGISTER_CLASS_XMM, 16),
+        UX if uses_memory => return Err(DECODE_INVALID),
+        UX => register(REGISTER_CLASS_XMM, d.rm, 16),
+        PQ => register(REGISTER_CLASS_MMX, d.reg & 7, 8),
+        QQ => rm_or_memory(d, REGISTER_CLASS_MMX, 8),
+        NQ if uses_memory => return Err(DECODE_INVALID),
+        NQ => register(REGISTER_CLASS_MMX, d.rm & 7, 8),
+        XB | XV | YB | YV => DecodedOperand {
+            kind: OPERAND_STRING,
+            register: if matches!(spec, XB | XV) { 6 } else { 7 },
+            size: if matches!(spec, XB | YB) { 1 } else { v },
+            ..Default::default()
+        },
+        ST => rm_or_memory(d, REGISTER_CLASS_X87, 0),
+        _ => DecodedOperand::default(),
+    };
+    Ok(operand)
+}
@@ -0,0 +1,23 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::slice;
+
+use super::{decode, resolve_memory_operand, DecodedInstruction};
+
+/// Decode the instruction at `bytes[..len]` in `mode` (16, 32 or 64).
+#[no_mangle]
+pub unsafe extern "C" fn x86_decode(bytes: *const u8, len: usize, mode: u8, result: *mut DecodedInstruction) {
+    unsafe {
+        let bytes = if len == 0 { &[][..] } else { slice::from_raw_parts(bytes, len) };
+        *result = decode(bytes, mode);
+    }
+}
+
+/// Fill in the memory operand of `instruction` from its `modrm`, `sib`,
+/// `rex` and `address_size`: base, index, scale, default segment and the
+/// displacement size still to be read. `is_64bit` selects 64-bit mode,
+/// where mod=00 rm=101 is RIP-relative.
+#[no_mangle]
+pub unsafe extern "C" fn x86_resolve_memory_operand(instruction: *mut DecodedInstruction, is_64bit: bool) {
+    unsafe { resolve_memory_operand(&mut *instruction, is_64bit) }
+}
@@ -0,0 +1,488 @@
+//! Opcode attribute tables for the legacy, 0F, 0F38 and 0F3A maps.
+//!
+//! Each entry lists up to three operand specifications (the `Eb`, `Gv`,
+//! `Iz`, ... notation of the opcode maps) plus decoding flags. Entries are
+//! built in const context, block by block, following the opcode map layout.
+
+/// Operand specifications.
+pub(crate) const NO: u8 = 0;
+/// ModRM rm: register or memory, byte / operand size / word / dword /
+/// dword-or-qword (REX.W) / size defined by the instruction.
+pub(crate) const EB: u8 = 1;
+pub(crate) const EV: u8 = 2;
+pub(crate) const EW: u8 = 3;
+pub(crate) const ED: u8 = 4;
+pub(crate) const EY: u8 = 5;
+pub(crate) const E0: u8 = 6;
+/// ModRM rm, memory only, operand size.
+pub(crate) const M: u8 = 7;
+/// ModRM reg: general register of the given size.
+pub(crate) const GB: u8 = 8;
+pub(crate) const GV: u8 = 9;
+pub(crate) const GW: u8 = 10;
+pub(crate) const GD: u8 = 11;
+pub(crate) const GY: u8 = 12;
+/// Immediates: imm8, sign-extended imm8, imm16, imm16/32, imm16/32/64.
+pub(crate) const IB: u8 = 13;
+pub(crate) const IBS: u8 = 14;
+pub(crate) const IW: u8 = 15;
+pub(crate) const IZ: u8 = 16;
+pub(crate) const IV: u8 = 17;
+/// Relative branch displacements: rel8, rel16/32.
+pub(crate) const JB: u8 = 18;
+pub(crate) const JZ: u8 = 19;
+/// Fixed registers: AL, rAX, eAX (16/32 only), DX, CL and the constant 1.
+pub(crate) const AL: u8 = 20;
+pub(crate) const RAX: u8 = 21;
+pub(crate) const EAX: u8 = 22;
+pub(crate) const DX: u8 = 23;
+pub(crate) const CL: u8 = 24;
+pub(crate) const ONE: u8 = 25;
+/// Register in the low three opcode bits (REX.B-extended): byte / operand size.
+pub(crate) const ZB: u8 = 26;
+pub(crate) const ZV: u8 = 27;
+/// Segment register from ModRM.reg / from opcode bits 5:3.
+pub(crate) const SW: u8 = 28;
+pub(crate) const SEG: u8 = 29;
+/// moffs8 / moffs16/32/64.
+pub(crate) const OB: u8 = 30;
+pub(crate) const OV: u8 = 31;
+/// ptr16:16/ptr16:32.
+pub(crate) const AP: u8 = 32;
+/// Control / debug register from ModRM.reg; GPR from ModRM.rm (MOV CR/DR).
+pub(crate) const CD: u8 = 33;
+pub(crate) const DD: u8 = 34;
+pub(crate) const RY: u8 = 35;
+/// XMM from ModRM.reg / rm-or-memory / rm register.
+pub(crate) const VX: u8 = 36;
+pub(crate) const WX: u8 = 37;
+pub(crate) const UX: u8 = 38;
+/// MMX from ModRM.reg / rm-or-memory / rm register.
+pub(crate) const PQ: u8 = 39;
+pub(crate) const QQ: u8 = 40;
+pub(crate) const NQ: u8 = 41;
+/// String operands DS:rSI and ES:rDI, byte / operand size.
+pub(crate) const XB: u8 = 42;
+pub(crate) const XV: u8 = 43;
+pub(crate) const YB: u8 = 44;
+pub(crate) const YV: u8 = 45;
+/// x87: ST(i) from ModRM.rm, or memory.
+pub(crate) const ST: u8 = 46;
+
+/// Entry flags.
+pub(crate) const MODRM: u8 = 1 << 0;
+pub(crate) const INVALID: u8 = 1 << 1;
+/// Undefined in 64-bit mode.
+pub(crate) const INVALID_64: u8 = 1 << 2;
+/// 64-bit operand size by default in 64-bit mode (0x66 selects 16).
+pub(crate) const DEFAULT_64: u8 = 1 << 3;
+/// 64-bit operand size in 64-bit mode regardless of prefixes.
+pub(crate) const FORCE_64: u8 = 1 << 4;
+/// XMM operands name MMX registers when there is no mandatory prefix.
+pub(crate) const MMX_FORM: u8 = 1 << 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct OpcodeEntry {
+    pub(crate) flags: u8,
+    pub(crate) operands: [u8; 3],
+}
+
+const fn op(flags: u8, operands: [u8; 3]) -> OpcodeEntry {
+    OpcodeEntry { flags, operands }
+}
+
+const NONE: OpcodeEntry = op(0, [NO, NO, NO]);
+const UNDEFINED: OpcodeEntry = op(INVALID, [NO, NO, NO]);
+
+pub(crate) static LEGACY_MAP: [OpcodeEntry; 256] = {
+    let mut t = [NONE; 256];
+
+    // ADD, OR, ADC, SBB, AND, SUB, XOR, CMP.
+    let mut base = 0;
+    while base < 0x40 {
+        t[base] = op(MODRM, [EB, GB, NO]);
+        t[base + 1] = op(MODRM, [EV, GV, NO]);
+        t[base + 2] = op(MODRM, [GB, EB, NO]);
+        t[base + 3] = op(MODRM, [GV, EV, NO]);
+        t[base + 4] = op(0, [AL, IB, NO]);
+        t[base + 5] = op(0, [RAX, IZ, NO]);
+        base += 8;
+    }
+    // PUSH/POP ES, CS, SS, DS; DAA, DAS, AAA, AAS. 0F, 26, 2E, 36 and 3E
+    // are consumed before the table is consulted.
+    t[0x06] = op(INVALID_64, [SEG, NO, NO]);
+    t[0x07] = op(INVALID_64, [SEG, NO, NO]);
+    t[0x0E] = op(INVALID_64, [SEG, NO, NO]);
+    t[0x16] = op(INVALID_64, [SEG, NO, NO]);
+    t[0x17] = op(INVALID_64, [SEG, NO, NO]);
+    t[0x1E] = op(INVALID_64, [SEG, NO, NO]);
+    t[0x1F] = op(INVALID_64, [SEG, NO, NO]);
+    t[0x27] = op(INVALID_64, [NO, NO, NO]);
+    t[0x2F] = op(INVALID_64, [NO, NO, NO]);
+    t[0x37] = op(INVALID_64, [NO, NO, NO]);
+    t[0x3F] = op(INVALID_64, [NO, NO, NO]);
+
+    let mut i = 0;
+    while i < 8 {
+        // INC/DEC r (REX in 64-bit mode), PUSH/POP r, XCHG r,rAX, MOV r,imm.
+        t[0x40 + i] = op(0, [ZV, NO, NO]);
+        t[0x48 + i] = op(0, [ZV, NO, NO]);
+        t[0x50 + i] = op(DEFAULT_64, [ZV, NO, NO]);
+        t[0x58 + i] = op(DEFAULT_64, [ZV, NO, NO]);
+        t[0x90 + i] = op(0, [ZV, RAX, NO]);
+        t[0xB0 + i] = op(0, [ZB, IB, NO]);
+        t[0xB8 + i] = op(0, [ZV, IV, NO]);
+        // x87 escapes.
+        t[0xD8 + i] = op(MODRM, [ST, NO, NO]);
+        i += 1;
+    }
+    i = 0;
+    while i < 16 {
+        t[0x70 + i] = op(FORCE_64, [JB, NO, NO]);
+        i += 1;
+    }
+
+    t[0x60] = op(INVALID_64, [NO, NO, NO]);
+    t[0x61] = op(INVALID_64, [NO, NO, NO]);
+    t[0x62] = op(MODRM | INVALID_64, [GV, M, NO]);
+    // ARPL; MOVSXD Gv,Ed in 64-bit mode.
+    t[0x63] = op(MODRM, [EW, GW, NO]);
+    t[0x68] = op(DEFAULT_64, [IZ, NO, NO]);
+    t[0x69] = op(MODRM, [GV, EV, IZ]);
+    t[0x6A] = op(DEFAULT_64, [IBS, NO, NO]);
+    t[0x6B] = op(MODRM, [GV, EV, IBS]);
+    t[0x6C] = op(0, [YB, DX, NO]);
+    t[0x6D] = op(0, [YV, DX, NO]);
+    t[0x6E] = op(0, [DX, XB, NO]);
+    t[0x6F] = op(0, [DX, XV, NO]);
+
+    // Group 1, TEST, XCHG, MOV, LEA, POP Ev.
+    t[0x80] = op(MODRM, [EB, IB, NO]);
+    t[0x81] = op(MODRM, [EV, IZ, NO]);
+    t[0x82] = op(MODRM | INVALID_64, [EB, IB, NO]);
+    t[0x83] = op(MODRM, [EV, IBS, NO]);
+    t[0x84] = op(MODRM, [EB, GB, NO]);
+    t[0x85] = op(MODRM, [EV, GV, NO]);
+    t[0x86] = op(MODRM, [EB, GB, NO]);
+    t[0x87] = op(MODRM, [EV, GV, NO]);
+    t[0x88] = op(MODRM, [EB, GB, NO]);
+    t[0x89] = op(MODRM, [EV, GV, NO]);
+    t[0x8A] = op(MODRM, [GB, EB, NO]);
+    t[0x8B] = op(MODRM, [GV, EV, NO]);
+    t[0x8C] = op(MODRM, [EV, SW, NO]);
+    t[0x8D] = op(MODRM, [GV, M, NO]);
+    t[0x8E] = op(MODRM, [SW, EW, NO]);
+    t[0x8F] = op(MODRM | DEFAULT_64, [EV, NO, NO]);
+
+    t[0x9A] = op(INVALID_64, [AP, NO, NO]);
+    t[0x9C] = op(DEFAULT_64, [NO, NO, NO]);
+    t[0x9D] = op(DEFAULT_64, [NO, NO, NO]);
+
+    t[0xA0] = op(0, [AL, OB, NO]);
+    t[0xA1] = op(0, [RAX, OV, NO]);
+    t[0xA2] = op(0, [OB, AL, NO]);
+    t[0xA3] = op(0, [OV, RAX, NO]);
+    t[0xA4] = op(0, [YB, XB, NO]);
+    t[0xA5] = op(0, [YV, XV, NO]);
+    t[0xA6] = op(0, [XB, YB, NO]);
+    t[0xA7] = op(0, [XV, YV, NO]);
+    t[0xA8] = op(0, [AL, IB, NO]);
+    t[0xA9] = op(0, [RAX, IZ, NO]);
+    t[0xAA] = op(0, [YB, AL, NO]);
+    t[0xAB] = op(0, [YV, RAX, NO]);
+    t[0xAC] = op(0, [AL, XB, NO]);
+    t[0xAD] = op(0, [RAX, XV, NO]);
+    t[0xAE] = op(0, [AL, YB, NO]);
+    t[0xAF] = op(0, [RAX, YV, NO]);
+
+    // Group 2, RET, LES/LDS (VEX in 64-bit mode), group 11, ENTER/LEAVE,
+    // far RET, INT.
+    t[0xC0] = op(MODRM, [EB, IB, NO]);
+    t[0xC1] = op(MODRM, [EV, IB, NO]);
+    t[0xC2] = op(FORCE_64, [IW, NO, NO]);
+    t[0xC3] = op(FORCE_64, [NO, NO, NO]);
+    t[0xC4] = op(MODRM | INVALID_64, [GV, M, NO]);
+    t[0xC5] = op(MODRM | INVALID_64, [GV, M, NO]);
+    t[0xC6] = op(MODRM, [EB, IB, NO]);
+    t[0xC7] = op(MODRM, [EV, IZ, NO]);
+    t[0xC8] = op(DEFAULT_64, [IW, IB, NO]);
+    t[0xC9] = op(DEFAULT_64, [NO, NO, NO]);
+    t[0xCA] = op(0, [IW, NO, NO]);
+    t[0xCD] = op(0, [IB, NO, NO]);
+    t[0xCE] = op(INVALID_64, [NO, NO, NO]);
+
+    t[0xD0] = op(MODRM, [EB, ONE, NO]);
+    t[0xD1] = op(MODRM, [EV, ONE, NO]);
+    t[0xD2] = op(MODRM, [EB, CL, NO]);
+    t[0xD3] = op(MODRM, [EV, CL, NO]);
+    t[0xD4] = op(INVALID_64, [IB, NO, NO]);
+    t[0xD5] = op(INVALID_64, [IB, NO, NO]);
+    t[0xD6] = op(INVALID_64, [NO, NO, NO]);
+
+    // LOOPcc/JrCXZ, IN/OUT, CALL/JMP.
+    t[0xE0] = op(FORCE_64, [JB, NO, NO]);
+    t[0xE1] = op(FORCE_64, [JB, NO, NO]);
+    t[0xE2] = op(FORCE_64, [JB, NO, NO]);
+    t[0xE3] = op(FORCE_64, [JB, NO, NO]);
+    t[0xE4] = op(0, [AL, IB, NO]);
+    t[0xE5] = op(0, [EAX, IB, NO]);
+    t[0xE6] = op(0, [IB, AL, NO]);
+    t[0xE7] = op(0, [IB, EAX, NO]);
+    t[0xE8] = op(FORCE_64, [JZ, NO, NO]);
+    t[0xE9] = op(FORCE_64, [JZ, NO, NO]);
+    t[0xEA] = op(INVALID_64, [AP, NO, NO]);
+    t[0xEB] = op(FORCE_64, [JB, NO, NO]);
+    t[0xEC] = op(0, [AL, DX, NO]);
+    t[0xED] = op(0, [EAX, DX, NO]);
+    t[0xEE] = op(0, [DX, AL, NO]);
+    t[0xEF] = op(0, [DX, EAX, NO]);
+
+    // Groups 3, 4 and 5; TEST's immediate is added for /0 and /1.
+    t[0xF6] = op(MODRM, [EB, NO, NO]);
+    t[0xF7] = op(MODRM, [EV, NO, NO]);
+    t[0xFE] = op(MODRM, [EB, NO, NO]);
+    t[0xFF] = op(MODRM, [EV, NO, NO]);
+    t
+};
+
+pub(crate) static MAP_0F_TABLE: [OpcodeEntry; 256] = {
+    let mut t = [UNDEFINED; 256];
+
+    // Groups 6 and 7, LAR/LSL, system instructions.
+    t[0x00] = op(MODRM, [EW, NO, NO]);
+    t[0x01] = op(MODRM, [E0, NO, NO]);
+    t[0x02] = op(MODRM, [GV, EW, NO]);
+    t[0x03] = op(MODRM, [GV, EW, NO]);
+    t[0x05] = NONE;
+    t[0x06] = NONE;
+    t[0x07] = NONE;
+    t[0x08] = NONE;
+    t[0x09] = NONE;
+    t[0x0B] = NONE;
+    t[0x0D] = op(MODRM, [E0, NO, NO]);
+
+    // SSE moves, prefetch and hint NOPs.
+    let mut i = 0x10;
+    while i < 0x18 {
+        t[i] = op(MODRM, [VX, WX, NO]);
+        i += 1;
+    }
+    t[0x11] = op(MODRM, [WX, VX, NO]);
+    t[0x13] = op(MODRM, [WX, VX, NO]);
+    t[0x17] = op(MODRM, [WX, VX, NO]);
+    i = 0x18;
+    while i < 0x20 {
+        t[i] = op(MODRM, [EV, NO, NO]);
+        i += 1;
+    }
+
+    // MOV to/from CR and DR.
+    t[0x20] = op(MODRM | FORCE_64, [RY, CD, NO]);
+    t[0x21] = op(MODRM | FORCE_64, [RY, DD, NO]);
+    t[0x22] = op(MODRM | FORCE_64, [CD, RY, NO]);
+    t[0x23] = op(MODRM | FORCE_64, [DD, RY, NO]);
+
+    i = 0x28;
+    while i < 0x30 {
+        t[i] = op(MODRM, [VX, WX, NO]);
+        i += 1;
+    }
+    t[0x29] = op(MODRM, [WX, VX, NO]);
+    t[0x2B] = op(MODRM, [M, VX, NO]);
+
+    // WRMSR, RDTSC, RDMSR, RDPMC, SYSENTER, SYSEXIT, GETSEC.
+    i = 0x30;
+    while i < 0x38 {
+        t[i] = NONE;
+        i += 1;
+    }
+    t[0x36] = UNDEFINED;
+
+    i = 0;
+    while i < 16 {
+        // CMOVcc, Jcc rel16/32, SETcc.
+        t[0x40 + i] = op(MODRM, [GV, EV, NO]);
+        t[0x80 + i] = op(FORCE_64, [JZ, NO, NO]);
+        t[0x90 + i] = op(MODRM, [EB, NO, NO]);
+        i += 1;
+    }
+
+    // SSE arithmetic, then MMX/SSE2 integer operations.
+    i = 0x50;
+    while i < 0x60 {
+        t[i] = op(MODRM, [VX, WX, NO]);
+        i += 1;
+    }
+    t[0x50] = op(MODRM, [GD, UX, NO]);
+    i = 0x60;
+    while i < 0x70 {
+        t[i] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+        i += 1;
+    }
+    t[0x6E] = op(MODRM | MMX_FORM, [VX, EY, NO]);
+    t[0x70] = op(MODRM | MMX_FORM, [VX, WX, IB]);
+    t[0x71] = op(MODRM | MMX_FORM, [UX, IB, NO]);
+    t[0x72] = op(MODRM | MMX_FORM, [UX, IB, NO]);
+    t[0x73] = op(MODRM | MMX_FORM, [UX, IB, NO]);
+    t[0x74] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+    t[0x75] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+    t[0x76] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+    t[0x77] = NONE;
+    t[0x78] = op(MODRM, [EY, GY, NO]);
+    t[0x79] = op(MODRM, [GY, EY, NO]);
+    t[0x7C] = op(MODRM, [VX, WX, NO]);
+    t[0x7D] = op(MODRM, [VX, WX, NO]);
+    t[0x7E] = op(MODRM | MMX_FORM, [EY, VX, NO]);
+    t[0x7F] = op(MODRM | MMX_FORM, [WX, VX, NO]);
+
+    // PUSH/POP FS/GS, CPUID, bit tests, double shifts, IMUL.
+    t[0xA0] = op(DEFAULT_64, [SEG, NO, NO]);
+    t[0xA1] = op(DEFAULT_64, [SEG, NO, NO]);
+    t[0xA2] = NONE;
+    t[0xA3] = op(MODRM, [EV, GV, NO]);
+    t[0xA4] = op(MODRM, [EV, GV, IB]);
+    t[0xA5] = op(MODRM, [EV, GV, CL]);
+    t[0xA8] = op(DEFAULT_64, [SEG, NO, NO]);
+    t[0xA9] = op(DEFAULT_64, [SEG, NO, NO]);
+    t[0xAA] = NONE;
+    t[0xAB] = op(MODRM, [EV, GV, NO]);
+    t[0xAC] = op(MODRM, [EV, GV, IB]);
+    t[0xAD] = op(MODRM, [EV, GV, CL]);
+    t[0xAE] = op(MODRM, [E0, NO, NO]);
+    t[0xAF] = op(MODRM, [GV, EV, NO]);
+
+    // CMPXCHG, LSS/LFS/LGS, BTR/BTC, MOVZX/MOVSX, POPCNT, UD1, group 8,
+    // BSF/BSR (TZCNT/LZCNT).
+    t[0xB0] = op(MODRM, [EB, GB, NO]);
+    t[0xB1] = op(MODRM, [EV, GV, NO]);
+    t[0xB2] = op(MODRM, [GV, M, NO]);
+    t[0xB3] = op(MODRM, [EV, GV, NO]);
+    t[0xB4] = op(MODRM, [GV, M, NO]);
+    t[0xB5] = op(MODRM, [GV, M, NO]);
+    t[0xB6] = op(MODRM, [GV, EB, NO]);
+    t[0xB7] = op(MODRM, [GV, EW, NO]);
+    t[0xB8] = op(MODRM, [GV, EV, NO]);
+    t[0xB9] = op(MODRM, [GV, EV, NO]);
+    t[0xBA] = op(MODRM, [EV, IB, NO]);
+    t[0xBB] = op(MODRM, [EV, GV, NO]);
+    t[0xBC] = op(MODRM, [GV, EV, NO]);
+    t[0xBD] = op(MODRM, [GV, EV, NO]);
+    t[0xBE] = op(MODRM, [GV, EB, NO]);
+    t[0xBF] = op(MODRM, [GV, EW, NO]);
+
+    // XADD, SSE compares/shuffles, MOVNTI, PINSRW/PEXTRW, group 9, BSWAP.
+    t[0xC0] = op(MODRM, [EB, GB, NO]);
+    t[0xC1] = op(MODRM, [EV, GV, NO]);
+    t[0xC2] = op(MODRM, [VX, WX, IB]);
+    t[0xC3] = op(MODRM, [M, GY, NO]);
+    t[0xC4] = op(MODRM | MMX_FORM, [VX, ED, IB]);
+    t[0xC5] = op(MODRM | MMX_FORM, [GD, UX, IB]);
+    t[0xC6] = op(MODRM, [VX, WX, IB]);
+    t[0xC7] = op(MODRM, [E0, NO, NO]);
+    i = 0;
+    while i < 8 {
+        t[0xC8 + i] = op(0, [ZV, NO, NO]);
+        i += 1;
+    }
+
+    // MMX/SSE2 integer operations.
+    i = 0xD0;
+    while i < 0x100 {
+        t[i] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+        i += 1;
+    }
+    t[0xD0] = op(MODRM, [VX, WX, NO]);
+    t[0xD6] = op(MODRM, [WX, VX, NO]);
+    t[0xD7] = op(MODRM | MMX_FORM, [GD, UX, NO]);
+    t[0xE6] = op(MODRM, [VX, WX, NO]);
+    t[0xE7] = op(MODRM | MMX_FORM, [M, VX, NO]);
+    t[0xF0] = op(MODRM, [VX, M, NO]);
+    t[0xF7] = op(MODRM | MMX_FORM, [VX, UX, NO]);
+    // UD0.
+    t[0xFF] = op(MODRM, [GV, EV, NO]);
+    t
+};
+
+pub(crate) static MAP_0F38_TABLE: [OpcodeEntry; 256] = {
+    let mut t = [UNDEFINED; 256];
+    let sse = op(MODRM, [VX, WX, NO]);
+
+    // SSSE3 (with MMX forms), then SSE4.1/4.2.
+    let mut i = 0x00;
+    while i < 0x0C {
+        t[i] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+        i += 1;
+    }
+    t[0x1C] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+    t[0x1D] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+    t[0x1E] = op(MODRM | MMX_FORM, [VX, WX, NO]);
+    t[0x10] = sse;
+    t[0x14] = sse;
+    t[0x15] = sse;
+    t[0x17] = sse;
+    i = 0x20;
+    while i < 0x42 {
+        t[i] = sse;
+        i += 1;
+    }
+    t[0x26] = UNDEFINED;
+    t[0x27] = UNDEFINED;
+    t[0x2C] = UNDEFINED;
+    t[0x2D] = UNDEFINED;
+    t[0x2E] = UNDEFINED;
+    t[0x2F] = UNDEFINED;
+    t[0x36] = UNDEFINED;
+    t[0x2A] = op(MODRM, [VX, M, NO]);
+
+    // INVEPT/INVVPID/INVPCID, SHA, AES.
+    t[0x80] = op(MODRM | FORCE_64, [GY, M, NO]);
+    t[0x81] = op(MODRM | FORCE_64, [GY, M, NO]);
+    t[0x82] = op(MODRM | FORCE_64, [GY, M, NO]);
+    i = 0xC8;
+    while i < 0xCE {
+        t[i] = sse;
+        i += 1;
+    }
+    i = 0xDB;
+    while i < 0xE0 {
+        t[i] = sse;
+        i += 1;
+    }
+
+    // MOVBE (CRC32 with F2), ADCX/ADOX.
+    t[0xF0] = op(MODRM, [GV, M, NO]);
+    t[0xF1] = op(MODRM, [M, GV, NO]);
+    t[0xF6] = op(MODRM, [GY, EY, NO]);
+    t
+};
+
+pub(crate) static MAP_0F3A_TABLE: [OpcodeEntry; 256] = {
+    let mut t = [UNDEFINED; 256];
+    let sse = op(MODRM, [VX, WX, IB]);
+
+    let mut i = 0x08;
+    while i < 0x10 {
+        t[i] = sse;
+        i += 1;
+    }
+    t[0x0F] = op(MODRM | MMX_FORM, [VX, WX, IB]);
+    t[0x14] = op(MODRM, [ED, VX, IB]);
+    t[0x15] = op(MODRM, [ED, VX, IB]);
+    t[0x16] = op(MODRM, [EY, VX, IB]);
+    t[0x17] = op(MODRM, [ED, VX, IB]);
+    t[0x20] = op(MODRM, [VX, ED, IB]);
+    t[0x21] = sse;
+    t[0x22] = op(MODRM, [VX, EY, IB]);
+    t[0x40] = sse;
+    t[0x41] = sse;
+    t[0x42] = sse;
+    t[0x44] = sse;
+    i = 0x60;
+    while i < 0x64 {
+        t[i] = sse;
+        i += 1;
+    }
+    t[0xCC] = sse;
+    t[0xDF] = sse;
+    t
+};
@@ -0,0 +1,119 @@
+use crate::decoder::*;
+use crate::memory_accessor::{SEGMENT_DS, SEGMENT_FS, SEGMENT_SS};
+
+#[test]
+fn decode_prefixes_and_addressing() {
+    // lock add qword [fs:r8 + r9*4 + 0x10], rdx
+    let d = decode(&[0xF0, 0x64, 0x4B, 0x01, 0x54, 0x88, 0x10], 64);
+    assert_eq!(d.status, DECODE_OK);
+    assert_eq!(d.length, 7);
+    assert_eq!(d.opcode, 0x01);
+    assert_eq!(d.prefixes, PREFIX_LOCK | PREFIX_SEGMENT | PREFIX_REX);
+    assert_eq!(d.rex, REX_W | REX_X | REX_B);
+    assert_eq!(d.operand_size, 64);
+    assert_eq!(d.address_size, 64);
+    assert!(d.has_sib);
+    assert_eq!((d.base, d.index, d.scale, d.displacement), (8, 9, 4, 0x10));
+    assert_eq!(d.segment, SEGMENT_FS as u8);
+    assert_eq!(d.operands[0], DecodedOperand { kind: OPERAND_MEMORY, size: 8, ..Default::default() });
+    assert_eq!(d.operands[1].register, 2);
+
+    // mov eax, [rip - 8]: 64-bit mode ignores the DS override.
+    let d = decode(&[0x3E, 0x8B, 0x05, 0xF8, 0xFF, 0xFF, 0xFF], 64);
+    assert_eq!(d.status, DECODE_OK);
+    assert_eq!((d.base, d.displacement, d.operand_size), (REGISTER_RIP, -8, 32));
+    assert_eq!(d.segment, SEGMENT_DS as u8);
+
+    // [rbp + 8] defaults to SS, [r13 + 8] and [r12] to DS.
+    assert_eq!(decode(&[0x8B, 0x45, 0x08], 64).segment, SEGMENT_SS as u8);
+    assert_eq!(decode(&[0x41, 0x8B, 0x45, 0x08], 64).segment, SEGMENT_DS as u8);
+    assert_eq!(decode(&[0x41, 0x8B, 0x04, 0x24], 64).segment, SEGMENT_DS as u8);
+
+    // mov ax, [bp + di - 2] in 16-bit mode defaults to SS.
+    let d = decode(&[0x8B, 0x43, 0xFE], 16);
+    assert_eq!((d.base, d.index, d.displacement, d.operand_size), (5, 7, -2, 16));
+    assert_eq!(d.segment, SEGMENT_SS as u8);
+
+    // 67 switches 32-bit code to 16-bit addressing: mov eax, [0x1234].
+    let d = decode(&[0x67, 0x8B, 0x06, 0x34, 0x12], 32);
+    assert_eq!((d.address_size, d.base, d.displacement_size, d.displacement), (16, REGISTER_NONE, 2, 0x1234));
+
+    // A legacy prefix after REX discards it: 48 66 89 c8 is mov ax, cx.
+    let d = decode(&[0x48, 0x66, 0x89, 0xC8], 64);
+    assert_eq!((d.rex, d.operand_size), (0, 16));
+
+    // Without REX, byte register 4 is AH; with it, SPL.
+    assert_eq!(decode(&[0x88, 0xE0], 64).operands[1].register_class, REGISTER_CLASS_HIGH_BYTE);
+    assert_eq!(decode(&[0x40, 0x88, 0xE0], 64).operands[1], DecodedOperand { kind: OPERAND_REGISTER, register: 4, size: 1, ..Default::default() });
+}
+
+#[test]
+fn decode_immediates_and_maps() {
+    // mov rax, imm64
+    let d = decode(&[0x48, 0xB8, 1, 2, 3, 4, 5, 6, 7, 8], 64);
+    assert_eq!((d.immediate, d.immediate_size, d.length), (0x0807_0605_0403_0201, 8, 10));
+
+    // add rax, -1 (imm32 sign-extended to 64 bits)
+    let d = decode(&[0x48, 0x05, 0xFF, 0xFF, 0xFF, 0xFF], 64);
+    assert_eq!(d.immediate, u64::MAX);
+
+    // enter 0x10, 2
+    let d = decode(&[0xC8, 0x10, 0x00, 0x02], 32);
+    assert_eq!((d.immediate, d.immediate2), (0x10, 2));
+
+    // jmp far 0x0008:0x00001000
+    let d = decode(&[0xEA, 0x00, 0x10, 0x00, 0x00, 0x08, 0x00], 32);
+    assert_eq!(d.operands[0].kind, OPERAND_FAR_POINTER);
+    assert_eq!((d.immediate, d.immediate2), (0x1000, 8));
+
+    // mov cr3, rax
+    let d = decode(&[0x0F, 0x22, 0xD8], 64);
+    assert_eq!((d.map, d.operands[0].register_class, d.operands[0].register), (MAP_0F, REGISTER_CLASS_CONTROL, 3));
+
+    // crc32 eax, byte [rcx]
+    let d = decode(&[0xF2, 0x0F, 0x38, 0xF0, 0x01], 64);
+    assert_eq!((d.status, d.map, d.mandatory_prefix), (DECODE_OK, MAP_0F38, 0xF2));
+    assert_eq!(d.operands[1].size, 1);
+
+    // movzx ax, byte [rcx]: 66 still sets the size outside the SSE forms.
+    let d = decode(&[0x66, 0x0F, 0xB6, 0x01], 64);
+    assert_eq!((d.mandatory_prefix, d.operand_size), (0x66, 16));
+
+    // push rbp/call [rax] default to 64-bit operands.
+    assert_eq!(decode(&[0x55], 64).operand_size, 64);
+    assert_eq!(decode(&[0xFF, 0x10], 64).operand_size, 64);
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(decode(&[0x06], 64).status, DECODE_INVALID);
+    assert_eq!(decode(&[0x06], 32).status, DECODE_OK);
+    assert_eq!(decode(&[0x0F, 0x04], 32).status, DECODE_INVALID);
+    assert_eq!(decode(&[0xC5, 0xF8, 0x77], 64).status, DECODE_INVALID);
+    assert_eq!(decode(&[0x8D, 0xC0], 32).status, DECODE_INVALID);
+    assert_eq!(decode(&[0x8B, 0x80, 0x00], 32).status, DECODE_TRUNCATED);
+    assert_eq!(decode(&[], 32).status, DECODE_TRUNCATED);
+    let d = decode(&[0x66; 16], 32);
+    assert_eq!((d.status, d.length), (DECODE_TOO_LONG, 15));
+}
+
+#[test]
+fn resolved_memory_operand_matches_the_decoder() {
+    // mov eax, [r12 + rcx*2 + 8] and the 16-bit mov ax, [bp + 2].
+    for (bytes, mode, long) in [(&[0x41, 0x8B, 0x44, 0x4C, 0x08][..], 64, true), (&[0x8B, 0x46, 0x02][..], 16, false)] {
+        let decoded = decode(bytes, mode);
+        let mut d = DecodedInstruction {
+            modrm: decoded.modrm,
+            has_sib: decoded.has_sib,
+            sib: decoded.sib,
+            rex: decoded.rex,
+            address_size: decoded.address_size,
+            ..Default::default()
+        };
+        resolve_memory_operand(&mut d, long);
+        assert_eq!(
+            (d.base, d.index, d.scale, d.segment, d.displacement_size),
+            (decoded.base, decoded.index, decoded.scale, decoded.segment, decoded.displacement_size)
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use crate::test_support::{enable_ia32e_paging, make_accessor};
+use crate::{MemoryAccessor, MemoryStream, MAX_INSTRUCTION_LENGTH};
+
+/// Linear 0x5000 -> 0x9000, 0x6000 -> 0x7000, 0x7000 -> 0x8000 (XD).
+fn map_code(memory: &mut MemoryStream, acc: &mut MemoryAccessor) {
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(0x4000 + 5 * 8, 0x9000 | flags);
+    memory.write_qword_at(0x4000 + 6 * 8, 0x7000 | flags);
+    memory.write_qword_at(0x4000 + 7 * 8, (1 << 63) | 0x8000 | flags);
+    enable_ia32e_paging(memory, acc);
+}
+
+#[test]
+fn fetch_window_crosses_pages_and_clamps_to_fifteen_bytes() {
+    let (mut memory, mut acc) = make_accessor();
+    map_code(&mut memory, &mut acc);
+    for i in 0..8u8 {
+        memory.write_byte_at(0x9FF8 + i as usize, 0xA0 + i);
+        memory.write_byte_at(0x7000 + i as usize, 0xB0 + i);
+    }
+    let mask = 0x0000_FFFF_FFFF_FFFF;
+
+    let window = acc.fetch_code(0x5FFC, 32, false, true, mask);
+    assert_eq!((window.length as usize, window.fault), (MAX_INSTRUCTION_LENGTH, 0));
+    assert_eq!(window.bytes[..6], [0xA4, 0xA5, 0xA6, 0xA7, 0xB0, 0xB1]);
+    assert_eq!(window.bytes[14], 0);
+    assert!(!acc.instruction_fetch());
+
+    let window = acc.fetch_code(0x5FFE, 3, false, true, mask);
+    assert_eq!((window.length, window.bytes[..3].to_vec()), (3, vec![0xA6, 0xA7, 0xB0]));
+}
+
+#[test]
+fn fetch_window_stops_at_non_executable_page_without_touching_cr2() {
+    let (mut memory, mut acc) = make_accessor();
+    map_code(&mut memory, &mut acc);
+    memory.write_dword_at(0x7FFE, 0x0F0B_9090);
+    let mask = 0x0000_FFFF_FFFF_FFFF;
+    acc.write_control_register(2, 0x1234);
+
+    // The XD page ends the window after two bytes; no fault is raised yet.
+    let window = acc.fetch_code(0x6FFE, 15, false, true, mask);
+    assert_eq!((window.length, window.fault), (2, (0x0E << 16) | 0x11));
+    assert_eq!(window.bytes[..2], [0x90, 0x90]);
+    assert_eq!(acc.read_control_register(2), 0x1234);
+
+    // Fetching from the faulting byte raises it.
+    let window = acc.fetch_code(0x7000, 15, false, true, mask);
+    assert_eq!((window.length, window.fault), (0, (0x0E << 16) | 0x11));
+    assert_eq!(acc.read_control_register(2), 0x7000);
+}
@@ -1,10 +1,40 @@
+mod decoder;
 mod memory_stream;
 mod memory_accessor;
 mod uint64;
 
+pub use decoder::*;
 pub use memory_stream::*;
 pub use memory_accessor::*;
 pub use uint64::*;
 
+#[cfg(test)]
+mod test_support;
 #[cfg(test)]
 mod paging_tests;
+#[cfg(test)]
+mod segment_tests;
+#[cfg(test)]
+mod task_tests;
+#[cfg(test)]
+mod msr_tests;
+#[cfg(test)]
+mod debug_tests;
+#[cfg(test)]
+mod control_tests;
+#[cfg(test)]
+mod walk_tests;
+#[cfg(test)]
+mod state_tests;
+#[cfg(test)]
+mod save_state_tests;
+#[cfg(test)]
+mod replay_tests;
+#[cfg(test)]
+mod trace_tests;
+#[cfg(test)]
+mod fetch_tests;
+#[cfg(test)]
+mod decoder_tests;
+#[cfg(test)]
+mod address_tests;
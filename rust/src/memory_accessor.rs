@@ -4,6 +4,11 @@
 //! CPU registers, flags, and memory access for x86 emulation.
 
 use crate::memory_stream::MemoryStream;
+use debug::DebugRegisters;
+use msr::MsrFile;
+use replay::ReplayLog;
+use tlb::Tlb;
+use trace::MemoryTracer;
 
 /// Register addresses layout:
 /// 0-7:   GPRs (EAX-EDI / RAX-RDI)
@@ -14,6 +19,21 @@ use crate::memory_stream::MemoryStream;
 /// 25:    EDI_ON_MEMORY (special)
 const MAX_REGISTER_ADDRESS: usize = 26;
 
+/// EFLAGS bits that are not kept as individual flag fields.
+pub const EFLAGS_TF: u32 = 1 << 8;
+pub const EFLAGS_IOPL: u32 = 0x3 << 12;
+pub const EFLAGS_NT: u32 = 1 << 14;
+pub const EFLAGS_RF: u32 = 1 << 16;
+pub const EFLAGS_VM: u32 = 1 << 17;
+pub const EFLAGS_AC: u32 = 1 << 18;
+pub const EFLAGS_VIF: u32 = 1 << 19;
+pub const EFLAGS_VIP: u32 = 1 << 20;
+pub const EFLAGS_ID: u32 = 1 << 21;
+const EFLAGS_SYSTEM_MASK: u32 =
+    EFLAGS_TF | EFLAGS_IOPL | EFLAGS_NT | EFLAGS_RF | EFLAGS_VM | EFLAGS_AC | EFLAGS_VIF | EFLAGS_VIP | EFLAGS_ID;
+/// EFLAGS bits PHP does not track; `write_system_flags` loads them.
+const EFLAGS_NATIVE_ONLY: u32 = EFLAGS_TF | EFLAGS_RF | EFLAGS_AC;
+
 /// MemoryAccessor structure for managing CPU registers and flags.
 #[repr(C)]
 pub struct MemoryAccessor {
@@ -32,10 +52,18 @@ pub struct MemoryAccessor {
     direction_flag: bool,
     interrupt_flag: bool,
     instruction_fetch: bool,
+    /// TF, IOPL, NT, RF, VM, AC, VIF, VIP and ID at their EFLAGS positions
+    system_flags: u32,
 
     /// Extended Feature Enable Register (EFER MSR)
     efer: u64,
 
+    /// Remaining model-specific registers (STAR, LSTAR, PAT, MTRRs, ...).
+    msrs: MsrFile,
+
+    /// DR0-DR3, DR6 and DR7.
+    debug: DebugRegisters,
+
     /// Control registers (CR0-CR8).
     ///
     /// Stored as 64-bit to preserve long mode semantics:
@@ -43,12 +71,64 @@ pub struct MemoryAccessor {
     /// - CR3/CR4 are conceptually 64-bit in IA-32e.
     control_registers: [u64; 9],
 
+    /// PAE PDPTE registers, loaded from CR3 by MOV to CR0/CR3/CR4.
+    pdptes: [u64; 4],
+
+    /// Cached linear-to-physical translations.
+    tlb: Tlb,
+
+    /// Hidden descriptor caches for ES, CS, SS, DS, FS and GS.
+    segments: [SegmentCache; SEGMENT_COUNT],
+
+    /// GDTR, IDTR, LDTR and TR (indexed by `TABLE_*`).
+    descriptor_tables: [DescriptorTableRegister; DESCRIPTOR_TABLE_COUNT],
+
+    /// A20 gate; when off, legacy-mode system accesses wrap at 1MB.
+    a20_enabled: bool,
+
+    /// Instruction count and record/replay log of nondeterministic inputs.
+    replay: ReplayLog,
+
+    /// Memory-access tracer, when enabled.
+    tracer: Option<Box<MemoryTracer>>,
+
     /// Pointer to the memory stream (owned by PHP, just referenced here)
     memory: *mut MemoryStream,
 }
 
+mod address;
 mod core;
+mod debug;
+mod descriptor;
+mod fault;
+mod fetch;
+mod msr;
 mod paging;
+mod replay;
+mod save_state;
+mod segment;
+mod stack;
+mod state;
+mod task;
+mod tlb;
+mod trace;
+mod walk;
 mod ffi;
 
+pub use address::*;
+pub use self::core::*;
+pub use debug::*;
+pub use descriptor::*;
+pub use fault::*;
+pub use fetch::*;
+pub use msr::*;
+pub use paging::*;
+pub use replay::*;
+pub use save_state::*;
+pub use segment::*;
+pub use state::*;
+pub use task::*;
+pub use tlb::*;
+pub use trace::*;
+pub use walk::*;
 pub use ffi::*;
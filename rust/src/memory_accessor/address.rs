@@ -0,0 +1,62 @@
+//! Effective-address computation for decoded ModRM/SIB operands.
+//!
+//! Works from a `DecodedInstruction` and the register file, so PHP gets the
+//! offset (and optionally the segmented linear address) of a memory operand
+//! in one call instead of fetching each base and index register itself.
+
+use crate::decoder::{DecodedInstruction, REGISTER_NONE, REGISTER_RIP};
+
+use super::state::GPR_ADDRESSES;
+use super::{MemoryAccessor, SegmentAccess};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EffectiveAddress {
+    /// Offset within the segment, wrapped to the address size.
+    pub offset: u64,
+    /// Segment base + offset; only meaningful when `fault` is 0.
+    pub linear: u64,
+    /// `SEGMENT_*` used for the access.
+    pub segment: u32,
+    /// Packed #GP(0)/#SS(0) from the segment checks, 0 on success.
+    pub fault: u32,
+}
+
+impl MemoryAccessor {
+    /// Offset of `instruction`'s memory operand: base + index * scale +
+    /// displacement, wrapped to 16, 32 or 64 bits by the address size.
+    /// `next_ip` is the address of the following instruction, which
+    /// RIP-relative operands are relative to.
+    pub fn effective_offset(&self, instruction: &DecodedInstruction, next_ip: u64) -> u64 {
+        let register = |number: u8| match number {
+            REGISTER_NONE => 0,
+            REGISTER_RIP => next_ip,
+            n => self.registers[GPR_ADDRESSES[n as usize & 15]] as u64,
+        };
+        let index = register(instruction.index).wrapping_mul(instruction.scale as u64);
+        let offset = register(instruction.base)
+            .wrapping_add(index)
+            .wrapping_add(instruction.displacement as u64);
+        match instruction.address_size {
+            16 => offset & 0xFFFF,
+            32 => offset & 0xFFFF_FFFF,
+            _ => offset,
+        }
+    }
+
+    /// Offset and linear address of `instruction`'s memory operand for an
+    /// access of `size` bits, with the segment limit, type and canonical
+    /// checks of `segmented_linear`.
+    pub fn effective_address(
+        &self,
+        instruction: &DecodedInstruction,
+        next_ip: u64,
+        size: u32,
+        kind: SegmentAccess,
+    ) -> EffectiveAddress {
+        let offset = self.effective_offset(instruction, next_ip);
+        let segment = instruction.segment as usize;
+        let (linear, fault) = self.segmented_linear(segment, offset, size, kind);
+        EffectiveAddress { offset, linear, segment: segment as u32, fault }
+    }
+}
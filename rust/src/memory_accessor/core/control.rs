@@ -1,5 +1,37 @@
+use super::super::fault::{pack_fault, VECTOR_GP, VECTOR_UD};
+use super::super::msr::{EFER_LMA, EFER_LME, MAX_PHYSICAL_ADDRESS_BITS};
 use super::super::MemoryAccessor;
 
+pub const CR0_PE: u64 = 1 << 0;
+pub const CR0_TS: u64 = 1 << 3;
+pub const CR0_WP: u64 = 1 << 16;
+pub const CR0_AM: u64 = 1 << 18;
+pub const CR0_NW: u64 = 1 << 29;
+pub const CR0_CD: u64 = 1 << 30;
+pub const CR0_PG: u64 = 1 << 31;
+/// PE, MP, EM, TS, ET, NE, WP, AM, NW, CD and PG.
+const CR0_DEFINED: u64 = 0xE005_003F;
+
+pub const CR4_DE: u64 = 1 << 3;
+pub const CR4_PSE: u64 = 1 << 4;
+pub const CR4_PAE: u64 = 1 << 5;
+pub const CR4_PGE: u64 = 1 << 7;
+pub const CR4_LA57: u64 = 1 << 12;
+pub const CR4_PCIDE: u64 = 1 << 17;
+pub const CR4_SMEP: u64 = 1 << 20;
+pub const CR4_SMAP: u64 = 1 << 21;
+/// VME through OSXMMEXCPT, LA57, PCIDE, SMEP and SMAP: the features CPUID reports.
+const CR4_SUPPORTED: u64 = 0x7FF | CR4_LA57 | CR4_PCIDE | CR4_SMEP | CR4_SMAP;
+
+/// Page-table base plus PWT/PCD; everything above MAXPHYADDR is reserved.
+const CR3_LONG_MODE_VALID: u64 = (1 << MAX_PHYSICAL_ADDRESS_BITS) - 1;
+/// MOV to CR3 hint (CR4.PCIDE=1): keep the new PCID's translations.
+pub const CR3_NO_FLUSH: u64 = 1 << 63;
+
+/// CR0/CR4 bits whose change reloads the PAE PDPTE registers.
+const CR0_PDPTE_RELOAD: u64 = CR0_PG | CR0_CD | CR0_NW;
+const CR4_PDPTE_RELOAD: u64 = CR4_PSE | CR4_PAE | CR4_PGE | CR4_PCIDE | CR4_SMEP | CR4_SMAP;
+
 impl MemoryAccessor {
     // Control register operations
     #[inline(always)]
@@ -13,8 +45,68 @@ impl MemoryAccessor {
 
     #[inline(always)]
     pub fn write_control_register(&mut self, index: usize, value: u64) {
-        if index < self.control_registers.len() {
-            self.control_registers[index] = value;
+        if index >= self.control_registers.len() {
+            return;
+        }
+        let pcide = (self.control_registers[4] & CR4_PCIDE) != 0;
+        let stored = if index == 3 && pcide { value & !CR3_NO_FLUSH } else { value };
+        let old = std::mem::replace(&mut self.control_registers[index], stored);
+        if Self::reloads_pdptes(index, old, stored) {
+            self.reload_pdptes();
+        }
+        match index {
+            3 => {
+                let pcid = self.current_pcid();
+                self.tlb.set_pcid(pcid);
+                if !pcide {
+                    // Reloading CR3 keeps global translations while CR4.PGE is set.
+                    self.flush_tlb((self.control_registers[4] & CR4_PGE) == 0);
+                } else if (value & CR3_NO_FLUSH) == 0 {
+                    self.tlb.flush_pcid(pcid);
+                }
+            }
+            4 => {
+                self.tlb.set_pcid(self.current_pcid());
+                if old != stored {
+                    self.flush_tlb(true);
+                }
+            }
+            0 if old != stored => self.flush_tlb(true),
+            _ => {}
+        }
+    }
+
+    /// MOV to CRn from `old` to `new` reloads the PDPTEs under PAE paging.
+    #[inline(always)]
+    fn reloads_pdptes(index: usize, old: u64, new: u64) -> bool {
+        match index {
+            0 => ((old ^ new) & CR0_PDPTE_RELOAD) != 0,
+            3 => true,
+            4 => ((old ^ new) & CR4_PDPTE_RELOAD) != 0,
+            _ => false,
+        }
+    }
+
+    /// #GP(0) when writing `value` to CRn would load invalid PDPTEs.
+    fn check_pdpte_load(&mut self, index: usize, value: u64) -> u32 {
+        if !Self::reloads_pdptes(index, self.control_registers[index], value) {
+            return 0;
+        }
+        let mut registers = self.control_registers;
+        registers[index] = value;
+        if !self.uses_pae_pdptes(registers[0], registers[4]) {
+            return 0;
+        }
+        self.read_pdptes(registers[3]).1
+    }
+
+    /// PCID of the current address space: CR3[11:0] with CR4.PCIDE set.
+    #[inline(always)]
+    pub fn current_pcid(&self) -> u16 {
+        if (self.control_registers[4] & CR4_PCIDE) != 0 {
+            (self.control_registers[3] & 0xFFF) as u16
+        } else {
+            0
         }
     }
 
@@ -26,6 +118,107 @@ impl MemoryAccessor {
 
     #[inline(always)]
     pub fn write_efer(&mut self, value: u64) {
-        self.efer = value;
+        if std::mem::replace(&mut self.efer, value) != value {
+            self.flush_tlb(true);
+        }
+    }
+
+    /// MOV to CRn with the architectural checks applied.
+    ///
+    /// Returns 0, #GP(0) for reserved bits and invalid mode transitions, or
+    /// #UD for CR1 and CR5-CR7. Undefined bits of CR0[31:0] are ignored, as
+    /// on hardware. Enabling paging with EFER.LME set activates IA-32e mode
+    /// (EFER.LMA); disabling it deactivates it. Under PAE paging, writes that
+    /// reload the PDPTE registers fault with #GP(0) on invalid PDPTEs.
+    pub fn load_control_register(&mut self, index: usize, value: u64) -> u32 {
+        let gp = pack_fault(VECTOR_GP, 0);
+        match index {
+            0 => {
+                if (value >> 32) != 0 {
+                    return gp;
+                }
+                let value = value & CR0_DEFINED;
+                let pe = (value & CR0_PE) != 0;
+                let pg = (value & CR0_PG) != 0;
+                if (pg && !pe) || ((value & CR0_NW) != 0 && (value & CR0_CD) == 0) {
+                    return gp;
+                }
+                let was_paging = self.paging_enabled();
+                let lme = (self.efer & EFER_LME) != 0;
+                if pg && !was_paging && lme && (self.control_registers[4] & CR4_PAE) == 0 {
+                    return gp;
+                }
+                if !pg && was_paging && (self.is_64bit_mode() || (self.control_registers[4] & CR4_PCIDE) != 0) {
+                    return gp;
+                }
+                let err = self.check_pdpte_load(0, value);
+                if err != 0 {
+                    return err;
+                }
+                self.write_control_register(0, value);
+                if pg && lme {
+                    self.write_efer(self.efer | EFER_LMA);
+                } else if !pg {
+                    self.write_efer(self.efer & !EFER_LMA);
+                }
+            }
+            2 => self.control_registers[2] = value,
+            3 => {
+                let value = if self.long_mode_active() {
+                    let no_flush = if (self.control_registers[4] & CR4_PCIDE) != 0 { CR3_NO_FLUSH } else { 0 };
+                    if (value & !(CR3_LONG_MODE_VALID | no_flush)) != 0 {
+                        return gp;
+                    }
+                    value
+                } else {
+                    value & 0xFFFF_FFFF
+                };
+                let err = self.check_pdpte_load(3, value);
+                if err != 0 {
+                    return err;
+                }
+                self.write_control_register(3, value);
+            }
+            4 => {
+                if (value & !CR4_SUPPORTED) != 0 {
+                    return gp;
+                }
+                // PAE stays on and the paging depth is fixed while IA-32e is active.
+                let old = self.control_registers[4];
+                if self.long_mode_active() && ((value & CR4_PAE) == 0 || ((value ^ old) & CR4_LA57) != 0) {
+                    return gp;
+                }
+                // PCIDE can only be turned on in IA-32e mode with CR3[11:0] clear.
+                let enabling_pcid = (value & !old & CR4_PCIDE) != 0;
+                if enabling_pcid && (!self.long_mode_active() || (self.control_registers[3] & 0xFFF) != 0) {
+                    return gp;
+                }
+                let err = self.check_pdpte_load(4, value);
+                if err != 0 {
+                    return err;
+                }
+                self.write_control_register(4, value);
+            }
+            8 => {
+                if (value & !0xF) != 0 {
+                    return gp;
+                }
+                self.control_registers[8] = value;
+            }
+            _ => return pack_fault(VECTOR_UD, 0),
+        }
+        0
+    }
+
+    /// Local APIC TPR as seen through CR8 (TPR[7:4] = CR8[3:0]).
+    #[inline(always)]
+    pub fn task_priority(&self) -> u8 {
+        ((self.control_registers[8] & 0xF) << 4) as u8
+    }
+
+    /// Mirror a TPR written through the local APIC into CR8.
+    #[inline(always)]
+    pub fn set_task_priority(&mut self, tpr: u8) {
+        self.control_registers[8] = (tpr >> 4) as u64;
     }
 }
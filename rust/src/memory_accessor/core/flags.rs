@@ -1,4 +1,4 @@
-use super::super::MemoryAccessor;
+use super::super::{MemoryAccessor, EFLAGS_AC, EFLAGS_NATIVE_ONLY, EFLAGS_NT, EFLAGS_SYSTEM_MASK, EFLAGS_TF};
 
 impl MemoryAccessor {
     /// Update CPU flags based on a value.
@@ -11,7 +11,7 @@ impl MemoryAccessor {
             self.sign_flag = value < 0;
             // OF cannot be derived from the result alone for most operations.
             self.overflow_flag = false;
-            self.parity_flag = ((value & 0xFF) as u8).count_ones() % 2 == 0;
+            self.parity_flag = ((value & 0xFF) as u8).count_ones().is_multiple_of(2);
             return;
         }
 
@@ -27,7 +27,7 @@ impl MemoryAccessor {
         self.overflow_flag = value < signed_min || value > signed_max;
 
         // Parity flag (count of 1 bits in low byte)
-        self.parity_flag = ((masked & 0xFF) as u8).count_ones() % 2 == 0;
+        self.parity_flag = ((masked & 0xFF) as u8).count_ones().is_multiple_of(2);
     }
 
     // Flag getters
@@ -121,4 +121,52 @@ impl MemoryAccessor {
     pub fn instruction_fetch(&self) -> bool {
         self.instruction_fetch
     }
+
+    /// Compose the full EFLAGS image (bit 1 always reads as 1).
+    pub fn read_eflags(&self) -> u32 {
+        (self.carry_flag as u32)
+            | 0x2
+            | ((self.parity_flag as u32) << 2)
+            | ((self.auxiliary_carry_flag as u32) << 4)
+            | ((self.zero_flag as u32) << 6)
+            | ((self.sign_flag as u32) << 7)
+            | ((self.interrupt_flag as u32) << 9)
+            | ((self.direction_flag as u32) << 10)
+            | ((self.overflow_flag as u32) << 11)
+            | self.system_flags
+    }
+
+    /// Load every EFLAGS bit from a packed image.
+    pub fn write_eflags(&mut self, value: u32) {
+        self.carry_flag = (value & (1 << 0)) != 0;
+        self.parity_flag = (value & (1 << 2)) != 0;
+        self.auxiliary_carry_flag = (value & (1 << 4)) != 0;
+        self.zero_flag = (value & (1 << 6)) != 0;
+        self.sign_flag = (value & (1 << 7)) != 0;
+        self.interrupt_flag = (value & (1 << 9)) != 0;
+        self.direction_flag = (value & (1 << 10)) != 0;
+        self.overflow_flag = (value & (1 << 11)) != 0;
+        self.system_flags = value & EFLAGS_SYSTEM_MASK;
+    }
+
+    /// Load TF, RF and AC from a flags image (POPF, IRET, interrupt
+    /// delivery), leaving every other bit alone.
+    pub fn write_system_flags(&mut self, value: u32) {
+        self.system_flags = (self.system_flags & !EFLAGS_NATIVE_ONLY) | (value & EFLAGS_NATIVE_ONLY);
+    }
+
+    #[inline(always)]
+    pub fn trap_flag(&self) -> bool {
+        (self.system_flags & EFLAGS_TF) != 0
+    }
+
+    #[inline(always)]
+    pub fn nested_task_flag(&self) -> bool {
+        (self.system_flags & EFLAGS_NT) != 0
+    }
+
+    #[inline(always)]
+    pub fn alignment_check_flag(&self) -> bool {
+        (self.system_flags & EFLAGS_AC) != 0
+    }
 }
@@ -2,3 +2,5 @@ mod registers;
 mod flags;
 mod control;
 mod memory;
+
+pub use control::*;
@@ -1,4 +1,10 @@
 use crate::memory_stream::MemoryStream;
+use super::super::debug::DebugRegisters;
+use super::super::descriptor::{DescriptorTableRegister, DESCRIPTOR_TABLE_COUNT};
+use super::super::msr::MsrFile;
+use super::super::replay::ReplayLog;
+use super::super::segment::{SEGMENT_COUNT, SEGMENT_REGISTER_BASE};
+use super::super::tlb::Tlb;
 use super::super::{MemoryAccessor, MAX_REGISTER_ADDRESS};
 
 impl MemoryAccessor {
@@ -16,8 +22,18 @@ impl MemoryAccessor {
             direction_flag: false,
             interrupt_flag: false,
             instruction_fetch: false,
+            system_flags: 0,
             efer: 0,
+            msrs: MsrFile::new(),
+            debug: DebugRegisters::new(),
             control_registers: [0x22, 0, 0, 0, 0, 0, 0, 0, 0], // CR0: MP + NE set
+            pdptes: [0; 4],
+            tlb: Tlb::new(),
+            segments: Self::reset_segment_caches(),
+            descriptor_tables: [DescriptorTableRegister { base: 0, limit: 0xFFFF, selector: 0 }; DESCRIPTOR_TABLE_COUNT],
+            a20_enabled: false,
+            replay: ReplayLog::new(),
+            tracer: None,
             memory,
         }
     }
@@ -128,6 +144,12 @@ impl MemoryAccessor {
                 self.registers[address] = new_value;
             } else {
                 self.registers[address] = value;
+                // Real-mode selector loads refresh the cached base only.
+                if (SEGMENT_REGISTER_BASE..SEGMENT_REGISTER_BASE + SEGMENT_COUNT).contains(&address)
+                    && !self.protected_mode_enabled()
+                {
+                    self.load_segment_real_mode(address - SEGMENT_REGISTER_BASE, value as u16);
+                }
             }
         } else {
             // Write to memory
@@ -0,0 +1,247 @@
+//! Debug registers, hardware breakpoints and single-step traps.
+//!
+//! Data breakpoints are traps: `read_memory_*`/`write_memory_*` complete the
+//! access and only latch the matching B0-B3 bits. Instruction breakpoints
+//! are faults checked at the instruction boundary. While `debug_armed`, the
+//! executor brackets each instruction with `debug_instruction_begin` and
+//! `finish_instruction`; the latter folds latched hits and TF single-step
+//! into DR6 and reports the #DB trap.
+
+use super::fault::{pack_fault, VECTOR_DB, VECTOR_GP, VECTOR_UD};
+use super::{MemoryAccessor, CR4_DE, EFLAGS_RF, EFLAGS_TF};
+
+/// DR6 status bits.
+pub const DR6_B0: u64 = 1 << 0;
+pub const DR6_BD: u64 = 1 << 13;
+pub const DR6_BS: u64 = 1 << 14;
+pub const DR6_BT: u64 = 1 << 15;
+/// DR7.GD: general-detect of MOV DR.
+pub const DR7_GD: u64 = 1 << 13;
+
+/// Bits of DR6 that always read as 1.
+const DR6_FIXED_ONES: u64 = 0xFFFF_0FF0;
+const DR6_WRITABLE: u64 = 0xF | DR6_BD | DR6_BS | DR6_BT;
+/// Bit 10 of DR7 always reads as 1; bits 11, 12, 14 and 15 as 0.
+const DR7_FIXED_ONES: u64 = 1 << 10;
+const DR7_WRITABLE: u64 = 0xFFFF_03FF | DR7_GD;
+const DR6_HIT_MASK: u64 = 0xF;
+
+/// DR7 R/W field encodings.
+const BREAK_EXECUTE: u64 = 0b00;
+const BREAK_WRITE: u64 = 0b01;
+const BREAK_READ_WRITE: u64 = 0b11;
+
+/// DR0-DR3, DR6, DR7 and breakpoint/single-step state pending for the
+/// current instruction.
+pub(crate) struct DebugRegisters {
+    address: [u64; 4],
+    dr6: u64,
+    dr7: u64,
+    /// B0-B3 bits of data breakpoints hit by the current instruction.
+    pending_hits: u64,
+    /// TF was set when the current instruction started.
+    single_step: bool,
+}
+
+impl DebugRegisters {
+    pub(crate) fn new() -> Self {
+        DebugRegisters {
+            address: [0; 4],
+            dr6: DR6_FIXED_ONES,
+            dr7: DR7_FIXED_ONES,
+            pending_hits: 0,
+            single_step: false,
+        }
+    }
+
+    /// DR0-DR3, DR6 and DR7, as written to save states.
+    pub(crate) fn saved(&self) -> [u64; 6] {
+        let [dr0, dr1, dr2, dr3] = self.address;
+        [dr0, dr1, dr2, dr3, self.dr6, self.dr7]
+    }
+
+    /// Restore `saved()` values; nothing is pending after a restore.
+    pub(crate) fn restore(&mut self, values: [u64; 6]) {
+        *self = DebugRegisters::new();
+        self.address.copy_from_slice(&values[..4]);
+        self.dr6 = values[4];
+        self.dr7 = values[5];
+    }
+
+    /// Breakpoint `n` is enabled locally or globally.
+    #[inline(always)]
+    fn enabled(&self, n: usize) -> bool {
+        (self.dr7 >> (n * 2)) & 0x3 != 0
+    }
+
+    #[inline(always)]
+    fn condition(&self, n: usize) -> u64 {
+        (self.dr7 >> (16 + n * 4)) & 0x3
+    }
+
+    /// Breakpoint length in bytes from the LEN field (01=2, 11=4, 10=8).
+    #[inline(always)]
+    fn length(&self, n: usize) -> u64 {
+        match (self.dr7 >> (18 + n * 4)) & 0x3 {
+            0b01 => 2,
+            0b11 => 4,
+            0b10 => 8,
+            _ => 1,
+        }
+    }
+
+    /// B0-B3 bits for enabled breakpoints whose condition accepts `accept`
+    /// and whose range overlaps [linear, linear + bytes).
+    fn matches(&self, linear: u64, bytes: u64, accept: impl Fn(u64) -> bool) -> u64 {
+        if (self.dr7 & 0xFF) == 0 {
+            return 0;
+        }
+        let last = linear.wrapping_add(bytes - 1);
+        let mut hits = 0;
+        for n in 0..4 {
+            if !self.enabled(n) || !accept(self.condition(n)) {
+                continue;
+            }
+            let length = self.length(n);
+            let start = self.address[n] & !(length - 1);
+            let end = start + (length - 1);
+            if linear <= end && start <= last {
+                hits |= 1 << n;
+            }
+        }
+        hits
+    }
+}
+
+impl MemoryAccessor {
+    /// MOV from DRn. Returns (value, error).
+    pub fn read_debug_register(&mut self, index: usize) -> (u64, u32) {
+        let index = match self.resolve_debug_register(index) {
+            Ok(index) => index,
+            Err(err) => return (0, err),
+        };
+        let debug = &self.debug;
+        let value = match index {
+            0..=3 => debug.address[index],
+            6 => debug.dr6,
+            _ => debug.dr7,
+        };
+        (value, 0)
+    }
+
+    /// MOV to DRn. Returns 0 or #GP/#UD/#DB.
+    pub fn write_debug_register(&mut self, index: usize, value: u64) -> u32 {
+        let index = match self.resolve_debug_register(index) {
+            Ok(index) => index,
+            Err(err) => return err,
+        };
+        let long_mode = self.long_mode_active();
+        let debug = &mut self.debug;
+        match index {
+            0..=3 => {
+                debug.address[index] = if long_mode { value } else { value & 0xFFFF_FFFF };
+            }
+            6 | 7 => {
+                if (value >> 32) != 0 {
+                    return pack_fault(VECTOR_GP, 0);
+                }
+                if index == 6 {
+                    debug.dr6 = (value & DR6_WRITABLE) | DR6_FIXED_ONES;
+                } else {
+                    debug.dr7 = (value & DR7_WRITABLE) | DR7_FIXED_ONES;
+                    // The executor skips the bracket while nothing is armed,
+                    // so clear a stale RF here as the MOV itself retires.
+                    self.system_flags &= !EFLAGS_RF;
+                }
+            }
+            _ => unreachable!(),
+        }
+        0
+    }
+
+    /// Privilege, CR4.DE aliasing and DR7.GD checks shared by MOV DR.
+    fn resolve_debug_register(&mut self, index: usize) -> Result<usize, u32> {
+        if self.current_privilege_level() != 0 {
+            return Err(pack_fault(VECTOR_GP, 0));
+        }
+        let index = match index {
+            0..=3 | 6 | 7 => index,
+            4 | 5 => {
+                // CR4.DE makes DR4/DR5 undefined instead of aliases of DR6/DR7.
+                if (self.control_registers[4] & CR4_DE) != 0 {
+                    return Err(pack_fault(VECTOR_UD, 0));
+                }
+                index + 2
+            }
+            _ => return Err(pack_fault(VECTOR_UD, 0)),
+        };
+        if (self.debug.dr7 & DR7_GD) != 0 {
+            self.debug.dr6 |= DR6_BD;
+            self.debug.dr7 &= !DR7_GD;
+            return Err(pack_fault(VECTOR_DB, 0));
+        }
+        Ok(index)
+    }
+
+    /// An enabled breakpoint or TF: instructions must be bracketed.
+    pub fn debug_armed(&self) -> bool {
+        (self.debug.dr7 & 0xFF) != 0 || (self.system_flags & EFLAGS_TF) != 0
+    }
+
+    /// Start of an instruction at `linear`: check instruction breakpoints
+    /// and latch TF for single-step.
+    ///
+    /// Returns a #DB fault when an enabled execute breakpoint matches and
+    /// EFLAGS.RF is clear; DR6 then carries the matching B0-B3 bits.
+    pub fn debug_instruction_begin(&mut self, linear: u64) -> u32 {
+        self.debug.pending_hits = 0;
+        self.debug.single_step = (self.system_flags & EFLAGS_TF) != 0;
+        if (self.system_flags & EFLAGS_RF) != 0 {
+            return 0;
+        }
+        let hits = self.debug.matches(linear, 1, |condition| condition == BREAK_EXECUTE);
+        if hits == 0 {
+            return 0;
+        }
+        self.debug.dr6 = (self.debug.dr6 & !DR6_HIT_MASK) | hits;
+        pack_fault(VECTOR_DB, 0)
+    }
+
+    /// End of a successfully executed instruction.
+    ///
+    /// Clears EFLAGS.RF and returns a #DB trap when data breakpoints were
+    /// hit or TF was set at the start of the instruction.
+    pub fn debug_instruction_end(&mut self) -> u32 {
+        self.system_flags &= !EFLAGS_RF;
+        let hits = std::mem::take(&mut self.debug.pending_hits);
+        let single_step = std::mem::take(&mut self.debug.single_step);
+        if hits == 0 && !single_step {
+            return 0;
+        }
+        if hits != 0 {
+            self.debug.dr6 = (self.debug.dr6 & !DR6_HIT_MASK) | hits;
+        }
+        if single_step {
+            self.debug.dr6 |= DR6_BS;
+        }
+        pack_fault(VECTOR_DB, 0)
+    }
+
+    /// `debug_instruction_end` followed by `retire_instruction`.
+    pub fn finish_instruction(&mut self) -> u32 {
+        let trap = self.debug_instruction_end();
+        self.retire_instruction();
+        trap
+    }
+
+    /// Latch data breakpoints matching a completed access.
+    #[inline(always)]
+    pub(crate) fn note_data_access(&mut self, linear: u64, bytes: u64, is_write: bool) {
+        if (self.debug.dr7 & 0xFF) == 0 || self.instruction_fetch {
+            return;
+        }
+        self.debug.pending_hits |= self.debug.matches(linear, bytes, |condition| {
+            condition == BREAK_READ_WRITE || (is_write && condition == BREAK_WRITE)
+        });
+    }
+}
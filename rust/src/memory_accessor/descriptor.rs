@@ -0,0 +1,339 @@
+//! GDT/LDT/IDT descriptor reading and decoding.
+//!
+//! Descriptors are read through the linear address space with supervisor
+//! rights, the way the CPU performs implicit system accesses. In IA-32e mode
+//! system descriptors (LDT, TSS, call gates) and IDT gates are 16 bytes wide.
+
+use super::fault::{pack_fault, VECTOR_GP};
+use super::segment::SegmentCache;
+use super::MemoryAccessor;
+
+/// Decoded descriptor classification.
+pub const DESCRIPTOR_INVALID: u32 = 0;
+pub const DESCRIPTOR_CODE: u32 = 1;
+pub const DESCRIPTOR_DATA: u32 = 2;
+pub const DESCRIPTOR_LDT: u32 = 3;
+pub const DESCRIPTOR_TSS_AVAILABLE: u32 = 4;
+pub const DESCRIPTOR_TSS_BUSY: u32 = 5;
+pub const DESCRIPTOR_CALL_GATE: u32 = 6;
+pub const DESCRIPTOR_INTERRUPT_GATE: u32 = 7;
+pub const DESCRIPTOR_TRAP_GATE: u32 = 8;
+pub const DESCRIPTOR_TASK_GATE: u32 = 9;
+
+/// Indices into the accessor's descriptor-table registers.
+pub const TABLE_GDTR: usize = 0;
+pub const TABLE_IDTR: usize = 1;
+pub const TABLE_LDTR: usize = 2;
+pub const TABLE_TR: usize = 3;
+pub const DESCRIPTOR_TABLE_COUNT: usize = 4;
+
+/// GDTR/LDTR/IDTR/TR contents as seen by descriptor lookups.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DescriptorTableRegister {
+    pub base: u64,
+    pub limit: u32,
+    /// Selector for LDTR/TR; unused for GDTR/IDTR.
+    pub selector: u16,
+}
+
+/// A decoded GDT/LDT/IDT entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SegmentDescriptor {
+    /// Segment base (64-bit for IA-32e LDT/TSS descriptors).
+    pub base: u64,
+    /// Gate target offset.
+    pub offset: u64,
+    /// Byte-granular limit.
+    pub limit: u32,
+    /// One of the `DESCRIPTOR_*` kinds.
+    pub kind: u32,
+    /// Gate target selector (TSS selector for task gates).
+    pub selector: u16,
+    /// Attributes in the `SegmentCache` layout.
+    pub attributes: u16,
+    /// Raw 4-bit type field.
+    pub descriptor_type: u8,
+    pub dpl: u8,
+    pub present: bool,
+    /// 16, 32 or 64 (segment default size or gate width).
+    pub default_size: u8,
+    /// Call gate parameter count.
+    pub param_count: u8,
+    /// IA-32e interrupt stack table index.
+    pub ist: u8,
+}
+
+impl SegmentDescriptor {
+    /// Decode an 8-byte descriptor, plus the upper half when `long_mode`
+    /// selects the 16-byte system descriptor format.
+    pub fn decode(low: u64, high: u64, long_mode: bool) -> Self {
+        let access = ((low >> 40) & 0xFF) as u16;
+        let flags = ((low >> 52) & 0xF) as u16;
+        let descriptor_type = (access & 0xF) as u8;
+
+        let mut limit = ((low & 0xFFFF) | ((low >> 32) & 0xF_0000)) as u32;
+        if (flags & 0x8) != 0 {
+            limit = (limit << 12) | 0xFFF;
+        }
+
+        let mut descriptor = SegmentDescriptor {
+            base: ((low >> 16) & 0xFF_FFFF) | (((low >> 56) & 0xFF) << 24),
+            limit,
+            attributes: access | (flags << 12),
+            descriptor_type,
+            dpl: ((access >> 5) & 0x3) as u8,
+            present: (access & 0x80) != 0,
+            ..Default::default()
+        };
+
+        if (access & 0x10) != 0 {
+            let is_code = (descriptor_type & 0x8) != 0;
+            descriptor.kind = if is_code { DESCRIPTOR_CODE } else { DESCRIPTOR_DATA };
+            descriptor.default_size = if long_mode && is_code && (flags & 0x2) != 0 {
+                64
+            } else if (flags & 0x4) != 0 {
+                32
+            } else {
+                16
+            };
+            return descriptor;
+        }
+
+        descriptor.kind = Self::system_kind(descriptor_type, long_mode);
+        match descriptor.kind {
+            DESCRIPTOR_LDT | DESCRIPTOR_TSS_AVAILABLE | DESCRIPTOR_TSS_BUSY => {
+                if long_mode {
+                    descriptor.base |= (high & 0xFFFF_FFFF) << 32;
+                }
+                descriptor.default_size = Self::system_width(descriptor_type, long_mode);
+            }
+            DESCRIPTOR_CALL_GATE | DESCRIPTOR_INTERRUPT_GATE | DESCRIPTOR_TRAP_GATE | DESCRIPTOR_TASK_GATE => {
+                descriptor.base = 0;
+                descriptor.limit = 0;
+                descriptor.selector = ((low >> 16) & 0xFFFF) as u16;
+                descriptor.default_size = Self::system_width(descriptor_type, long_mode);
+                let mut offset = (low & 0xFFFF) | (((low >> 48) & 0xFFFF) << 16);
+                if descriptor.default_size == 16 {
+                    offset &= 0xFFFF;
+                }
+                if long_mode {
+                    offset |= (high & 0xFFFF_FFFF) << 32;
+                }
+                if descriptor.kind == DESCRIPTOR_TASK_GATE {
+                    offset = 0;
+                }
+                descriptor.offset = offset;
+                if descriptor.kind == DESCRIPTOR_CALL_GATE && !long_mode {
+                    descriptor.param_count = ((low >> 32) & 0x1F) as u8;
+                }
+                if long_mode && descriptor.kind != DESCRIPTOR_CALL_GATE {
+                    descriptor.ist = ((low >> 32) & 0x7) as u8;
+                }
+            }
+            _ => {}
+        }
+        descriptor
+    }
+
+    /// Classify a system descriptor type (S=0).
+    fn system_kind(descriptor_type: u8, long_mode: bool) -> u32 {
+        if long_mode {
+            return match descriptor_type {
+                0x2 => DESCRIPTOR_LDT,
+                0x9 => DESCRIPTOR_TSS_AVAILABLE,
+                0xB => DESCRIPTOR_TSS_BUSY,
+                0xC => DESCRIPTOR_CALL_GATE,
+                0xE => DESCRIPTOR_INTERRUPT_GATE,
+                0xF => DESCRIPTOR_TRAP_GATE,
+                _ => DESCRIPTOR_INVALID,
+            };
+        }
+        match descriptor_type {
+            0x1 | 0x9 => DESCRIPTOR_TSS_AVAILABLE,
+            0x2 => DESCRIPTOR_LDT,
+            0x3 | 0xB => DESCRIPTOR_TSS_BUSY,
+            0x4 | 0xC => DESCRIPTOR_CALL_GATE,
+            0x5 => DESCRIPTOR_TASK_GATE,
+            0x6 | 0xE => DESCRIPTOR_INTERRUPT_GATE,
+            0x7 | 0xF => DESCRIPTOR_TRAP_GATE,
+            _ => DESCRIPTOR_INVALID,
+        }
+    }
+
+    /// Operand width of a TSS or gate: bit 3 of the type selects 32-bit.
+    fn system_width(descriptor_type: u8, long_mode: bool) -> u8 {
+        if long_mode {
+            64
+        } else if (descriptor_type & 0x8) != 0 {
+            32
+        } else {
+            16
+        }
+    }
+
+    /// System descriptor (S=0): LDT, TSS or gate.
+    #[inline(always)]
+    pub fn is_system(&self) -> bool {
+        (self.attributes & 0x10) == 0
+    }
+
+    /// Build the hidden segment register contents for `selector`.
+    pub fn to_segment_cache(&self, selector: u16) -> SegmentCache {
+        SegmentCache {
+            selector,
+            attributes: self.attributes,
+            limit: self.limit,
+            base: self.base,
+        }
+    }
+}
+
+impl MemoryAccessor {
+    /// Read GDTR, IDTR, LDTR or TR.
+    #[inline(always)]
+    pub fn descriptor_table(&self, table: usize) -> Option<DescriptorTableRegister> {
+        self.descriptor_tables.get(table).copied()
+    }
+
+    /// Load GDTR, IDTR, LDTR or TR.
+    pub fn load_descriptor_table(&mut self, table: usize, value: DescriptorTableRegister) -> bool {
+        match self.descriptor_tables.get_mut(table) {
+            Some(slot) => {
+                *slot = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Read and decode the GDT/LDT entry referenced by `selector`.
+    ///
+    /// Returns (descriptor, error). A selector beyond the table limit, or an
+    /// LDT reference while LDTR is null, yields #GP(selector & ~3). When
+    /// `set_accessed` is true, the accessed bit of a code/data descriptor is
+    /// written back to the table.
+    pub fn read_segment_descriptor(
+        &mut self,
+        gdtr: &DescriptorTableRegister,
+        ldtr: &DescriptorTableRegister,
+        selector: u16,
+        set_accessed: bool,
+    ) -> (SegmentDescriptor, u32) {
+        let fault = pack_fault(VECTOR_GP, (selector & 0xFFFC) as u32);
+        let (entry, err) = self.descriptor_entry_address(gdtr, ldtr, selector);
+        if err != 0 {
+            return (SegmentDescriptor::default(), err);
+        }
+
+        let table_limit = (if (selector & 0x4) != 0 { ldtr.limit } else { gdtr.limit }) as u64;
+        let offset = ((selector as u64) & 0xFFF8) + 7;
+        if offset > table_limit {
+            return (SegmentDescriptor::default(), fault);
+        }
+
+        let (low, err) = self.read_system_64(entry);
+        if err != 0 {
+            return (SegmentDescriptor::default(), err);
+        }
+
+        let long_mode = self.long_mode_active();
+        let mut high = 0;
+        if long_mode && ((low >> 44) & 0x1) == 0 {
+            if offset + 8 > table_limit {
+                return (SegmentDescriptor::default(), fault);
+            }
+            let (value, err) = self.read_system_64(entry + 8);
+            if err != 0 {
+                return (SegmentDescriptor::default(), err);
+            }
+            high = value;
+        }
+
+        let mut descriptor = SegmentDescriptor::decode(low, high, long_mode);
+        if set_accessed
+            && matches!(descriptor.kind, DESCRIPTOR_CODE | DESCRIPTOR_DATA)
+            && (descriptor.attributes & 0x1) == 0
+        {
+            let access = ((low >> 40) & 0xFF) as u8 | 0x1;
+            let err = self.write_system_8(entry + 5, access);
+            if err != 0 {
+                return (descriptor, err);
+            }
+            descriptor.attributes |= 0x1;
+        }
+        (descriptor, 0)
+    }
+
+    /// Read the GDT/LDT entry for `selector` to refresh a hidden cache.
+    ///
+    /// The accessed bit is left alone and a failed read does not change CR2:
+    /// the instruction's own descriptor read is the one that reports faults.
+    pub fn peek_segment_descriptor(
+        &mut self,
+        gdtr: &DescriptorTableRegister,
+        ldtr: &DescriptorTableRegister,
+        selector: u16,
+    ) -> Option<SegmentDescriptor> {
+        let cr2 = self.control_registers[2];
+        let (descriptor, err) = self.read_segment_descriptor(gdtr, ldtr, selector, false);
+        if err != 0 {
+            self.control_registers[2] = cr2;
+            return None;
+        }
+        Some(descriptor)
+    }
+
+    /// Read and decode the IDT gate for `vector`.
+    ///
+    /// Returns #GP(vector * 8 + 2) when the gate lies beyond the IDT limit.
+    pub fn read_gate_descriptor(
+        &mut self,
+        idtr: &DescriptorTableRegister,
+        vector: u8,
+    ) -> (SegmentDescriptor, u32) {
+        let long_mode = self.long_mode_active();
+        let entry_size: u64 = if long_mode { 16 } else { 8 };
+        let offset = (vector as u64) * entry_size;
+        if offset + entry_size - 1 > idtr.limit as u64 {
+            return (
+                SegmentDescriptor::default(),
+                pack_fault(VECTOR_GP, ((vector as u32) << 3) | 0x2),
+            );
+        }
+
+        let entry = idtr.base.wrapping_add(offset);
+        let (low, err) = self.read_system_64(entry);
+        if err != 0 {
+            return (SegmentDescriptor::default(), err);
+        }
+        let mut high = 0;
+        if long_mode {
+            let (value, err) = self.read_system_64(entry + 8);
+            if err != 0 {
+                return (SegmentDescriptor::default(), err);
+            }
+            high = value;
+        }
+        (SegmentDescriptor::decode(low, high, long_mode), 0)
+    }
+
+    /// Linear address of the GDT/LDT entry for `selector`.
+    pub(crate) fn descriptor_entry_address(
+        &self,
+        gdtr: &DescriptorTableRegister,
+        ldtr: &DescriptorTableRegister,
+        selector: u16,
+    ) -> (u64, u32) {
+        let table = if (selector & 0x4) != 0 {
+            if (ldtr.selector & 0xFFFC) == 0 {
+                return (0, pack_fault(VECTOR_GP, (selector & 0xFFFC) as u32));
+            }
+            ldtr
+        } else {
+            gdtr
+        };
+        (table.base.wrapping_add((selector as u64) & 0xFFF8), 0)
+    }
+}
@@ -0,0 +1,29 @@
+//! Exception vectors and the packed fault encoding shared by the accessor.
+//!
+//! Faults cross the FFI boundary as `(vector << 16) | error_code`, the same
+//! layout `translate_linear` has always used for #PF. A zero value means
+//! success and `MMIO_SIGNAL` asks PHP to complete the access itself.
+
+pub const VECTOR_DB: u32 = 0x01;
+pub const VECTOR_UD: u32 = 0x06;
+pub const VECTOR_TS: u32 = 0x0A;
+pub const VECTOR_NP: u32 = 0x0B;
+pub const VECTOR_SS: u32 = 0x0C;
+pub const VECTOR_GP: u32 = 0x0D;
+pub const VECTOR_PF: u32 = 0x0E;
+pub const VECTOR_AC: u32 = 0x11;
+
+/// Error value signalling that PHP must handle an MMIO access.
+pub const MMIO_SIGNAL: u32 = 0xFFFFFFFF;
+
+/// Pack an exception vector and its error code.
+#[inline(always)]
+pub const fn pack_fault(vector: u32, error_code: u32) -> u32 {
+    (vector << 16) | (error_code & 0xFFFF)
+}
+
+/// Extract the vector from a packed fault.
+#[inline(always)]
+pub const fn fault_vector(packed: u32) -> u32 {
+    packed >> 16
+}
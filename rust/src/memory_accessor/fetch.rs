@@ -0,0 +1,76 @@
+//! Instruction fetch window.
+//!
+//! `fetch_code` hands the decoder up to 15 opcode bytes in one call instead
+//! of one FFI round trip per byte. Bytes are translated with
+//! instruction-fetch semantics (execute-disable, SMEP) page by page, so a
+//! window that runs into an unmapped or non-executable page still returns
+//! the bytes before it.
+
+use super::MemoryAccessor;
+
+/// Longest legal x86 instruction.
+pub const MAX_INSTRUCTION_LENGTH: usize = 15;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CodeWindow {
+    /// Opcode bytes; only the first `length` are valid.
+    pub bytes: [u8; 16],
+    pub length: u32,
+    /// Why the window stopped short: a packed fault for the byte at
+    /// `length`, 0xFFFFFFFF for MMIO, or 0 when every requested byte was
+    /// fetched.
+    pub fault: u32,
+}
+
+impl MemoryAccessor {
+    /// Fetch up to `max_len` (at most 15) code bytes at `linear`.
+    ///
+    /// A fault on the first byte is raised as usual (CR2 set). A fault
+    /// further in only ends the window: CR2 is left alone, since the
+    /// instruction may not need those bytes. If it does, fetching again
+    /// from `linear + length` raises the fault properly.
+    pub fn fetch_code(
+        &mut self,
+        linear: u64,
+        max_len: usize,
+        is_user: bool,
+        paging_enabled: bool,
+        linear_mask: u64,
+    ) -> CodeWindow {
+        let wanted = max_len.min(MAX_INSTRUCTION_LENGTH);
+        let was_fetching = self.instruction_fetch;
+        self.instruction_fetch = true;
+
+        let mut window = CodeWindow::default();
+        let mut first_physical = 0;
+        let mut offset = 0;
+        while offset < wanted {
+            let address = linear.wrapping_add(offset as u64);
+            let cr2 = self.control_registers[2];
+            let (physical, err) = self.translate_linear(address, false, is_user, paging_enabled, linear_mask);
+            let err = if err == 0 && Self::is_mmio_address(physical as usize) { 0xFFFF_FFFF } else { err };
+            if err != 0 {
+                if offset > 0 {
+                    self.control_registers[2] = cr2;
+                }
+                window.fault = err;
+                break;
+            }
+            if offset == 0 {
+                first_physical = physical;
+            }
+            let chunk = (wanted - offset).min(0x1000 - (address & 0xFFF) as usize);
+            for i in 0..chunk {
+                window.bytes[offset + i] = self.read_physical_8(physical as usize + i);
+            }
+            offset += chunk;
+        }
+        window.length = offset as u32;
+
+        let head = window.bytes[..8].iter().rev().fold(0u64, |value, &byte| (value << 8) | byte as u64);
+        self.trace_access(linear, first_physical, offset.max(1), false, head, window.fault);
+        self.instruction_fetch = was_fetching;
+        window
+    }
+}
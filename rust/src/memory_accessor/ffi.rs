@@ -1,7 +1,16 @@
 #![allow(clippy::missing_safety_doc)]
 
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::Path;
+use std::ptr;
+
+use crate::decoder::DecodedInstruction;
 use crate::memory_stream::MemoryStream;
-use super::MemoryAccessor;
+use super::{
+    convert_trace_log, CodeWindow, CpuState, EffectiveAddress, DescriptorTableRegister, MemoryAccessor, PageWalk, SegmentAccess, SegmentCache, SegmentDescriptor,
+    TaskSwitchReason, REPLAY_LOG_IO_ERROR, SAVE_STATE_IO_ERROR,
+};
 
 
 /// Create a new MemoryAccessor instance.
@@ -231,6 +240,27 @@ pub unsafe extern "C" fn memory_accessor_write_control_register(
     unsafe { (*accessor).write_control_register(index, value as u64) }
 }
 
+/// MOV to CRn: validated write. Returns 0 or a packed #GP(0)/#UD.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_load_control_register(
+    accessor: *mut MemoryAccessor,
+    index: usize,
+    value: u64,
+) -> u32 {
+    unsafe { (*accessor).load_control_register(index, value) }
+}
+
+/// Local APIC TPR derived from CR8.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_task_priority(accessor: *const MemoryAccessor) -> u8 {
+    unsafe { (*accessor).task_priority() }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_set_task_priority(accessor: *mut MemoryAccessor, tpr: u8) {
+    unsafe { (*accessor).set_task_priority(tpr) }
+}
+
 // EFER operations
 #[no_mangle]
 pub unsafe extern "C" fn memory_accessor_read_efer(accessor: *const MemoryAccessor) -> u64 {
@@ -242,6 +272,12 @@ pub unsafe extern "C" fn memory_accessor_write_efer(accessor: *mut MemoryAccesso
     unsafe { (*accessor).write_efer(value) }
 }
 
+/// Open or close the A20 gate.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_set_a20(accessor: *mut MemoryAccessor, enabled: bool) {
+    unsafe { (*accessor).set_a20_enabled(enabled) }
+}
+
 // Memory operations
 #[no_mangle]
 pub unsafe extern "C" fn memory_accessor_read_from_memory(accessor: *const MemoryAccessor, address: usize) -> u8 {
@@ -478,6 +514,605 @@ pub unsafe extern "C" fn memory_accessor_write_physical_16(
     unsafe { (*accessor).write_physical_16(address, value) }
 }
 
+// Segment descriptor caches
+
+/// Read the hidden part of a segment register (0=ES .. 5=GS).
+/// Returns false if the segment index is out of range.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_segment_cache(
+    accessor: *const MemoryAccessor,
+    segment: usize,
+    result: *mut SegmentCache,
+) -> bool {
+    unsafe {
+        match (*accessor).segment_cache(segment) {
+            Some(cache) => {
+                *result = cache;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Load the hidden part of a segment register together with its selector.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_load_segment_cache(
+    accessor: *mut MemoryAccessor,
+    segment: usize,
+    selector: u16,
+    base: u64,
+    limit: u32,
+    attributes: u16,
+) -> bool {
+    unsafe {
+        (*accessor).load_segment_cache(segment, SegmentCache { selector, attributes, limit, base })
+    }
+}
+
+/// Translate segment:offset to a linear address.
+/// kind: 0=read, 1=write, 2=execute. size is in bits.
+/// result_error is 0 on success or (vector << 16) | error_code for #GP/#SS.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_segmented_linear(
+    accessor: *const MemoryAccessor,
+    segment: usize,
+    offset: u64,
+    size: u32,
+    kind: u32,
+    result_linear: *mut u64,
+    result_error: *mut u32,
+) {
+    unsafe {
+        let (linear, err) = (*accessor).segmented_linear(segment, offset, size, SegmentAccess::from_raw(kind));
+        *result_linear = linear;
+        *result_error = err;
+    }
+}
+
+// Descriptor tables
+
+/// Read and decode the GDT/LDT descriptor for `selector`.
+/// result_error is 0 on success or (vector << 16) | error_code (#GP, #PF).
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_read_segment_descriptor(
+    accessor: *mut MemoryAccessor,
+    gdtr_base: u64,
+    gdtr_limit: u32,
+    ldtr_selector: u16,
+    ldtr_base: u64,
+    ldtr_limit: u32,
+    selector: u16,
+    set_accessed: bool,
+    result: *mut SegmentDescriptor,
+    result_error: *mut u32,
+) {
+    let gdtr = DescriptorTableRegister { base: gdtr_base, limit: gdtr_limit, selector: 0 };
+    let ldtr = DescriptorTableRegister { base: ldtr_base, limit: ldtr_limit, selector: ldtr_selector };
+    unsafe {
+        let (descriptor, err) = (*accessor).read_segment_descriptor(&gdtr, &ldtr, selector, set_accessed);
+        *result = descriptor;
+        *result_error = err;
+    }
+}
+
+/// Read the GDT/LDT entry for `selector` without setting the accessed bit
+/// or CR2. Returns false when the read would fault.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_peek_segment_descriptor(
+    accessor: *mut MemoryAccessor,
+    gdtr_base: u64,
+    gdtr_limit: u32,
+    ldtr_selector: u16,
+    ldtr_base: u64,
+    ldtr_limit: u32,
+    selector: u16,
+    result: *mut SegmentDescriptor,
+) -> bool {
+    let gdtr = DescriptorTableRegister { base: gdtr_base, limit: gdtr_limit, selector: 0 };
+    let ldtr = DescriptorTableRegister { base: ldtr_base, limit: ldtr_limit, selector: ldtr_selector };
+    unsafe {
+        match (*accessor).peek_segment_descriptor(&gdtr, &ldtr, selector) {
+            Some(descriptor) => {
+                *result = descriptor;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Read and decode the IDT gate for `vector`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_read_gate_descriptor(
+    accessor: *mut MemoryAccessor,
+    idtr_base: u64,
+    idtr_limit: u32,
+    vector: u8,
+    result: *mut SegmentDescriptor,
+    result_error: *mut u32,
+) {
+    let idtr = DescriptorTableRegister { base: idtr_base, limit: idtr_limit, selector: 0 };
+    unsafe {
+        let (descriptor, err) = (*accessor).read_gate_descriptor(&idtr, vector);
+        *result = descriptor;
+        *result_error = err;
+    }
+}
+
+// Stack operations
+
+/// Push a value onto the stack through SS, honouring the SS B bit / 64-bit width.
+/// Returns error code (0 on success, (vector << 16) | error_code for #SS/#GP/#PF,
+/// 0xFFFFFFFF for MMIO). RSP is unchanged on error.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_push(
+    accessor: *mut MemoryAccessor,
+    value: u64,
+    size: u32,
+    is_user: bool,
+    paging_enabled: bool,
+    linear_mask: u64,
+) -> u32 {
+    unsafe { (*accessor).push(value, size, is_user, paging_enabled, linear_mask) }
+}
+
+/// Pop a value from the stack through SS.
+/// RSP is only advanced when result_error is 0.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_pop(
+    accessor: *mut MemoryAccessor,
+    size: u32,
+    is_user: bool,
+    paging_enabled: bool,
+    linear_mask: u64,
+    result_value: *mut u64,
+    result_error: *mut u32,
+) {
+    unsafe {
+        let (val, err) = (*accessor).pop(size, is_user, paging_enabled, linear_mask);
+        *result_value = val;
+        *result_error = err;
+    }
+}
+
+// Descriptor-table registers and EFLAGS
+
+/// Read GDTR (0), IDTR (1), LDTR (2) or TR (3).
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_descriptor_table(
+    accessor: *const MemoryAccessor,
+    table: usize,
+    result: *mut DescriptorTableRegister,
+) -> bool {
+    unsafe {
+        match (*accessor).descriptor_table(table) {
+            Some(value) => {
+                *result = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Load GDTR (0), IDTR (1), LDTR (2) or TR (3).
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_load_descriptor_table(
+    accessor: *mut MemoryAccessor,
+    table: usize,
+    base: u64,
+    limit: u32,
+    selector: u16,
+) -> bool {
+    unsafe { (*accessor).load_descriptor_table(table, DescriptorTableRegister { base, limit, selector }) }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_read_eflags(accessor: *const MemoryAccessor) -> u32 {
+    unsafe { (*accessor).read_eflags() }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_write_eflags(accessor: *mut MemoryAccessor, value: u32) {
+    unsafe { (*accessor).write_eflags(value) }
+}
+
+/// Load TF, RF and AC only.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_write_system_flags(accessor: *mut MemoryAccessor, value: u32) {
+    unsafe { (*accessor).write_system_flags(value) }
+}
+
+// Task switching
+
+/// JMP (0) or CALL (1) to a TSS or task gate.
+/// result_eip is the new task's EIP once the switch has committed; a non-zero
+/// result_error after commit is delivered in the new task.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_task_switch(
+    accessor: *mut MemoryAccessor,
+    selector: u16,
+    reason: u32,
+    return_eip: u32,
+    result_eip: *mut u32,
+    result_error: *mut u32,
+) {
+    unsafe {
+        let (eip, err) = (*accessor).task_switch(selector, TaskSwitchReason::from_raw(reason), return_eip);
+        *result_eip = eip;
+        *result_error = err;
+    }
+}
+
+/// Deliver `vector` through an IDT task gate, pushing `error_code` when `has_error_code`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_interrupt_task_switch(
+    accessor: *mut MemoryAccessor,
+    vector: u8,
+    return_eip: u32,
+    has_error_code: bool,
+    error_code: u32,
+    result_eip: *mut u32,
+    result_error: *mut u32,
+) {
+    unsafe {
+        let code = if has_error_code { Some(error_code) } else { None };
+        let (eip, err) = (*accessor).interrupt_task_switch(vector, return_eip, code);
+        *result_eip = eip;
+        *result_error = err;
+    }
+}
+
+/// IRET with NT set: switch back to the task in the current TSS back-link.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_iret_task_switch(
+    accessor: *mut MemoryAccessor,
+    return_eip: u32,
+    result_eip: *mut u32,
+    result_error: *mut u32,
+) {
+    unsafe {
+        let (eip, err) = (*accessor).iret_task_switch(return_eip);
+        *result_eip = eip;
+        *result_error = err;
+    }
+}
+
+// Model-specific registers
+
+/// RDMSR. result_error is 0 or #GP(0) packed as (vector << 16).
+/// The TSC is read through the recorder, as for RDTSC.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_rdmsr(
+    accessor: *mut MemoryAccessor,
+    index: u32,
+    result_value: *mut u64,
+    result_error: *mut u32,
+) {
+    unsafe {
+        let (value, err) = (*accessor).rdmsr(index);
+        *result_value = value;
+        *result_error = err;
+    }
+}
+
+/// WRMSR. Returns 0 or #GP(0) packed as (vector << 16).
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_wrmsr(accessor: *mut MemoryAccessor, index: u32, value: u64) -> u32 {
+    unsafe { (*accessor).write_msr(index, value) }
+}
+
+// Debug registers
+
+/// MOV from DR0-DR7. result_error is 0 or a packed #GP/#UD/#DB.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_read_debug_register(
+    accessor: *mut MemoryAccessor,
+    index: usize,
+    result_value: *mut u64,
+    result_error: *mut u32,
+) {
+    unsafe {
+        let (value, err) = (*accessor).read_debug_register(index);
+        *result_value = value;
+        *result_error = err;
+    }
+}
+
+/// MOV to DR0-DR7. Returns 0 or a packed #GP/#UD/#DB.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_write_debug_register(
+    accessor: *mut MemoryAccessor,
+    index: usize,
+    value: u64,
+) -> u32 {
+    unsafe { (*accessor).write_debug_register(index, value) }
+}
+
+/// Instruction boundary: returns a #DB fault for a matching instruction breakpoint.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_debug_instruction_begin(accessor: *mut MemoryAccessor, linear: u64) -> u32 {
+    unsafe { (*accessor).debug_instruction_begin(linear) }
+}
+
+/// Instruction retired and counted: returns a #DB trap for data breakpoints or single-step.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_finish_instruction(accessor: *mut MemoryAccessor) -> u32 {
+    unsafe { (*accessor).finish_instruction() }
+}
+
+/// An enabled DR7 breakpoint or EFLAGS.TF needs the begin/finish bracket.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_debug_armed(accessor: *const MemoryAccessor) -> bool {
+    unsafe { (*accessor).debug_armed() }
+}
+
+/// INVLPG: drop cached translations for the page containing `linear`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_invlpg(accessor: *mut MemoryAccessor, linear: u64) {
+    unsafe { (*accessor).invlpg(linear) }
+}
+
+/// INVPCID with the descriptor's PCID quadword and linear address.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_invpcid(
+    accessor: *mut MemoryAccessor,
+    kind: u64,
+    descriptor_pcid: u64,
+    linear: u64,
+) -> u32 {
+    unsafe { (*accessor).invpcid(kind, descriptor_pcid, linear) }
+}
+
+/// Walk the page tables for `linear` without setting A/D bits, CR2 or the TLB.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_walk(accessor: *const MemoryAccessor, linear: u64, result: *mut PageWalk) {
+    unsafe {
+        *result = (*accessor).walk_page_tables(linear);
+    }
+}
+
+/// Text listing of every mapped range under CR3. Returns the text length;
+/// the NUL-terminated text is copied only when it fits in `buffer_len`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_page_table_dump(
+    accessor: *const MemoryAccessor,
+    buffer: *mut c_char,
+    buffer_len: usize,
+) -> usize {
+    let dump = unsafe { (*accessor).page_table_dump() };
+    let bytes = dump.as_bytes();
+    if !buffer.is_null() && bytes.len() < buffer_len {
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, bytes.len());
+            *buffer.add(bytes.len()) = 0;
+        }
+    }
+    bytes.len()
+}
+
+/// Linear addresses that map to `physical`. Returns how many there are;
+/// up to `capacity` of them are stored in `result`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_physical_to_linear(
+    accessor: *const MemoryAccessor,
+    physical: u64,
+    result: *mut u64,
+    capacity: usize,
+) -> usize {
+    let linears = unsafe { (*accessor).physical_to_linear(physical) };
+    if !result.is_null() {
+        let count = linears.len().min(capacity);
+        unsafe {
+            ptr::copy_nonoverlapping(linears.as_ptr(), result, count);
+        }
+    }
+    linears.len()
+}
+
+/// Copy the register state into `result`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_get_state(accessor: *const MemoryAccessor, result: *mut CpuState) {
+    unsafe {
+        *result = (*accessor).cpu_state();
+    }
+}
+
+/// Load the register state from `state`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_set_state(accessor: *mut MemoryAccessor, state: *const CpuState) {
+    unsafe { (*accessor).set_cpu_state(&*state) }
+}
+
+/// Write a save-state file to the NUL-terminated `path`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_save_state(accessor: *const MemoryAccessor, path: *const c_char) -> u32 {
+    match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => unsafe { (*accessor).save_state_file(Path::new(path)) },
+        Err(_) => SAVE_STATE_IO_ERROR,
+    }
+}
+
+/// Load a save-state file from the NUL-terminated `path`. Returns a
+/// `SAVE_STATE_*` status; the machine is unchanged unless it is 0.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_load_state(accessor: *mut MemoryAccessor, path: *const c_char) -> u32 {
+    match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => unsafe { (*accessor).load_state_file(Path::new(path)) },
+        Err(_) => SAVE_STATE_IO_ERROR,
+    }
+}
+
+/// Start recording nondeterministic inputs.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_replay_record(accessor: *mut MemoryAccessor) {
+    unsafe { (*accessor).start_recording() }
+}
+
+/// Start replaying the log file at the NUL-terminated `path`. Returns a
+/// `REPLAY_LOG_*` status.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_replay_load(accessor: *mut MemoryAccessor, path: *const c_char) -> u32 {
+    match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => unsafe { (*accessor).load_replay_log(Path::new(path)) },
+        Err(_) => REPLAY_LOG_IO_ERROR,
+    }
+}
+
+/// Write the recorded log to the NUL-terminated `path`. Returns a
+/// `REPLAY_LOG_*` status.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_replay_save(accessor: *const MemoryAccessor, path: *const c_char) -> u32 {
+    match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => unsafe { (*accessor).save_replay_log(Path::new(path)) },
+        Err(_) => REPLAY_LOG_IO_ERROR,
+    }
+}
+
+/// Stop recording or replaying.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_replay_stop(accessor: *mut MemoryAccessor) {
+    unsafe { (*accessor).stop_replay() }
+}
+
+/// Current mode: 0=off, 1=recording, 2=replaying.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_replay_mode(accessor: *const MemoryAccessor) -> u32 {
+    unsafe { (*accessor).replay_mode() }
+}
+
+/// Count one retired instruction.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_retire_instruction(accessor: *mut MemoryAccessor) {
+    unsafe { (*accessor).retire_instruction() }
+}
+
+/// Instructions retired since recording or replay started.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_instruction_count(accessor: *const MemoryAccessor) -> u64 {
+    unsafe { (*accessor).instruction_count() }
+}
+
+/// Pass a nondeterministic input (e.g. an MMIO read result) through the
+/// recorder. `result` receives the value the guest should observe.
+/// Returns 0, or 1/2 when a replay diverged/ran out of events.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_replay_input(
+    accessor: *mut MemoryAccessor,
+    kind: u32,
+    address: u64,
+    size: u32,
+    value: u64,
+    result: *mut u64,
+) -> u32 {
+    let (observed, status) = unsafe { (*accessor).replay_input(kind, address, size, value) };
+    unsafe {
+        *result = observed;
+    }
+    status
+}
+
+/// Record an interrupt about to be delivered.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_record_interrupt(accessor: *mut MemoryAccessor, vector: u8) {
+    unsafe { (*accessor).record_interrupt(vector) }
+}
+
+/// While replaying, the vector of the interrupt due at this instruction
+/// boundary, or -1.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_replay_interrupt(accessor: *mut MemoryAccessor) -> i32 {
+    unsafe { (*accessor).replay_interrupt() }.map_or(-1, i32::from)
+}
+
+/// RDTSC, recorded or replayed.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_read_tsc(accessor: *mut MemoryAccessor) -> u64 {
+    unsafe { (*accessor).read_tsc() }
+}
+
+/// Start tracing accesses of the types in `access_mask` (1=R, 2=W, 4=X)
+/// to the NUL-terminated `path`.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_trace_start(
+    accessor: *mut MemoryAccessor,
+    path: *const c_char,
+    access_mask: u16,
+) -> bool {
+    match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => unsafe { (*accessor).start_trace(Path::new(path), access_mask) },
+        Err(_) => false,
+    }
+}
+
+/// Add an inclusive linear range filter to the running trace.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_trace_add_range(accessor: *mut MemoryAccessor, start: u64, end: u64) {
+    unsafe { (*accessor).add_trace_range(start, end) }
+}
+
+/// Flush and close the trace. Returns false if a write failed.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_trace_stop(accessor: *mut MemoryAccessor) -> bool {
+    unsafe { (*accessor).stop_trace() }
+}
+
+/// Convert the trace log at `input` to text at `output`. Returns the number
+/// of records, or -1 on error.
+#[no_mangle]
+pub unsafe extern "C" fn memory_trace_convert(input: *const c_char, output: *const c_char) -> i64 {
+    let (Ok(input), Ok(output)) = (unsafe { CStr::from_ptr(input) }.to_str(), unsafe { CStr::from_ptr(output) }.to_str())
+    else {
+        return -1;
+    };
+    convert_trace_log(Path::new(input), Path::new(output)).map_or(-1, |count| count as i64)
+}
+
+/// Fetch up to `max_len` (at most 15) code bytes at `linear` with
+/// instruction-fetch semantics.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_fetch_code(
+    accessor: *mut MemoryAccessor,
+    linear: u64,
+    max_len: usize,
+    is_user: bool,
+    paging_enabled: bool,
+    linear_mask: u64,
+    result: *mut CodeWindow,
+) {
+    unsafe {
+        *result = (*accessor).fetch_code(linear, max_len, is_user, paging_enabled, linear_mask);
+    }
+}
+
+/// Offset of a decoded instruction's memory operand, wrapped to its
+/// address size. `next_ip` is the base for RIP-relative operands.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_effective_offset(
+    accessor: *const MemoryAccessor,
+    instruction: *const DecodedInstruction,
+    next_ip: u64,
+) -> u64 {
+    unsafe { (*accessor).effective_offset(&*instruction, next_ip) }
+}
+
+/// Offset and segmented linear address of a decoded instruction's memory
+/// operand. kind: 0=read, 1=write, 2=execute. size is in bits.
+#[no_mangle]
+pub unsafe extern "C" fn memory_accessor_effective_address(
+    accessor: *const MemoryAccessor,
+    instruction: *const DecodedInstruction,
+    next_ip: u64,
+    size: u32,
+    kind: u32,
+    result: *mut EffectiveAddress,
+) {
+    unsafe {
+        *result = (*accessor).effective_address(&*instruction, next_ip, size, SegmentAccess::from_raw(kind));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,314 @@
+//! Model-specific register file.
+//!
+//! RDMSR/WRMSR go through `read_msr`/`write_msr`, which reject unknown
+//! indices and reserved bits with #GP(0). EFER lives in `efer`, and
+//! FS_BASE/GS_BASE are the hidden bases of the FS/GS segment caches, so a
+//! WRMSR to either takes effect in address translation immediately. The
+//! guest's RDMSR goes through `rdmsr`, which reads the TSC via `read_tsc` so
+//! record/replay covers it exactly like RDTSC. The identification and
+//! feature MSRs that firmware and kernels read unconditionally
+//! (PLATFORM_ID, FEATURE_CONTROL, MISC_ENABLE, ...) are modelled with fixed
+//! values so those probes do not fault.
+
+use std::time::Instant;
+
+use super::fault::{pack_fault, VECTOR_GP};
+use super::segment::{SEGMENT_FS, SEGMENT_GS};
+use super::MemoryAccessor;
+
+pub const MSR_TSC: u32 = 0x10;
+pub const MSR_PLATFORM_ID: u32 = 0x17;
+pub const MSR_APIC_BASE: u32 = 0x1B;
+pub const MSR_FEATURE_CONTROL: u32 = 0x3A;
+pub const MSR_BIOS_SIGN_ID: u32 = 0x8B;
+pub const MSR_PLATFORM_INFO: u32 = 0xCE;
+pub const MSR_MTRRCAP: u32 = 0xFE;
+pub const MSR_SYSENTER_CS: u32 = 0x174;
+pub const MSR_SYSENTER_ESP: u32 = 0x175;
+pub const MSR_SYSENTER_EIP: u32 = 0x176;
+pub const MSR_MCG_CAP: u32 = 0x179;
+pub const MSR_MCG_STATUS: u32 = 0x17A;
+pub const MSR_MISC_ENABLE: u32 = 0x1A0;
+/// IA32_MTRR_PHYSBASE0; PHYSBASEn/PHYSMASKn pairs follow up to 0x20F.
+pub const MSR_MTRR_PHYSBASE0: u32 = 0x200;
+pub const MSR_MTRR_FIX64K_00000: u32 = 0x250;
+pub const MSR_MTRR_FIX16K_80000: u32 = 0x258;
+pub const MSR_MTRR_FIX16K_A0000: u32 = 0x259;
+/// IA32_MTRR_FIX4K_C0000; the remaining 4K ranges follow up to 0x26F.
+pub const MSR_MTRR_FIX4K_C0000: u32 = 0x268;
+pub const MSR_PAT: u32 = 0x277;
+pub const MSR_MTRR_DEF_TYPE: u32 = 0x2FF;
+pub const MSR_EFER: u32 = 0xC000_0080;
+pub const MSR_STAR: u32 = 0xC000_0081;
+pub const MSR_LSTAR: u32 = 0xC000_0082;
+pub const MSR_CSTAR: u32 = 0xC000_0083;
+pub const MSR_SFMASK: u32 = 0xC000_0084;
+pub const MSR_FS_BASE: u32 = 0xC000_0100;
+pub const MSR_GS_BASE: u32 = 0xC000_0101;
+pub const MSR_KERNEL_GS_BASE: u32 = 0xC000_0102;
+pub const MSR_TSC_AUX: u32 = 0xC000_0103;
+
+/// EFER bits.
+pub const EFER_SCE: u64 = 1 << 0;
+pub const EFER_LME: u64 = 1 << 8;
+pub const EFER_LMA: u64 = 1 << 10;
+pub const EFER_NXE: u64 = 1 << 11;
+
+/// Physical address width reported by CPUID 0x80000008.
+pub const MAX_PHYSICAL_ADDRESS_BITS: u32 = 36;
+
+const VARIABLE_MTRR_COUNT: usize = 8;
+const FIXED_MTRR_COUNT: usize = 11;
+
+/// 8 variable ranges, fixed ranges and write-combining supported.
+const MTRRCAP_VALUE: u64 = (VARIABLE_MTRR_COUNT as u64) | (1 << 8) | (1 << 10);
+/// Power-on PAT: WB, WT, UC-, UC repeated.
+const PAT_RESET: u64 = 0x0007_0406_0007_0406;
+/// Power-on APIC_BASE: 0xFEE00000, global enable, bootstrap processor.
+const APIC_BASE_RESET: u64 = 0xFEE0_0000 | (1 << 11) | (1 << 8);
+/// FEATURE_CONTROL locked by firmware with VMX and SMX off.
+const FEATURE_CONTROL_VALUE: u64 = 1;
+/// Power-on MISC_ENABLE: fast strings on, BTS and PEBS unavailable.
+const MISC_ENABLE_RESET: u64 = (1 << 0) | (1 << 11) | (1 << 12);
+/// Fast strings, automatic thermal control, EIST, limit CPUID maxval and
+/// xTPR disable; the rest of MISC_ENABLE is read-only.
+const MISC_ENABLE_WRITABLE: u64 = (1 << 0) | (1 << 3) | (1 << 16) | (1 << 22) | (1 << 23);
+
+const PHYSICAL_ADDRESS_MASK: u64 = (1 << MAX_PHYSICAL_ADDRESS_BITS) - 1;
+const EFER_WRITABLE: u64 = EFER_SCE | EFER_LME | EFER_NXE;
+const APIC_BASE_WRITABLE: u64 = (PHYSICAL_ADDRESS_MASK & !0xFFF) | (1 << 11) | (1 << 10) | (1 << 8);
+const MTRR_DEF_TYPE_WRITABLE: u64 = 0xFF | (1 << 10) | (1 << 11);
+const MTRR_PHYSBASE_WRITABLE: u64 = (PHYSICAL_ADDRESS_MASK & !0xFFF) | 0xFF;
+const MTRR_PHYSMASK_WRITABLE: u64 = (PHYSICAL_ADDRESS_MASK & !0xFFF) | (1 << 11);
+
+/// MSRs other than EFER and the FS/GS bases.
+pub(crate) struct MsrFile {
+    star: u64,
+    lstar: u64,
+    cstar: u64,
+    sfmask: u64,
+    kernel_gs_base: u64,
+    tsc_aux: u64,
+    apic_base: u64,
+    pat: u64,
+    sysenter_cs: u64,
+    sysenter_esp: u64,
+    sysenter_eip: u64,
+    misc_enable: u64,
+    mtrr_def_type: u64,
+    /// PHYSBASE0, PHYSMASK0, ..., PHYSBASE7, PHYSMASK7.
+    mtrr_variable: [u64; VARIABLE_MTRR_COUNT * 2],
+    mtrr_fixed: [u64; FIXED_MTRR_COUNT],
+    /// Added to the microseconds elapsed since `tsc_epoch` to form the TSC.
+    tsc_offset: u64,
+    tsc_epoch: Instant,
+}
+
+impl MsrFile {
+    pub(crate) fn new() -> Self {
+        MsrFile {
+            star: 0,
+            lstar: 0,
+            cstar: 0,
+            sfmask: 0,
+            kernel_gs_base: 0,
+            tsc_aux: 0,
+            apic_base: APIC_BASE_RESET,
+            pat: PAT_RESET,
+            sysenter_cs: 0,
+            sysenter_esp: 0,
+            sysenter_eip: 0,
+            misc_enable: MISC_ENABLE_RESET,
+            mtrr_def_type: 0,
+            mtrr_variable: [0; VARIABLE_MTRR_COUNT * 2],
+            mtrr_fixed: [0; FIXED_MTRR_COUNT],
+            tsc_offset: 0,
+            tsc_epoch: Instant::now(),
+        }
+    }
+
+    /// Microsecond-granular counter, matching the PHP `Tsc` helper's units.
+    fn elapsed_ticks(&self) -> u64 {
+        self.tsc_epoch.elapsed().as_micros() as u64
+    }
+
+    fn fixed_mtrr_index(index: u32) -> Option<usize> {
+        match index {
+            MSR_MTRR_FIX64K_00000 => Some(0),
+            MSR_MTRR_FIX16K_80000 => Some(1),
+            MSR_MTRR_FIX16K_A0000 => Some(2),
+            MSR_MTRR_FIX4K_C0000..=0x26F => Some(3 + (index - MSR_MTRR_FIX4K_C0000) as usize),
+            _ => None,
+        }
+    }
+
+    fn variable_mtrr_index(index: u32) -> Option<usize> {
+        let last = MSR_MTRR_PHYSBASE0 + (VARIABLE_MTRR_COUNT as u32) * 2 - 1;
+        (MSR_MTRR_PHYSBASE0..=last)
+            .contains(&index)
+            .then(|| (index - MSR_MTRR_PHYSBASE0) as usize)
+    }
+}
+
+/// UC, WC, WT, WP and WB.
+#[inline(always)]
+fn is_valid_mtrr_type(memory_type: u8) -> bool {
+    matches!(memory_type, 0 | 1 | 4 | 5 | 6)
+}
+
+/// MTRR types plus UC- (7).
+#[inline(always)]
+fn is_valid_pat_type(memory_type: u8) -> bool {
+    matches!(memory_type, 0 | 1 | 4 | 5 | 6 | 7)
+}
+
+impl MemoryAccessor {
+    /// RDMSR. Returns (value, error) with #GP(0) for unknown MSRs.
+    pub fn read_msr(&self, index: u32) -> (u64, u32) {
+        let msrs = &self.msrs;
+        let value = match index {
+            MSR_TSC => msrs.elapsed_ticks().wrapping_add(msrs.tsc_offset),
+            MSR_PLATFORM_ID | MSR_BIOS_SIGN_ID | MSR_PLATFORM_INFO | MSR_MCG_CAP | MSR_MCG_STATUS => 0,
+            MSR_APIC_BASE => msrs.apic_base,
+            MSR_FEATURE_CONTROL => FEATURE_CONTROL_VALUE,
+            MSR_MTRRCAP => MTRRCAP_VALUE,
+            MSR_SYSENTER_CS => msrs.sysenter_cs,
+            MSR_SYSENTER_ESP => msrs.sysenter_esp,
+            MSR_SYSENTER_EIP => msrs.sysenter_eip,
+            MSR_MISC_ENABLE => msrs.misc_enable,
+            MSR_PAT => msrs.pat,
+            MSR_MTRR_DEF_TYPE => msrs.mtrr_def_type,
+            MSR_EFER => self.efer,
+            MSR_STAR => msrs.star,
+            MSR_LSTAR => msrs.lstar,
+            MSR_CSTAR => msrs.cstar,
+            MSR_SFMASK => msrs.sfmask,
+            MSR_FS_BASE => self.segments[SEGMENT_FS].base,
+            MSR_GS_BASE => self.segments[SEGMENT_GS].base,
+            MSR_KERNEL_GS_BASE => msrs.kernel_gs_base,
+            MSR_TSC_AUX => msrs.tsc_aux,
+            _ => {
+                if let Some(slot) = MsrFile::variable_mtrr_index(index) {
+                    msrs.mtrr_variable[slot]
+                } else if let Some(slot) = MsrFile::fixed_mtrr_index(index) {
+                    msrs.mtrr_fixed[slot]
+                } else {
+                    return (0, pack_fault(VECTOR_GP, 0));
+                }
+            }
+        };
+        (value, 0)
+    }
+
+    /// RDMSR as executed by the guest: IA32_TIME_STAMP_COUNTER comes from
+    /// `read_tsc` (recorded or replayed), everything else from `read_msr`.
+    pub fn rdmsr(&mut self, index: u32) -> (u64, u32) {
+        if index == MSR_TSC {
+            return (self.read_tsc(), 0);
+        }
+        self.read_msr(index)
+    }
+
+    /// WRMSR. Returns 0, or #GP(0) for unknown/read-only MSRs, reserved
+    /// bits, non-canonical addresses and invalid memory types.
+    pub fn write_msr(&mut self, index: u32, value: u64) -> u32 {
+        let gp = pack_fault(VECTOR_GP, 0);
+        let canonical = self.is_canonical(value);
+        let msrs = &mut self.msrs;
+
+        match index {
+            MSR_TSC => msrs.tsc_offset = value.wrapping_sub(msrs.elapsed_ticks()),
+            MSR_APIC_BASE => {
+                if (value & !APIC_BASE_WRITABLE) != 0 {
+                    return gp;
+                }
+                msrs.apic_base = value;
+            }
+            MSR_SYSENTER_CS => msrs.sysenter_cs = value & 0xFFFF_FFFF,
+            // Microcode update signature: the written value is discarded.
+            MSR_BIOS_SIGN_ID => {}
+            MSR_MCG_STATUS => {
+                if value != 0 {
+                    return gp;
+                }
+            }
+            MSR_MISC_ENABLE => {
+                if ((value ^ msrs.misc_enable) & !MISC_ENABLE_WRITABLE) != 0 {
+                    return gp;
+                }
+                msrs.misc_enable = value;
+            }
+            MSR_SYSENTER_ESP | MSR_SYSENTER_EIP | MSR_LSTAR | MSR_CSTAR | MSR_KERNEL_GS_BASE
+            | MSR_FS_BASE | MSR_GS_BASE => {
+                if !canonical {
+                    return gp;
+                }
+                match index {
+                    MSR_SYSENTER_ESP => msrs.sysenter_esp = value,
+                    MSR_SYSENTER_EIP => msrs.sysenter_eip = value,
+                    MSR_LSTAR => msrs.lstar = value,
+                    MSR_CSTAR => msrs.cstar = value,
+                    MSR_KERNEL_GS_BASE => msrs.kernel_gs_base = value,
+                    MSR_FS_BASE => self.segments[SEGMENT_FS].base = value,
+                    _ => self.segments[SEGMENT_GS].base = value,
+                }
+            }
+            MSR_PAT => {
+                if !value.to_le_bytes().iter().all(|t| is_valid_pat_type(*t)) {
+                    return gp;
+                }
+                msrs.pat = value;
+            }
+            MSR_MTRR_DEF_TYPE => {
+                if (value & !MTRR_DEF_TYPE_WRITABLE) != 0 || !is_valid_mtrr_type(value as u8) {
+                    return gp;
+                }
+                msrs.mtrr_def_type = value;
+            }
+            MSR_EFER => return self.write_efer_checked(value),
+            MSR_STAR => msrs.star = value,
+            MSR_SFMASK | MSR_TSC_AUX => {
+                if (value >> 32) != 0 {
+                    return gp;
+                }
+                if index == MSR_SFMASK {
+                    msrs.sfmask = value;
+                } else {
+                    msrs.tsc_aux = value;
+                }
+            }
+            _ => {
+                if let Some(slot) = MsrFile::variable_mtrr_index(index) {
+                    let is_mask = (slot & 1) != 0;
+                    let writable = if is_mask { MTRR_PHYSMASK_WRITABLE } else { MTRR_PHYSBASE_WRITABLE };
+                    if (value & !writable) != 0 || (!is_mask && !is_valid_mtrr_type(value as u8)) {
+                        return gp;
+                    }
+                    msrs.mtrr_variable[slot] = value;
+                } else if let Some(slot) = MsrFile::fixed_mtrr_index(index) {
+                    if !value.to_le_bytes().iter().all(|t| is_valid_mtrr_type(*t)) {
+                        return gp;
+                    }
+                    msrs.mtrr_fixed[slot] = value;
+                } else {
+                    // Unknown MSRs and read-only ones such as MTRRCAP and FEATURE_CONTROL.
+                    return gp;
+                }
+            }
+        }
+        0
+    }
+
+    /// WRMSR to EFER: LMA is read-only, and LME cannot change while paging is on.
+    fn write_efer_checked(&mut self, value: u64) -> u32 {
+        let gp = pack_fault(VECTOR_GP, 0);
+        if (value & !(EFER_WRITABLE | EFER_LMA)) != 0 {
+            return gp;
+        }
+        if self.paging_enabled() && ((value ^ self.efer) & EFER_LME) != 0 {
+            return gp;
+        }
+        self.write_efer((value & EFER_WRITABLE) | (self.efer & EFER_LMA));
+        0
+    }
+}
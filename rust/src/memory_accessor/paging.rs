@@ -1,4 +1,253 @@
-use super::MemoryAccessor;
+use super::fault::{pack_fault, VECTOR_PF};
+use super::fault::{VECTOR_AC, VECTOR_GP};
+use super::msr::{EFER_LME, EFER_NXE, MAX_PHYSICAL_ADDRESS_BITS};
+use super::{MemoryAccessor, CR0_AM, CR0_PG, CR0_WP, CR4_LA57, CR4_PAE, CR4_PGE, CR4_PSE, CR4_SMAP, CR4_SMEP, EFLAGS_AC};
+
+/// #PF error-code bits.
+pub const PF_PRESENT: u32 = 1 << 0;
+pub const PF_WRITE: u32 = 1 << 1;
+pub const PF_USER: u32 = 1 << 2;
+pub const PF_RESERVED: u32 = 1 << 3;
+pub const PF_FETCH: u32 = 1 << 4;
+
+/// IA-32e linear-address masks for 4-level and 5-level paging.
+pub const LINEAR_MASK_4_LEVEL: u64 = (1 << 48) - 1;
+pub const LINEAR_MASK_5_LEVEL: u64 = (1 << 57) - 1;
+
+/// Paging-entry bits shared by all formats.
+pub(crate) const PAGE_PRESENT: u64 = 1 << 0;
+pub(crate) const PAGE_WRITABLE: u64 = 1 << 1;
+pub(crate) const PAGE_USER: u64 = 1 << 2;
+pub(crate) const PAGE_ACCESSED: u64 = 1 << 5;
+pub(crate) const PAGE_DIRTY: u64 = 1 << 6;
+pub(crate) const PAGE_LARGE: u64 = 1 << 7;
+pub(crate) const PAGE_GLOBAL: u64 = 1 << 8;
+/// Execute-disable bit of PAE and IA-32e paging entries.
+pub(crate) const PAGE_XD: u64 = 1 << 63;
+
+/// Reserved bits, checked on every present entry of a walk.
+///
+/// Address bits from MAXPHYADDR up to bit 51 (IA-32e) or bit 62 (PAE).
+pub(crate) const RESERVED_ADDRESS_IA32E: u64 = ((1 << 52) - 1) & !((1 << MAX_PHYSICAL_ADDRESS_BITS) - 1);
+pub(crate) const RESERVED_ADDRESS_PAE: u64 = ((1 << 63) - 1) & !((1 << MAX_PHYSICAL_ADDRESS_BITS) - 1);
+/// PAE PDPTE: bits 2:1 and 8:5 (PDPTEs carry no R/W, U/S or A bits),
+/// plus bit 63 (no XD at this level).
+pub(crate) const RESERVED_PDPTE_PAE: u64 = 0x1E6 | PAGE_XD;
+/// Low address bits of large mappings: 20:13 for 2MB, 29:13 for 1GB.
+pub(crate) const RESERVED_LARGE_2M: u64 = 0x1F_E000;
+pub(crate) const RESERVED_LARGE_1G: u64 = 0x3FFF_E000;
+/// 32-bit 4MB PDE: bit 21 and the PSE-36 address bits above MAXPHYADDR.
+pub(crate) const RESERVED_LARGE_4M: u64 = ((0xFF << 13) & !((1 << (MAX_PHYSICAL_ADDRESS_BITS - 32 + 13)) - 1)) | (1 << 21);
+
+/// The access being translated, used for permission checks and to build
+/// the #PF error code.
+#[derive(Clone, Copy)]
+struct PageAccess {
+    is_write: bool,
+    is_user: bool,
+    is_fetch: bool,
+    /// Set the I/D bit of the error code for this fetch.
+    report_fetch: bool,
+    /// EFER.NXE with PAE-format entries: XD bits are honoured.
+    nxe: bool,
+    /// CR0.WP: supervisor writes honour R/W.
+    wp: bool,
+    /// CR4.SMEP: supervisor fetches from user pages fault.
+    smep: bool,
+    /// CR4.SMAP with EFLAGS.AC clear: supervisor data accesses to user pages fault.
+    smap: bool,
+}
+
+/// Rights granted by a walk so far: U/S and R/W are ANDed across levels,
+/// XD is ORed.
+#[derive(Clone, Copy)]
+pub(crate) struct PageRights {
+    pub(crate) user: bool,
+    pub(crate) writable: bool,
+    pub(crate) execute_disable: bool,
+}
+
+impl PageRights {
+    pub(crate) const ALL: PageRights = PageRights { user: true, writable: true, execute_disable: false };
+
+    #[inline(always)]
+    pub(crate) fn merge(self, entry: u64) -> Self {
+        PageRights {
+            user: self.user && (entry & PAGE_USER) != 0,
+            writable: self.writable && (entry & PAGE_WRITABLE) != 0,
+            execute_disable: self.execute_disable || (entry & PAGE_XD) != 0,
+        }
+    }
+}
+
+/// Deepest walk: PML5E, PML4E, PDPTE, PDE, PTE.
+pub const MAX_WALK_LEVELS: usize = 5;
+
+/// One paging-structure entry visited by a walk.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageWalkLevel {
+    /// Physical address of the entry (of its memory copy for PAE PDPTEs).
+    pub entry_address: u64,
+    pub entry: u64,
+}
+
+/// One level of a paging format.
+pub(crate) struct WalkLevel {
+    /// Position of this level's index in the linear address.
+    pub(crate) shift: u32,
+    pub(crate) index_bits: u32,
+    /// PS makes an entry at this level a leaf.
+    pub(crate) large: bool,
+    /// Reserved bits of any present entry.
+    reserved: u64,
+    /// Further reserved bits of a large-page entry.
+    large_reserved: u64,
+}
+
+const fn level(shift: u32, index_bits: u32, large: bool, reserved: u64, large_reserved: u64) -> WalkLevel {
+    WalkLevel { shift, index_bits, large, reserved, large_reserved }
+}
+
+const LEVELS_32: [WalkLevel; 2] = [level(22, 10, false, 0, 0), level(12, 10, false, 0, 0)];
+const LEVELS_32_PSE: [WalkLevel; 2] = [level(22, 10, true, 0, RESERVED_LARGE_4M), level(12, 10, false, 0, 0)];
+const LEVELS_PAE: [WalkLevel; 3] = [
+    level(30, 2, false, RESERVED_PDPTE_PAE | RESERVED_ADDRESS_PAE, 0),
+    level(21, 9, true, RESERVED_ADDRESS_PAE, RESERVED_LARGE_2M),
+    level(12, 9, false, RESERVED_ADDRESS_PAE, 0),
+];
+/// PML5 first; 4-level paging starts at index 1. PS is reserved in PML5Es
+/// and PML4Es.
+const LEVELS_IA32E: [WalkLevel; 5] = [
+    level(48, 9, false, RESERVED_ADDRESS_IA32E | PAGE_LARGE, 0),
+    level(39, 9, false, RESERVED_ADDRESS_IA32E | PAGE_LARGE, 0),
+    level(30, 9, true, RESERVED_ADDRESS_IA32E, RESERVED_LARGE_1G),
+    level(21, 9, true, RESERVED_ADDRESS_IA32E, RESERVED_LARGE_2M),
+    level(12, 9, false, RESERVED_ADDRESS_IA32E, 0),
+];
+
+/// The paging structures in use under the current CR4/EFER.
+#[derive(Clone, Copy)]
+pub(crate) struct PagingFormat {
+    pub(crate) levels: &'static [WalkLevel],
+    /// 8-byte entries (PAE and IA-32e).
+    wide: bool,
+    /// The top level comes from the PDPTE registers.
+    pub(crate) pdpte_registers: bool,
+    /// IA-32e linear-address width (48 or 57), 0 otherwise.
+    linear_bits: u32,
+    /// EFER.NXE: XD is a right rather than a reserved bit.
+    nxe: bool,
+}
+
+impl PagingFormat {
+    #[inline(always)]
+    pub(crate) fn address_mask(self) -> u64 {
+        if self.wide {
+            ((1 << MAX_PHYSICAL_ADDRESS_BITS) - 1) & !0xFFF
+        } else {
+            0xFFFF_F000
+        }
+    }
+
+    pub(crate) fn reserved_bits(self, level: &WalkLevel, large_leaf: bool) -> u64 {
+        let mut reserved = level.reserved;
+        if large_leaf {
+            reserved |= level.large_reserved;
+        }
+        if self.wide && !self.nxe {
+            reserved |= PAGE_XD;
+        }
+        reserved
+    }
+
+    /// Sign-extend an IA-32e linear address; other formats use it as is.
+    #[inline(always)]
+    pub(crate) fn canonical(self, linear: u64) -> u64 {
+        if self.linear_bits == 0 {
+            return linear;
+        }
+        let unused = 64 - self.linear_bits;
+        (((linear << unused) as i64) >> unused) as u64
+    }
+}
+
+/// How `walk_tables` ended.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TableWalkEnd {
+    Mapped,
+    NotPresent,
+    ReservedBit,
+}
+
+/// The entries visited by `walk_tables` and what they map.
+#[derive(Clone, Copy)]
+pub(crate) struct TableWalk {
+    /// Entries from the top level down; the last one ended the walk.
+    pub(crate) levels: [PageWalkLevel; MAX_WALK_LEVELS],
+    pub(crate) level_count: usize,
+    pub(crate) end: TableWalkEnd,
+    /// Combined rights of the levels walked; PAE PDPTEs carry none.
+    pub(crate) rights: PageRights,
+    pub(crate) physical: u64,
+    pub(crate) page_size: u64,
+}
+
+impl TableWalk {
+    /// The entry that ended the walk.
+    #[inline(always)]
+    pub(crate) fn last_entry(&self) -> u64 {
+        self.levels[self.level_count - 1].entry
+    }
+}
+
+impl PageAccess {
+    fn error_code(self, present: bool) -> u32 {
+        let mut code = 0;
+        if present {
+            code |= PF_PRESENT;
+        }
+        if self.is_write {
+            code |= PF_WRITE;
+        }
+        if self.is_user {
+            code |= PF_USER;
+        }
+        if self.report_fetch {
+            code |= PF_FETCH;
+        }
+        pack_fault(VECTOR_PF, code)
+    }
+
+    /// #PF for a non-present entry.
+    #[inline(always)]
+    fn not_present(self) -> u32 {
+        self.error_code(false)
+    }
+
+    /// #PF for a rights violation on a present entry.
+    #[inline(always)]
+    fn protection(self) -> u32 {
+        self.error_code(true)
+    }
+
+    /// Check the combined rights of a completed walk. Returns 0 or a
+    /// protection #PF.
+    fn check(self, rights: PageRights) -> u32 {
+        let denied = if self.is_user {
+            !rights.user || (self.is_write && !rights.writable)
+        } else {
+            (self.is_write && !rights.writable && self.wp)
+                || (rights.user && self.is_fetch && self.smep)
+                || (rights.user && !self.is_fetch && self.smap)
+        };
+        if denied || (self.is_fetch && self.nxe && rights.execute_disable) {
+            self.protection()
+        } else {
+            0
+        }
+    }
+}
 
 impl MemoryAccessor {
     /// Translate linear address to physical address through paging.
@@ -20,365 +269,341 @@ impl MemoryAccessor {
         }
 
         let cr4 = self.control_registers[4];
-        let pse = (cr4 & (1 << 4)) != 0;
-        let pae = (cr4 & (1 << 5)) != 0;
-        let lme = (self.efer & (1 << 8)) != 0;
+        let pae = (cr4 & CR4_PAE) != 0;
+
+        let nxe = (self.efer & EFER_NXE) != 0;
+        let smep = (cr4 & CR4_SMEP) != 0;
+        let access = PageAccess {
+            is_write,
+            is_user,
+            is_fetch: self.instruction_fetch,
+            // The I/D bit is only defined when execute-disable or SMEP is in effect.
+            report_fetch: self.instruction_fetch && ((pae && nxe) || smep),
+            nxe: pae && nxe,
+            wp: (self.control_registers[0] & CR0_WP) != 0,
+            smep,
+            smap: (cr4 & CR4_SMAP) != 0 && (self.system_flags & EFLAGS_AC) == 0,
+        };
 
-        let (physical, err) = if pae {
-            if lme {
-                self.translate_linear_ia32e(linear, is_write, is_user)
-            } else {
-                self.translate_linear_pae(linear, is_write, is_user)
+        if let Some((physical_page, rights)) = self.tlb.lookup(linear, is_write) {
+            if access.check(rights) == 0 {
+                return (physical_page | (linear & 0xFFF), 0);
             }
-        } else {
-            self.translate_linear_32(linear, is_write, is_user, pse)
+        }
+
+        let format = self.paging_format();
+        let walk = self.walk_tables(format, linear);
+        let err = match walk.end {
+            TableWalkEnd::NotPresent => access.not_present(),
+            TableWalkEnd::ReservedBit => access.protection() | PF_RESERVED,
+            TableWalkEnd::Mapped => access.check(walk.rights),
         };
 
-        // On a page fault, CR2 is set to the faulting linear address.
+        // On a page fault, CR2 is set to the faulting linear address:
+        // canonical in IA-32e mode, 32-bit otherwise.
         if err != 0 {
-            let cr2 = if pae && lme {
-                // Canonicalize 48-bit linear address (sign-extend bit 47).
-                if (linear & (1u64 << 47)) != 0 {
-                    linear | 0xFFFF_0000_0000_0000
-                } else {
-                    linear
-                }
+            self.control_registers[2] = if format.linear_bits != 0 {
+                format.canonical(linear)
             } else {
-                // Non-IA32e: CR2 is 32-bit.
                 linear & 0xFFFF_FFFF
             };
-            self.control_registers[2] = cr2;
+            return (linear, err);
         }
 
-        (physical, err)
+        self.mark_accessed(format, &walk, is_write);
+        self.fill_tlb(linear, walk.physical, walk.page_size, walk.rights, walk.last_entry(), is_write);
+        (walk.physical, 0)
     }
 
-    /// 32-bit paging translation.
-    fn translate_linear_32(
-        &mut self,
-        linear: u64,
-        is_write: bool,
-        is_user: bool,
-        pse: bool,
-    ) -> (u64, u32) {
-        let cr3 = (self.control_registers[3] & 0xFFFFF000) as usize;
-        let linear = linear as usize;
-        let dir_index = (linear >> 22) & 0x3FF;
-        let table_index = (linear >> 12) & 0x3FF;
-        let offset = linear & 0xFFF;
-
-        let pde_addr = (cr3 + (dir_index * 4)) & 0xFFFFFFFF;
-        let pde = self.read_physical_32(pde_addr) as u64;
-
-        // Check PDE present
-        if (pde & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear as u64, (0x0E << 16) | err);
+    /// The paging format selected by CR4.PSE, CR4.PAE, CR4.LA57 and EFER.LME.
+    pub(crate) fn paging_format(&self) -> PagingFormat {
+        let cr4 = self.control_registers[4];
+        let nxe = (self.efer & EFER_NXE) != 0;
+        if (cr4 & CR4_PAE) == 0 {
+            let levels: &[WalkLevel] = if (cr4 & CR4_PSE) != 0 { &LEVELS_32_PSE } else { &LEVELS_32 };
+            PagingFormat { levels, wide: false, pdpte_registers: false, linear_bits: 0, nxe: false }
+        } else if (self.efer & EFER_LME) == 0 {
+            PagingFormat { levels: &LEVELS_PAE, wide: true, pdpte_registers: true, linear_bits: 0, nxe }
+        } else if (cr4 & CR4_LA57) != 0 {
+            PagingFormat { levels: &LEVELS_IA32E, wide: true, pdpte_registers: false, linear_bits: 57, nxe }
+        } else {
+            PagingFormat { levels: &LEVELS_IA32E[1..], wide: true, pdpte_registers: false, linear_bits: 48, nxe }
         }
+    }
 
-        // Check user access
-        if is_user && (pde & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear as u64, (0x0E << 16) | err);
+    /// Physical address of the top-level table.
+    #[inline(always)]
+    pub(crate) fn page_table_root(&self, format: PagingFormat) -> u64 {
+        let cr3 = self.control_registers[3];
+        if format.pdpte_registers {
+            cr3 & 0xFFFF_FFE0
+        } else {
+            cr3 & 0xFFFF_F000
         }
+    }
 
-        // Check write access
-        if is_write && (pde & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear as u64, (0x0E << 16) | err);
-        }
+    /// (entry address, entry) for `index` of the table at `table`.
+    pub(crate) fn read_table_entry(&self, format: PagingFormat, depth: usize, table: u64, index: usize) -> (u64, u64) {
+        let size = if format.wide { 8 } else { 4 };
+        let address = (table + (index * size) as u64) & 0xFFFF_FFFF;
+        let entry = if format.pdpte_registers && depth == 0 {
+            self.pdptes[index]
+        } else if format.wide {
+            self.read_physical_64(address as usize)
+        } else {
+            self.read_physical_32(address as usize) as u64
+        };
+        (address, entry)
+    }
 
-        // Handle 4MB page (PSE)
-        let is_4m = pse && ((pde & (1 << 7)) != 0);
-        if is_4m {
-            let base = (pde & 0xFFC00000) as usize;
-            let mut pde = pde;
-            pde |= 0x20; // Set accessed
-            if is_write {
-                pde |= 0x40; // Set dirty
+    /// Walk the paging structures for `linear`, stopping at the first
+    /// non-present entry or entry with reserved bits set.
+    ///
+    /// Nothing is written: accessed/dirty bits, CR2 and the TLB are up to
+    /// the caller, and access rights are collected but not checked.
+    /// Physical addresses are treated as 32-bit to match the MemoryStream model.
+    pub(crate) fn walk_tables(&self, format: PagingFormat, linear: u64) -> TableWalk {
+        let mut walk = TableWalk {
+            levels: [PageWalkLevel::default(); MAX_WALK_LEVELS],
+            level_count: 0,
+            end: TableWalkEnd::NotPresent,
+            rights: PageRights::ALL,
+            physical: 0,
+            page_size: 0,
+        };
+        let mut table = self.page_table_root(format);
+        for (depth, level) in format.levels.iter().enumerate() {
+            let index = ((linear >> level.shift) & ((1 << level.index_bits) - 1)) as usize;
+            let (entry_address, entry) = self.read_table_entry(format, depth, table, index);
+            walk.levels[depth] = PageWalkLevel { entry_address, entry };
+            walk.level_count = depth + 1;
+
+            if (entry & PAGE_PRESENT) == 0 {
+                return walk;
             }
-            self.write_physical_32(pde_addr, pde as u32);
-            let phys = ((base + (linear & 0x3FFFFF)) & 0xFFFFFFFF) as u64;
-            return (phys, 0);
+            let large_leaf = level.large && (entry & PAGE_LARGE) != 0;
+            if (entry & format.reserved_bits(level, large_leaf)) != 0 {
+                walk.end = TableWalkEnd::ReservedBit;
+                return walk;
+            }
+            // PAE PDPTEs carry no rights.
+            if !(format.pdpte_registers && depth == 0) {
+                walk.rights = walk.rights.merge(entry);
+            }
+            if large_leaf || depth + 1 == format.levels.len() {
+                let size = 1u64 << level.shift;
+                walk.end = TableWalkEnd::Mapped;
+                walk.page_size = size;
+                walk.physical = ((entry & format.address_mask() & !(size - 1)) + (linear & (size - 1))) & 0xFFFF_FFFF;
+                return walk;
+            }
+            table = entry & format.address_mask() & 0xFFFF_FFFF;
         }
+        unreachable!("the last level is always a leaf")
+    }
 
-        // Read PTE
-        let pte_addr = ((pde & 0xFFFFF000) as usize + (table_index * 4)) & 0xFFFFFFFF;
-        let pte = self.read_physical_32(pte_addr) as u64;
-
-        // Check PTE present
-        if (pte & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear as u64, (0x0E << 16) | err);
+    /// Set the accessed bit of every entry of a completed walk, and the
+    /// dirty bit of its leaf for a write.
+    fn mark_accessed(&mut self, format: PagingFormat, walk: &TableWalk, is_write: bool) {
+        for (depth, level) in walk.levels[..walk.level_count].iter().enumerate() {
+            // The PDPTE registers are not written back.
+            if format.pdpte_registers && depth == 0 {
+                continue;
+            }
+            let dirty = if is_write && depth + 1 == walk.level_count { PAGE_DIRTY } else { 0 };
+            let entry = level.entry | PAGE_ACCESSED | dirty;
+            if entry == level.entry {
+                continue;
+            }
+            if format.wide {
+                self.write_physical_64(level.entry_address as usize, entry);
+            } else {
+                self.write_physical_32(level.entry_address as usize, entry as u32);
+            }
         }
+    }
 
-        // Check user access
-        if is_user && (pte & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear as u64, (0x0E << 16) | err);
-        }
+    /// PAE paging outside IA-32e mode, where the walk starts from the
+    /// PDPTE registers instead of memory.
+    #[inline(always)]
+    pub(crate) fn uses_pae_pdptes(&self, cr0: u64, cr4: u64) -> bool {
+        (cr0 & CR0_PG) != 0 && (cr4 & CR4_PAE) != 0 && (self.efer & EFER_LME) == 0
+    }
 
-        // Check write access
-        if is_write && (pte & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear as u64, (0x0E << 16) | err);
+    /// Read the four PDPTEs of the table at CR3[31:5]. Returns #GP(0) in
+    /// the error slot when a present entry has reserved bits set.
+    pub(crate) fn read_pdptes(&mut self, cr3: u64) -> ([u64; 4], u32) {
+        let base = (cr3 & 0xFFFF_FFE0) as usize;
+        let mut pdptes = [0; 4];
+        let mut err = 0;
+        for (i, pdpte) in pdptes.iter_mut().enumerate() {
+            *pdpte = self.read_physical_64(base + i * 8);
+            if (*pdpte & PAGE_PRESENT) != 0 && (*pdpte & (RESERVED_PDPTE_PAE | RESERVED_ADDRESS_PAE)) != 0 {
+                err = pack_fault(VECTOR_GP, 0);
+            }
         }
+        (pdptes, err)
+    }
 
-        // Set accessed/dirty bits
-        let mut pde = pde;
-        pde |= 0x20;
-        self.write_physical_32(pde_addr, pde as u32);
-
-        let mut pte = pte;
-        pte |= 0x20;
-        if is_write {
-            pte |= 0x40;
+    /// Load the PDPTE registers from memory when PAE paging is active.
+    pub(crate) fn reload_pdptes(&mut self) {
+        if self.uses_pae_pdptes(self.control_registers[0], self.control_registers[4]) {
+            self.pdptes = self.read_pdptes(self.control_registers[3]).0;
         }
-        self.write_physical_32(pte_addr, pte as u32);
+    }
 
-        let phys = (((pte & 0xFFFFF000) as usize + offset) & 0xFFFFFFFF) as u64;
-        (phys, 0)
+    /// The PAE PDPTE registers as last loaded.
+    #[inline(always)]
+    pub fn pae_pdptes(&self) -> [u64; 4] {
+        self.pdptes
     }
 
-    /// PAE paging translation.
-    fn translate_linear_pae(
+    /// Cache a successful walk; `leaf` is the mapping entry (for D and G).
+    #[inline(always)]
+    fn fill_tlb(&mut self, linear: u64, physical: u64, page_size: u64, rights: PageRights, leaf: u64, is_write: bool) {
+        let global = (leaf & PAGE_GLOBAL) != 0 && (self.control_registers[4] & CR4_PGE) != 0;
+        let dirty = is_write || (leaf & PAGE_DIRTY) != 0;
+        self.tlb.insert(linear, physical, page_size, rights, dirty, global);
+    }
+
+    /// Translate both pages of a `bytes`-wide access at `linear`.
+    ///
+    /// Returns the physical address of the first byte, the physical address
+    /// of the next page (only meaningful when the access crosses into it),
+    /// the number of bytes in the first page, and the error. Both pages are
+    /// translated before the caller touches memory, so a #PF on the second
+    /// page leaves the first untouched; CR2 then holds the second page's
+    /// first linear address.
+    fn translate_access(
         &mut self,
         linear: u64,
+        bytes: usize,
         is_write: bool,
         is_user: bool,
-    ) -> (u64, u32) {
-        let cr3 = (self.control_registers[3] & 0xFFFFF000) as usize;
-        let linear_usize = linear as usize;
-        let pdp_index = (linear_usize >> 30) & 0x3;
-        let dir_index = (linear_usize >> 21) & 0x1FF;
-        let table_index = (linear_usize >> 12) & 0x1FF;
-        let offset = linear_usize & 0xFFF;
-
-        // Read PDPTE
-        let pdpte_addr = (cr3 + (pdp_index * 8)) & 0xFFFFFFFF;
-        let pdpte = self.read_physical_64(pdpte_addr);
-
-        // Check PDPTE present
-        if (pdpte & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear, (0x0E << 16) | err);
-        }
-
-        // Check user access
-        if is_user && (pdpte & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-
-        // Check write access
-        if is_write && (pdpte & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-
-        // Mark PDPTE accessed
-        self.write_physical_64(pdpte_addr, pdpte | (1 << 5));
-
-        // Read PDE
-        let pde_addr = (((pdpte & 0xFFFFFF000) as usize) + (dir_index * 8)) & 0xFFFFFFFF;
-        let pde = self.read_physical_64(pde_addr);
-
-        // Check PDE present
-        if (pde & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear, (0x0E << 16) | err);
-        }
-
-        let is_large = (pde & (1 << 7)) != 0;
-
-        // Check user access
-        if is_user && (pde & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-
-        // Check write access
-        if is_write && (pde & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-
-        // Handle 2MB large page
-        if is_large {
-            let mut pde = pde;
-            pde |= 0x20;
-            if is_write {
-                pde |= 0x40;
+        paging_enabled: bool,
+        linear_mask: u64,
+    ) -> ([u64; 2], usize, u32) {
+        let (first, err) = self.translate_linear(linear, is_write, is_user, paging_enabled, linear_mask);
+        if err != 0 {
+            return ([0; 2], 0, err);
+        }
+        // Without paging the physical range is contiguous.
+        let split = if paging_enabled { bytes.min(0x1000 - (first & 0xFFF) as usize) } else { bytes };
+        let mut second = 0;
+        if split < bytes {
+            let next_page = (linear & !0xFFF).wrapping_add(0x1000);
+            let (physical, err) = self.translate_linear(next_page, is_write, is_user, paging_enabled, linear_mask);
+            if err != 0 {
+                return ([0; 2], 0, err);
             }
-            self.write_physical_64(pde_addr, pde);
-            let base = (pde & 0xFFE00000) as usize;
-            let phys = ((base + (linear_usize & 0x1FFFFF)) & 0xFFFFFFFF) as u64;
-            return (phys, 0);
-        }
-
-        // Read PTE
-        let pte_addr = (((pde & 0xFFFFFF000) as usize) + (table_index * 8)) & 0xFFFFFFFF;
-        let pte = self.read_physical_64(pte_addr);
-
-        // Check PTE present
-        if (pte & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear, (0x0E << 16) | err);
+            if Self::is_mmio_address(physical as usize) {
+                return ([0; 2], 0, 0xFFFFFFFF);
+            }
+            second = physical;
         }
-
-        // Check user access
-        if is_user && (pte & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear, (0x0E << 16) | err);
+        if Self::is_mmio_address(first as usize) {
+            return ([0; 2], 0, 0xFFFFFFFF); // Signal PHP to handle MMIO
         }
+        ([first, second], split, 0)
+    }
 
-        // Check write access
-        if is_write && (pte & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
+    /// #AC(0) for a misaligned `bytes`-wide user data access while CR0.AM
+    /// and EFLAGS.AC are both set, else 0.
+    #[inline(always)]
+    fn check_alignment(&self, linear: u64, bytes: usize, is_user: bool) -> u32 {
+        if bytes == 1
+            || !is_user
+            || self.instruction_fetch
+            || (linear & (bytes as u64 - 1)) == 0
+            || (self.control_registers[0] & CR0_AM) == 0
+            || (self.system_flags & EFLAGS_AC) == 0
+        {
+            return 0;
+        }
+        pack_fault(VECTOR_AC, 0)
+    }
 
-        // Set accessed/dirty bits
-        self.write_physical_64(pde_addr, pde | 0x20);
-        let mut pte_updated = pte | 0x20;
-        if is_write {
-            pte_updated |= 0x40;
+    /// Physical address of byte `index` of a translated access.
+    #[inline(always)]
+    fn access_byte_address(pages: [u64; 2], split: usize, index: usize) -> usize {
+        if index < split {
+            pages[0] as usize + index
+        } else {
+            pages[1] as usize + (index - split)
         }
-        self.write_physical_64(pte_addr, pte_updated);
-
-        let phys = ((pte & 0xFFFFFF000) as usize + offset) as u64;
-        (phys & 0xFFFFFFFF, 0)
     }
 
-    /// IA-32e (long mode) 4-level paging translation (PML4).
-    ///
-    /// This is selected when CR4.PAE=1, paging_enabled=true, and EFER.LME=1.
-    /// Physical addresses are treated as 32-bit (best-effort) to match the current MemoryStream model.
-    fn translate_linear_ia32e(
+    /// Little-endian read of `bytes` (1, 2, 4 or 8) at `linear`.
+    fn read_linear(
         &mut self,
         linear: u64,
-        is_write: bool,
+        bytes: usize,
         is_user: bool,
+        paging_enabled: bool,
+        linear_mask: u64,
     ) -> (u64, u32) {
-        let cr3 = (self.control_registers[3] & 0xFFFFF000) as usize;
-        let linear_usize = linear as usize;
-
-        let pml4_index = ((linear >> 39) & 0x1FF) as usize;
-        let pdpt_index = ((linear >> 30) & 0x1FF) as usize;
-        let dir_index = ((linear >> 21) & 0x1FF) as usize;
-        let table_index = ((linear >> 12) & 0x1FF) as usize;
-        let offset = linear_usize & 0xFFF;
-
-        // Read PML4E
-        let pml4e_addr = (cr3 + (pml4_index * 8)) & 0xFFFFFFFF;
-        let pml4e = self.read_physical_64(pml4e_addr);
-
-        if (pml4e & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_user && (pml4e & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_write && (pml4e & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-        // Mark PML4E accessed
-        self.write_physical_64(pml4e_addr, pml4e | (1 << 5));
-
-        // Read PDPTE
-        let pdpte_base = (pml4e & 0xFFFFFF000) as usize;
-        let pdpte_addr = (pdpte_base + (pdpt_index * 8)) & 0xFFFFFFFF;
-        let pdpte = self.read_physical_64(pdpte_addr);
-
-        if (pdpte & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_user && (pdpte & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_write && (pdpte & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear, (0x0E << 16) | err);
+        let (pages, split, err) = self.translate_access(linear, bytes, false, is_user, paging_enabled, linear_mask);
+        let err = if err != 0 { err } else { self.check_alignment(linear, bytes, is_user) };
+        if err != 0 {
+            self.trace_access(linear, pages[0], bytes, false, 0, err);
+            return (0, err);
         }
-        // Mark PDPTE accessed
-        self.write_physical_64(pdpte_addr, pdpte | (1 << 5));
-
-        // 1GB large page (PS)
-        if (pdpte & (1 << 7)) != 0 {
-            let mut pdpte_upd = pdpte | 0x20;
-            if is_write {
-                pdpte_upd |= 0x40;
+        let value = if split == bytes {
+            let physical = pages[0] as usize;
+            match bytes {
+                1 => self.read_physical_8(physical) as u64,
+                2 => self.read_physical_16(physical) as u64,
+                4 => self.read_physical_32(physical) as u64,
+                _ => self.read_physical_64(physical),
             }
-            self.write_physical_64(pdpte_addr, pdpte_upd);
-
-            // Base is 1GB-aligned; keep within 32-bit physical space.
-            let base = (pdpte & 0x000FFFFF_C0000000) as usize;
-            let phys = ((base + (linear_usize & 0x3FFFFFFF)) & 0xFFFFFFFF) as u64;
-            return (phys, 0);
-        }
-
-        // Read PDE
-        let pde_addr = (((pdpte & 0xFFFFFF000) as usize) + (dir_index * 8)) & 0xFFFFFFFF;
-        let pde = self.read_physical_64(pde_addr);
+        } else {
+            (0..bytes).fold(0, |value, i| {
+                let byte = self.read_physical_8(Self::access_byte_address(pages, split, i));
+                value | ((byte as u64) << (i * 8))
+            })
+        };
+        self.note_data_access(linear & linear_mask, bytes as u64, false);
+        self.trace_access(linear, pages[0], bytes, false, value, 0);
+        (value, 0)
+    }
 
-        if (pde & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_user && (pde & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_write && (pde & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear, (0x0E << 16) | err);
+    /// Little-endian write of `bytes` (1, 2, 4 or 8) at `linear`. Nothing is
+    /// written unless every byte's page translates and the alignment check
+    /// passes.
+    fn write_linear(
+        &mut self,
+        linear: u64,
+        value: u64,
+        bytes: usize,
+        is_user: bool,
+        paging_enabled: bool,
+        linear_mask: u64,
+    ) -> u32 {
+        let (pages, split, err) = self.translate_access(linear, bytes, true, is_user, paging_enabled, linear_mask);
+        let err = if err != 0 { err } else { self.check_alignment(linear, bytes, is_user) };
+        if err != 0 {
+            self.trace_access(linear, pages[0], bytes, true, value, err);
+            return err;
         }
-
-        // 2MB large page (PS)
-        if (pde & (1 << 7)) != 0 {
-            let mut pde_upd = pde | 0x20;
-            if is_write {
-                pde_upd |= 0x40;
+        if split == bytes {
+            let physical = pages[0] as usize;
+            match bytes {
+                4 => self.write_physical_32(physical, value as u32),
+                8 => self.write_physical_64(physical, value),
+                _ => {
+                    for i in 0..bytes {
+                        self.write_raw_byte(physical + i, (value >> (i * 8)) as u8);
+                    }
+                }
+            }
+        } else {
+            for i in 0..bytes {
+                self.write_raw_byte(Self::access_byte_address(pages, split, i), (value >> (i * 8)) as u8);
             }
-            self.write_physical_64(pde_addr, pde_upd);
-
-            let base = (pde & 0xFFE00000) as usize;
-            let phys = ((base + (linear_usize & 0x1FFFFF)) & 0xFFFFFFFF) as u64;
-            return (phys, 0);
-        }
-
-        // Read PTE
-        let pte_addr = (((pde & 0xFFFFFF000) as usize) + (table_index * 8)) & 0xFFFFFFFF;
-        let pte = self.read_physical_64(pte_addr);
-
-        if (pte & 0x1) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | (if is_user { 0b100 } else { 0 });
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_user && (pte & 0x4) == 0 {
-            let err = (if is_write { 0b10 } else { 0 }) | 0b100 | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-        if is_write && (pte & 0x2) == 0 {
-            let err = 0b10 | (if is_user { 0b100 } else { 0 }) | 0b1;
-            return (linear, (0x0E << 16) | err);
-        }
-
-        // Set accessed/dirty bits
-        self.write_physical_64(pde_addr, pde | 0x20);
-        let mut pte_updated = pte | 0x20;
-        if is_write {
-            pte_updated |= 0x40;
         }
-        self.write_physical_64(pte_addr, pte_updated);
-
-        let phys = ((pte & 0xFFFFFF000) as usize + offset) as u64;
-        (phys & 0xFFFFFFFF, 0)
+        self.note_data_access(linear & linear_mask, bytes as u64, true);
+        self.trace_access(linear, pages[0], bytes, true, value, 0);
+        0
     }
 
     /// Read memory with linear address translation.
@@ -390,14 +615,8 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> (u8, u32) {
-        let (physical, err) = self.translate_linear(linear, false, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return (0, err);
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return (0, 0xFFFFFFFF); // Signal PHP to handle MMIO
-        }
-        (self.read_physical_8(physical as usize), 0)
+        let (value, err) = self.read_linear(linear, 1, is_user, paging_enabled, linear_mask);
+        (value as u8, err)
     }
 
     /// Read 16-bit memory with linear address translation.
@@ -408,14 +627,8 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> (u16, u32) {
-        let (physical, err) = self.translate_linear(linear, false, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return (0, err);
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return (0, 0xFFFFFFFF);
-        }
-        (self.read_physical_16(physical as usize), 0)
+        let (value, err) = self.read_linear(linear, 2, is_user, paging_enabled, linear_mask);
+        (value as u16, err)
     }
 
     /// Read 32-bit memory with linear address translation.
@@ -426,14 +639,8 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> (u32, u32) {
-        let (physical, err) = self.translate_linear(linear, false, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return (0, err);
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return (0, 0xFFFFFFFF);
-        }
-        (self.read_physical_32(physical as usize), 0)
+        let (value, err) = self.read_linear(linear, 4, is_user, paging_enabled, linear_mask);
+        (value as u32, err)
     }
 
     /// Read 64-bit memory with linear address translation.
@@ -444,14 +651,7 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> (u64, u32) {
-        let (physical, err) = self.translate_linear(linear, false, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return (0, err);
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return (0, 0xFFFFFFFF);
-        }
-        (self.read_physical_64(physical as usize), 0)
+        self.read_linear(linear, 8, is_user, paging_enabled, linear_mask)
     }
 
     /// Write 8-bit memory with linear address translation.
@@ -464,15 +664,7 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> u32 {
-        let (physical, err) = self.translate_linear(linear, true, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return err;
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return 0xFFFFFFFF; // Signal PHP to handle MMIO
-        }
-        self.write_raw_byte(physical as usize, value);
-        0
+        self.write_linear(linear, value as u64, 1, is_user, paging_enabled, linear_mask)
     }
 
     /// Write 16-bit memory with linear address translation.
@@ -485,17 +677,7 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> u32 {
-        let (physical, err) = self.translate_linear(linear, true, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return err;
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return 0xFFFFFFFF;
-        }
-        // Write little-endian
-        self.write_raw_byte(physical as usize, (value & 0xFF) as u8);
-        self.write_raw_byte((physical + 1) as usize, ((value >> 8) & 0xFF) as u8);
-        0
+        self.write_linear(linear, value as u64, 2, is_user, paging_enabled, linear_mask)
     }
 
     /// Write 32-bit memory with linear address translation.
@@ -508,15 +690,7 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> u32 {
-        let (physical, err) = self.translate_linear(linear, true, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return err;
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return 0xFFFFFFFF;
-        }
-        self.write_physical_32(physical as usize, value);
-        0
+        self.write_linear(linear, value as u64, 4, is_user, paging_enabled, linear_mask)
     }
 
     /// Write 64-bit memory with linear address translation.
@@ -529,15 +703,97 @@ impl MemoryAccessor {
         paging_enabled: bool,
         linear_mask: u64,
     ) -> u32 {
-        let (physical, err) = self.translate_linear(linear, true, is_user, paging_enabled, linear_mask);
-        if err != 0 {
-            return err;
-        }
-        if Self::is_mmio_address(physical as usize) {
-            return 0xFFFFFFFF;
+        self.write_linear(linear, value, 8, is_user, paging_enabled, linear_mask)
+    }
+
+    /// CR0.PG as last written to the control registers.
+    #[inline(always)]
+    pub fn paging_enabled(&self) -> bool {
+        (self.control_registers[0] & (1 << 31)) != 0
+    }
+
+    /// IA-32e with 5-level paging (CR4.LA57): 57-bit linear addresses.
+    #[inline(always)]
+    pub fn la57_active(&self) -> bool {
+        self.long_mode_active() && (self.control_registers[4] & CR4_LA57) != 0
+    }
+
+    /// Sign-extend an IA-32e linear address from bit 47, or bit 56 under LA57.
+    #[inline(always)]
+    pub fn canonicalize(&self, linear: u64) -> u64 {
+        Self::sign_extend_linear(linear, self.la57_active())
+    }
+
+    #[inline(always)]
+    fn sign_extend_linear(linear: u64, la57: bool) -> u64 {
+        let unused = if la57 { 7 } else { 16 };
+        (((linear << unused) as i64) >> unused) as u64
+    }
+
+    /// Open or close the A20 gate.
+    pub fn set_a20_enabled(&mut self, enabled: bool) {
+        self.a20_enabled = enabled;
+    }
+
+    pub fn a20_enabled(&self) -> bool {
+        self.a20_enabled
+    }
+
+    /// Linear address mask used for implicit system accesses. Outside IA-32e
+    /// mode a closed A20 gate wraps them at 1MB.
+    #[inline(always)]
+    pub(crate) fn system_linear_mask(&self) -> u64 {
+        if self.la57_active() {
+            LINEAR_MASK_5_LEVEL
+        } else if self.long_mode_active() {
+            LINEAR_MASK_4_LEVEL
+        } else if self.a20_enabled {
+            0xFFFF_FFFF
+        } else {
+            0xF_FFFF
         }
-        self.write_physical_64(physical as usize, value);
-        0
+    }
+
+    /// Supervisor 64-bit read used for descriptor tables and the TSS.
+    pub(crate) fn read_system_64(&mut self, linear: u64) -> (u64, u32) {
+        let paging_enabled = self.paging_enabled();
+        let linear_mask = self.system_linear_mask();
+        self.read_memory_64(linear, false, paging_enabled, linear_mask)
+    }
+
+    /// Supervisor 16-bit read used for descriptor tables and the TSS.
+    pub(crate) fn read_system_16(&mut self, linear: u64) -> (u16, u32) {
+        let paging_enabled = self.paging_enabled();
+        let linear_mask = self.system_linear_mask();
+        self.read_memory_16(linear, false, paging_enabled, linear_mask)
+    }
+
+    /// Supervisor 32-bit read used for descriptor tables and the TSS.
+    pub(crate) fn read_system_32(&mut self, linear: u64) -> (u32, u32) {
+        let paging_enabled = self.paging_enabled();
+        let linear_mask = self.system_linear_mask();
+        self.read_memory_32(linear, false, paging_enabled, linear_mask)
+    }
+
+    /// Supervisor 16-bit write used for descriptor tables and the TSS.
+    pub(crate) fn write_system_16(&mut self, linear: u64, value: u16) -> u32 {
+        let paging_enabled = self.paging_enabled();
+        let linear_mask = self.system_linear_mask();
+        self.write_memory_16(linear, value, false, paging_enabled, linear_mask)
+    }
+
+    /// Supervisor 32-bit write used for descriptor tables and the TSS.
+    pub(crate) fn write_system_32(&mut self, linear: u64, value: u32) -> u32 {
+        let paging_enabled = self.paging_enabled();
+        let linear_mask = self.system_linear_mask();
+        self.write_memory_32(linear, value, false, paging_enabled, linear_mask)
+    }
+
+    /// Supervisor 8-bit write used for descriptor tables and the TSS.
+    pub(crate) fn write_system_8(&mut self, linear: u64, value: u8) -> u32 {
+        let paging_enabled = self.paging_enabled();
+        let linear_mask = self.system_linear_mask();
+        self.write_memory_8(linear, value, false, paging_enabled, linear_mask)
     }
 
     /// Write 16-bit value to physical memory.
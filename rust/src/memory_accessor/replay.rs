@@ -0,0 +1,227 @@
+//! Deterministic record and replay of nondeterministic inputs.
+//!
+//! Every input the guest can observe but the emulator cannot reproduce
+//! (MMIO read results supplied by PHP, the TSC, injected interrupts) goes
+//! through `replay_input` tagged with the number of instructions retired
+//! so far. While recording, the live value is logged and passed through;
+//! while replaying, the logged value is returned instead. Started right
+//! after `load_state`, a replay reproduces the recorded run bit-for-bit.
+//!
+//! The executor calls `retire_instruction` once per completed instruction
+//! and, when replaying, polls `replay_interrupt` at each instruction
+//! boundary instead of consulting its own interrupt sources.
+
+use std::fs;
+use std::path::Path;
+
+use super::msr::MSR_TSC;
+use super::save_state::{
+    seal, unseal, Reader, Writer, SAVE_STATE_BAD_CHECKSUM, SAVE_STATE_BAD_MAGIC, SAVE_STATE_BAD_VERSION,
+    SAVE_STATE_TRUNCATED,
+};
+use super::MemoryAccessor;
+
+pub const REPLAY_EVENT_MMIO_READ: u32 = 0;
+pub const REPLAY_EVENT_TSC: u32 = 1;
+/// `address` holds the vector.
+pub const REPLAY_EVENT_INTERRUPT: u32 = 2;
+
+pub const REPLAY_OFF: u32 = 0;
+pub const REPLAY_RECORDING: u32 = 1;
+pub const REPLAY_REPLAYING: u32 = 2;
+
+/// `replay_input` status codes.
+pub const REPLAY_OK: u32 = 0;
+/// The next logged event is of another kind, address or instruction.
+pub const REPLAY_DIVERGED: u32 = 1;
+/// Every logged event has been consumed.
+pub const REPLAY_EXHAUSTED: u32 = 2;
+
+/// `save_replay_log` / `load_replay_log` status codes.
+pub const REPLAY_LOG_OK: u32 = 0;
+pub const REPLAY_LOG_BAD_MAGIC: u32 = 1;
+pub const REPLAY_LOG_BAD_VERSION: u32 = 2;
+pub const REPLAY_LOG_BAD_CHECKSUM: u32 = 3;
+pub const REPLAY_LOG_TRUNCATED: u32 = 4;
+pub const REPLAY_LOG_IO_ERROR: u32 = 5;
+
+pub const REPLAY_LOG_VERSION: u32 = 1;
+const REPLAY_LOG_MAGIC: &[u8; 8] = b"PHPMRPLY";
+
+/// One logged input.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReplayEvent {
+    /// Instructions retired before the input arrived.
+    pub instruction: u64,
+    /// MMIO physical address or interrupt vector; 0 for the TSC.
+    pub address: u64,
+    pub value: u64,
+    pub kind: u32,
+    /// Access size in bytes for MMIO reads.
+    pub size: u32,
+}
+
+pub(crate) struct ReplayLog {
+    mode: u32,
+    instructions: u64,
+    events: Vec<ReplayEvent>,
+    cursor: usize,
+}
+
+impl ReplayLog {
+    pub(crate) fn new() -> Self {
+        ReplayLog { mode: REPLAY_OFF, instructions: 0, events: Vec::new(), cursor: 0 }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Writer(Vec::with_capacity(8 + self.events.len() * 32));
+        out.u64(self.events.len() as u64);
+        for event in &self.events {
+            out.u64(event.instruction);
+            out.u64(event.address);
+            out.u64(event.value);
+            out.u32(event.kind);
+            out.u32(event.size);
+        }
+        seal(REPLAY_LOG_MAGIC, REPLAY_LOG_VERSION, &out.0)
+    }
+
+    fn from_bytes(image: &[u8]) -> Result<Vec<ReplayEvent>, u32> {
+        let mut r = Reader { data: unseal(image, REPLAY_LOG_MAGIC, REPLAY_LOG_VERSION)? };
+        let count = r.u64()?;
+        if count > (r.data.len() / 32) as u64 {
+            return Err(SAVE_STATE_TRUNCATED);
+        }
+        (0..count)
+            .map(|_| {
+                Ok(ReplayEvent {
+                    instruction: r.u64()?,
+                    address: r.u64()?,
+                    value: r.u64()?,
+                    kind: r.u32()?,
+                    size: r.u32()?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The `REPLAY_LOG_*` status for a `SAVE_STATE_*` error from the shared
+/// file format.
+fn log_status(status: u32) -> u32 {
+    match status {
+        SAVE_STATE_BAD_MAGIC => REPLAY_LOG_BAD_MAGIC,
+        SAVE_STATE_BAD_VERSION => REPLAY_LOG_BAD_VERSION,
+        SAVE_STATE_BAD_CHECKSUM => REPLAY_LOG_BAD_CHECKSUM,
+        _ => REPLAY_LOG_TRUNCATED,
+    }
+}
+
+impl MemoryAccessor {
+    /// Start a new recording; the instruction count restarts at 0.
+    pub fn start_recording(&mut self) {
+        self.replay = ReplayLog { mode: REPLAY_RECORDING, ..ReplayLog::new() };
+    }
+
+    /// Replay `events` from the first one; the instruction count restarts at 0.
+    pub fn start_replay(&mut self, events: Vec<ReplayEvent>) {
+        self.replay = ReplayLog { mode: REPLAY_REPLAYING, events, ..ReplayLog::new() };
+    }
+
+    /// Stop recording or replaying. A recorded log stays available.
+    pub fn stop_replay(&mut self) {
+        self.replay.mode = REPLAY_OFF;
+    }
+
+    pub fn replay_mode(&self) -> u32 {
+        self.replay.mode
+    }
+
+    pub fn replay_events(&self) -> &[ReplayEvent] {
+        &self.replay.events
+    }
+
+    pub fn instruction_count(&self) -> u64 {
+        self.replay.instructions
+    }
+
+    #[inline(always)]
+    pub fn retire_instruction(&mut self) {
+        self.replay.instructions += 1;
+    }
+
+    /// Pass one nondeterministic input through the recorder. Returns the
+    /// value the guest observes and a `REPLAY_*` status; on divergence or
+    /// exhaustion the live value is returned unchanged.
+    pub fn replay_input(&mut self, kind: u32, address: u64, size: u32, live: u64) -> (u64, u32) {
+        let log = &mut self.replay;
+        let event = ReplayEvent { instruction: log.instructions, address, value: live, kind, size };
+        match log.mode {
+            REPLAY_RECORDING => {
+                log.events.push(event);
+                (live, REPLAY_OK)
+            }
+            REPLAY_REPLAYING => match log.events.get(log.cursor) {
+                None => (live, REPLAY_EXHAUSTED),
+                Some(logged) if ReplayEvent { value: logged.value, ..event } != *logged => (live, REPLAY_DIVERGED),
+                Some(logged) => {
+                    log.cursor += 1;
+                    (logged.value, REPLAY_OK)
+                }
+            },
+            _ => (live, REPLAY_OK),
+        }
+    }
+
+    /// While replaying, the vector of an interrupt logged at the current
+    /// instruction boundary (consumed), or None.
+    pub fn replay_interrupt(&mut self) -> Option<u8> {
+        let log = &mut self.replay;
+        if log.mode != REPLAY_REPLAYING {
+            return None;
+        }
+        let event = log.events.get(log.cursor)?;
+        if event.kind != REPLAY_EVENT_INTERRUPT || event.instruction != log.instructions {
+            return None;
+        }
+        log.cursor += 1;
+        Some(event.address as u8)
+    }
+
+    /// Record an interrupt the executor is about to deliver.
+    pub fn record_interrupt(&mut self, vector: u8) {
+        if self.replay.mode == REPLAY_RECORDING {
+            self.replay_input(REPLAY_EVENT_INTERRUPT, vector as u64, 0, 0);
+        }
+    }
+
+    /// RDTSC through the recorder.
+    pub fn read_tsc(&mut self) -> u64 {
+        let live = self.read_msr(MSR_TSC).0;
+        self.replay_input(REPLAY_EVENT_TSC, 0, 8, live).0
+    }
+
+    /// Write the recorded events to `path`. Returns a `REPLAY_LOG_*` status.
+    pub fn save_replay_log(&self, path: &Path) -> u32 {
+        match fs::write(path, self.replay.to_bytes()) {
+            Ok(()) => REPLAY_LOG_OK,
+            Err(_) => REPLAY_LOG_IO_ERROR,
+        }
+    }
+
+    /// Start replaying the log at `path`. Returns a `REPLAY_LOG_*` status;
+    /// nothing changes unless it is `REPLAY_LOG_OK`.
+    pub fn load_replay_log(&mut self, path: &Path) -> u32 {
+        let Ok(image) = fs::read(path) else {
+            return REPLAY_LOG_IO_ERROR;
+        };
+        match ReplayLog::from_bytes(&image) {
+            Ok(events) => {
+                self.start_replay(events);
+                REPLAY_LOG_OK
+            }
+            Err(status) => log_status(status),
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! Versioned whole-machine save states.
+//!
+//! A save state holds everything the native side owns: the register file
+//! with its allocation map, EFLAGS, control registers, EFER, PDPTEs,
+//! segment and descriptor-table caches, debug registers, MSRs and the
+//! allocated MemoryStream pages. Layout (little-endian):
+//!
+//! ```text
+//! header:  magic "PHPMSAVE", version u32, reserved u32,
+//!          payload length u64, CRC-32 of payload u32, reserved u32
+//! payload: CPU section, then memory geometry and (page index, 4 KiB) pairs
+//! ```
+//!
+//! The loader checks the magic, version, length and checksum and parses the
+//! whole payload before touching any state, so a rejected file leaves the
+//! machine unchanged.
+
+use std::fs;
+use std::path::Path;
+
+use crate::memory_stream::PAGE_SIZE;
+
+use super::descriptor::{DescriptorTableRegister, DESCRIPTOR_TABLE_COUNT};
+use super::msr::{
+    MSR_APIC_BASE, MSR_CSTAR, MSR_KERNEL_GS_BASE, MSR_LSTAR, MSR_MISC_ENABLE, MSR_MTRR_DEF_TYPE,
+    MSR_MTRR_FIX16K_80000, MSR_MTRR_FIX4K_C0000, MSR_MTRR_FIX64K_00000, MSR_MTRR_PHYSBASE0, MSR_PAT, MSR_SFMASK, MSR_STAR,
+    MSR_SYSENTER_CS, MSR_SYSENTER_EIP, MSR_SYSENTER_ESP, MSR_TSC, MSR_TSC_AUX,
+};
+use super::segment::{SegmentCache, SEGMENT_COUNT};
+use super::{MemoryAccessor, MAX_REGISTER_ADDRESS};
+
+/// Bumped whenever the payload layout changes; other versions are refused.
+pub const SAVE_STATE_VERSION: u32 = 1;
+const SAVE_STATE_MAGIC: &[u8; 8] = b"PHPMSAVE";
+const HEADER_SIZE: usize = 32;
+
+/// `load_state`/`save_state_file` status codes.
+pub const SAVE_STATE_OK: u32 = 0;
+pub const SAVE_STATE_BAD_MAGIC: u32 = 1;
+pub const SAVE_STATE_BAD_VERSION: u32 = 2;
+pub const SAVE_STATE_BAD_CHECKSUM: u32 = 3;
+pub const SAVE_STATE_TRUNCATED: u32 = 4;
+/// The file was saved with a different physical/swap memory size.
+pub const SAVE_STATE_MEMORY_MISMATCH: u32 = 5;
+pub const SAVE_STATE_IO_ERROR: u32 = 6;
+
+/// MSRs kept in the MSR file (EFER and the FS/GS bases travel elsewhere).
+const SAVED_MSRS: [u32; 13] = [
+    MSR_TSC,
+    MSR_APIC_BASE,
+    MSR_SYSENTER_CS,
+    MSR_SYSENTER_ESP,
+    MSR_SYSENTER_EIP,
+    MSR_MISC_ENABLE,
+    MSR_PAT,
+    MSR_MTRR_DEF_TYPE,
+    MSR_STAR,
+    MSR_LSTAR,
+    MSR_CSTAR,
+    MSR_SFMASK,
+    MSR_KERNEL_GS_BASE,
+];
+const SAVED_MTRRS: [(u32, u32); 4] = [
+    (MSR_MTRR_PHYSBASE0, 16),
+    (MSR_MTRR_FIX64K_00000, 1),
+    (MSR_MTRR_FIX16K_80000, 2),
+    (MSR_MTRR_FIX4K_C0000, 8),
+];
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if (crc & 1) != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE 802.3).
+fn crc32(data: &[u8]) -> u32 {
+    !data
+        .iter()
+        .fold(!0u32, |crc, &byte| CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+fn saved_msr_indices() -> impl Iterator<Item = u32> {
+    SAVED_MSRS
+        .into_iter()
+        .chain(SAVED_MTRRS.into_iter().flat_map(|(first, count)| first..first + count))
+        .chain([MSR_TSC_AUX])
+}
+
+/// Wrap `payload` in the magic/version/length/CRC header.
+pub(crate) fn seal(magic: &[u8; 8], version: u32, payload: &[u8]) -> Vec<u8> {
+    let mut image = Writer(Vec::with_capacity(HEADER_SIZE + payload.len()));
+    image.0.extend_from_slice(magic);
+    image.u32(version);
+    image.u32(0);
+    image.u64(payload.len() as u64);
+    image.u32(crc32(payload));
+    image.u32(0);
+    image.0.extend_from_slice(payload);
+    image.0
+}
+
+/// Check the header written by `seal` and return the payload.
+pub(crate) fn unseal<'a>(image: &'a [u8], magic: &[u8; 8], version: u32) -> Result<&'a [u8], u32> {
+    let mut header = Reader { data: image };
+    if &header.take::<8>()? != magic {
+        return Err(SAVE_STATE_BAD_MAGIC);
+    }
+    if header.u32()? != version {
+        return Err(SAVE_STATE_BAD_VERSION);
+    }
+    header.u32()?;
+    let payload_len = header.u64()?;
+    let checksum = header.u32()?;
+    header.u32()?;
+    if (header.data.len() as u64) < payload_len {
+        return Err(SAVE_STATE_TRUNCATED);
+    }
+    let payload = &header.data[..payload_len as usize];
+    if crc32(payload) != checksum {
+        return Err(SAVE_STATE_BAD_CHECKSUM);
+    }
+    Ok(payload)
+}
+
+pub(crate) struct Writer(pub(crate) Vec<u8>);
+
+impl Writer {
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    pub(crate) data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn take<const N: usize>(&mut self) -> Result<[u8; N], u32> {
+        if self.data.len() < N {
+            return Err(SAVE_STATE_TRUNCATED);
+        }
+        let (head, rest) = self.data.split_at(N);
+        self.data = rest;
+        Ok(head.try_into().unwrap())
+    }
+    pub(crate) fn u8(&mut self) -> Result<u8, u32> {
+        Ok(self.take::<1>()?[0])
+    }
+    pub(crate) fn u16(&mut self) -> Result<u16, u32> {
+        self.take().map(u16::from_le_bytes)
+    }
+    pub(crate) fn u32(&mut self) -> Result<u32, u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+    pub(crate) fn u64(&mut self) -> Result<u64, u32> {
+        self.take().map(u64::from_le_bytes)
+    }
+}
+
+/// Everything `load_state` applies, parsed up front.
+struct SavedMachine {
+    registers: [i64; MAX_REGISTER_ADDRESS],
+    registers_allocated: [bool; MAX_REGISTER_ADDRESS],
+    eflags: u32,
+    instruction_fetch: bool,
+    control_registers: [u64; 9],
+    efer: u64,
+    pdptes: [u64; 4],
+    segments: [SegmentCache; SEGMENT_COUNT],
+    descriptor_tables: [DescriptorTableRegister; DESCRIPTOR_TABLE_COUNT],
+    debug: [u64; 6],
+    msrs: Vec<(u32, u64)>,
+    memory_size: usize,
+    memory_offset: usize,
+    pages: Vec<(usize, Box<[u8; PAGE_SIZE]>)>,
+}
+
+impl MemoryAccessor {
+    /// Serialize the machine into a save-state image.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out = Writer(Vec::new());
+        for &value in &self.registers {
+            out.u64(value as u64);
+        }
+        for &allocated in &self.registers_allocated {
+            out.u8(allocated as u8);
+        }
+        out.u32(self.read_eflags());
+        out.u8(self.instruction_fetch as u8);
+        for &value in &self.control_registers {
+            out.u64(value);
+        }
+        out.u64(self.efer);
+        for &pdpte in &self.pdptes {
+            out.u64(pdpte);
+        }
+        for cache in &self.segments {
+            out.u16(cache.selector);
+            out.u16(cache.attributes);
+            out.u32(cache.limit);
+            out.u64(cache.base);
+        }
+        for table in &self.descriptor_tables {
+            out.u64(table.base);
+            out.u32(table.limit);
+            out.u16(table.selector);
+        }
+        for value in self.debug.saved() {
+            out.u64(value);
+        }
+        let msrs: Vec<(u32, u64)> = saved_msr_indices().map(|index| (index, self.read_msr(index).0)).collect();
+        out.u32(msrs.len() as u32);
+        for (index, value) in msrs {
+            out.u32(index);
+            out.u64(value);
+        }
+
+        let memory = unsafe { self.memory.as_ref() };
+        out.u64(memory.map_or(0, |m| m.physical_max_memory_size()) as u64);
+        out.u64(memory.map_or(0, |m| m.swap_size()) as u64);
+        out.u64(memory.map_or(0, |m| m.size()) as u64);
+        out.u64(memory.map_or(0, |m| m.offset()) as u64);
+        let pages: Vec<_> = memory.map(|m| m.allocated_pages().collect()).unwrap_or_default();
+        out.u64(pages.len() as u64);
+        for (index, page) in pages {
+            out.u64(index as u64);
+            out.0.extend_from_slice(page);
+        }
+
+        seal(SAVE_STATE_MAGIC, SAVE_STATE_VERSION, &out.0)
+    }
+
+    /// Restore a `save_state` image. Returns `SAVE_STATE_OK` or the reason
+    /// the image was refused; the machine is untouched on failure.
+    pub fn load_state(&mut self, image: &[u8]) -> u32 {
+        match self.parse_state(image) {
+            Ok(saved) => {
+                self.apply_state(saved);
+                SAVE_STATE_OK
+            }
+            Err(status) => status,
+        }
+    }
+
+    /// Write a save-state file to `path`.
+    pub fn save_state_file(&self, path: &Path) -> u32 {
+        match fs::write(path, self.save_state()) {
+            Ok(()) => SAVE_STATE_OK,
+            Err(_) => SAVE_STATE_IO_ERROR,
+        }
+    }
+
+    /// Load a save-state file from `path`.
+    pub fn load_state_file(&mut self, path: &Path) -> u32 {
+        match fs::read(path) {
+            Ok(image) => self.load_state(&image),
+            Err(_) => SAVE_STATE_IO_ERROR,
+        }
+    }
+
+    fn parse_state(&self, image: &[u8]) -> Result<SavedMachine, u32> {
+        let mut r = Reader { data: unseal(image, SAVE_STATE_MAGIC, SAVE_STATE_VERSION)? };
+        let mut registers = [0i64; MAX_REGISTER_ADDRESS];
+        for value in registers.iter_mut() {
+            *value = r.u64()? as i64;
+        }
+        let mut registers_allocated = [false; MAX_REGISTER_ADDRESS];
+        for allocated in registers_allocated.iter_mut() {
+            *allocated = r.u8()? != 0;
+        }
+        let eflags = r.u32()?;
+        let instruction_fetch = r.u8()? != 0;
+        let mut control_registers = [0u64; 9];
+        for value in control_registers.iter_mut() {
+            *value = r.u64()?;
+        }
+        let efer = r.u64()?;
+        let mut pdptes = [0u64; 4];
+        for pdpte in pdptes.iter_mut() {
+            *pdpte = r.u64()?;
+        }
+        let mut segments = [SegmentCache::default(); SEGMENT_COUNT];
+        for cache in segments.iter_mut() {
+            *cache = SegmentCache { selector: r.u16()?, attributes: r.u16()?, limit: r.u32()?, base: r.u64()? };
+        }
+        let mut descriptor_tables = [DescriptorTableRegister { base: 0, limit: 0, selector: 0 }; DESCRIPTOR_TABLE_COUNT];
+        for table in descriptor_tables.iter_mut() {
+            *table = DescriptorTableRegister { base: r.u64()?, limit: r.u32()?, selector: r.u16()? };
+        }
+        let mut debug = [0u64; 6];
+        for value in debug.iter_mut() {
+            *value = r.u64()?;
+        }
+        let msr_count = r.u32()?;
+        let mut msrs = Vec::new();
+        for _ in 0..msr_count {
+            msrs.push((r.u32()?, r.u64()?));
+        }
+
+        let physical_max = r.u64()?;
+        let swap = r.u64()?;
+        let memory_size = r.u64()? as usize;
+        let memory_offset = r.u64()? as usize;
+        let memory = unsafe { self.memory.as_ref() };
+        if memory.map_or((0, 0), |m| (m.physical_max_memory_size() as u64, m.swap_size() as u64)) != (physical_max, swap) {
+            return Err(SAVE_STATE_MEMORY_MISMATCH);
+        }
+        let page_count = r.u64()?;
+        let mut pages = Vec::new();
+        for _ in 0..page_count {
+            let index = r.u64()? as usize;
+            pages.push((index, Box::new(r.take::<PAGE_SIZE>()?)));
+        }
+
+        Ok(SavedMachine {
+            registers,
+            registers_allocated,
+            eflags,
+            instruction_fetch,
+            control_registers,
+            efer,
+            pdptes,
+            segments,
+            descriptor_tables,
+            debug,
+            msrs,
+            memory_size,
+            memory_offset,
+            pages,
+        })
+    }
+
+    fn apply_state(&mut self, saved: SavedMachine) {
+        self.registers = saved.registers;
+        self.registers_allocated = saved.registers_allocated;
+        self.write_eflags(saved.eflags);
+        self.instruction_fetch = saved.instruction_fetch;
+        // Restore the registers verbatim rather than through MOV to CRn:
+        // the saved PDPTEs may differ from what CR3 points at now.
+        self.control_registers = saved.control_registers;
+        self.efer = saved.efer;
+        self.pdptes = saved.pdptes;
+        self.tlb.set_pcid(self.current_pcid());
+        self.tlb.flush(true);
+        self.segments = saved.segments;
+        self.descriptor_tables = saved.descriptor_tables;
+        self.debug.restore(saved.debug);
+        for (index, value) in saved.msrs {
+            self.write_msr(index, value);
+        }
+        if let Some(memory) = unsafe { self.memory.as_mut() } {
+            memory.restore_pages(saved.memory_size, saved.memory_offset, saved.pages);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! Hidden segment descriptor caches and segmented address translation.
+//!
+//! Every segment register carries a hidden part (base, limit, attributes)
+//! that is only refreshed when the selector is loaded. Keeping that part
+//! here instead of in PHP lets a single call turn (segment, offset) into a
+//! linear address with the limit, type and privilege checks applied.
+
+use super::fault::{pack_fault, VECTOR_GP, VECTOR_SS};
+use super::MemoryAccessor;
+
+pub const SEGMENT_ES: usize = 0;
+pub const SEGMENT_CS: usize = 1;
+pub const SEGMENT_SS: usize = 2;
+pub const SEGMENT_DS: usize = 3;
+pub const SEGMENT_FS: usize = 4;
+pub const SEGMENT_GS: usize = 5;
+pub const SEGMENT_COUNT: usize = 6;
+
+/// Register address of ES; the other segment registers follow in order.
+pub(crate) const SEGMENT_REGISTER_BASE: usize = 8;
+
+/// Attribute layout: descriptor byte 5 in bits 0-7, the flags nibble of
+/// byte 6 (AVL, L, D/B, G) in bits 12-15.
+pub const SEGMENT_ATTR_ACCESSED: u16 = 1 << 0;
+pub const SEGMENT_ATTR_READ_WRITE: u16 = 1 << 1;
+pub const SEGMENT_ATTR_DIRECTION: u16 = 1 << 2;
+pub const SEGMENT_ATTR_CODE: u16 = 1 << 3;
+pub const SEGMENT_ATTR_NON_SYSTEM: u16 = 1 << 4;
+pub const SEGMENT_ATTR_PRESENT: u16 = 1 << 7;
+pub const SEGMENT_ATTR_AVL: u16 = 1 << 12;
+pub const SEGMENT_ATTR_LONG: u16 = 1 << 13;
+pub const SEGMENT_ATTR_DEFAULT_BIG: u16 = 1 << 14;
+pub const SEGMENT_ATTR_GRANULARITY: u16 = 1 << 15;
+
+/// Attributes of a segment loaded in real mode (present, read/write, accessed).
+pub const SEGMENT_ATTR_REAL_MODE_DATA: u16 = 0x93;
+/// Attributes of CS in real mode (present, execute/read, accessed).
+pub const SEGMENT_ATTR_REAL_MODE_CODE: u16 = 0x9B;
+
+/// Hidden part of a segment register.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SegmentCache {
+    pub selector: u16,
+    pub attributes: u16,
+    /// Byte-granular limit (already scaled when G=1).
+    pub limit: u32,
+    pub base: u64,
+}
+
+impl SegmentCache {
+    /// Cache contents after reset or a real-mode load of `selector`.
+    pub const fn real_mode(selector: u16, attributes: u16) -> Self {
+        SegmentCache {
+            selector,
+            attributes,
+            limit: 0xFFFF,
+            base: (selector as u64) << 4,
+        }
+    }
+
+    #[inline(always)]
+    pub fn dpl(&self) -> u8 {
+        ((self.attributes >> 5) & 0x3) as u8
+    }
+
+    #[inline(always)]
+    pub fn is_present(&self) -> bool {
+        (self.attributes & SEGMENT_ATTR_PRESENT) != 0
+    }
+
+    #[inline(always)]
+    pub fn is_code(&self) -> bool {
+        (self.attributes & SEGMENT_ATTR_CODE) != 0
+    }
+
+    #[inline(always)]
+    pub fn is_readable(&self) -> bool {
+        !self.is_code() || (self.attributes & SEGMENT_ATTR_READ_WRITE) != 0
+    }
+
+    #[inline(always)]
+    pub fn is_writable(&self) -> bool {
+        !self.is_code() && (self.attributes & SEGMENT_ATTR_READ_WRITE) != 0
+    }
+
+    #[inline(always)]
+    pub fn is_conforming(&self) -> bool {
+        self.is_code() && (self.attributes & SEGMENT_ATTR_DIRECTION) != 0
+    }
+
+    #[inline(always)]
+    pub fn is_expand_down(&self) -> bool {
+        !self.is_code() && (self.attributes & SEGMENT_ATTR_DIRECTION) != 0
+    }
+
+    #[inline(always)]
+    pub fn is_long(&self) -> bool {
+        (self.attributes & SEGMENT_ATTR_LONG) != 0
+    }
+
+    #[inline(always)]
+    pub fn is_default_big(&self) -> bool {
+        (self.attributes & SEGMENT_ATTR_DEFAULT_BIG) != 0
+    }
+}
+
+/// Kind of access performed through a segment.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SegmentAccess {
+    Read = 0,
+    Write = 1,
+    Execute = 2,
+}
+
+impl SegmentAccess {
+    /// Decode the FFI representation; unknown values are treated as reads.
+    pub fn from_raw(kind: u32) -> Self {
+        match kind {
+            1 => SegmentAccess::Write,
+            2 => SegmentAccess::Execute,
+            _ => SegmentAccess::Read,
+        }
+    }
+}
+
+impl MemoryAccessor {
+    /// Segment caches as they are after reset.
+    pub(crate) fn reset_segment_caches() -> [SegmentCache; SEGMENT_COUNT] {
+        let mut segments = [SegmentCache::real_mode(0, SEGMENT_ATTR_REAL_MODE_DATA); SEGMENT_COUNT];
+        segments[SEGMENT_CS] = SegmentCache::real_mode(0, SEGMENT_ATTR_REAL_MODE_CODE);
+        segments
+    }
+
+    /// Read the hidden part of a segment register.
+    #[inline(always)]
+    pub fn segment_cache(&self, segment: usize) -> Option<SegmentCache> {
+        self.segments.get(segment).copied()
+    }
+
+    /// Replace the hidden part of a segment register (protected-mode load).
+    pub fn load_segment_cache(&mut self, segment: usize, cache: SegmentCache) -> bool {
+        if segment >= SEGMENT_COUNT {
+            return false;
+        }
+        self.segments[segment] = cache;
+        self.registers[SEGMENT_REGISTER_BASE + segment] = cache.selector as i64;
+        self.registers_allocated[SEGMENT_REGISTER_BASE + segment] = true;
+        true
+    }
+
+    /// Real-mode selector load.
+    ///
+    /// Only the selector and base change; the hidden limit and attributes
+    /// are retained, which is exactly what makes unreal mode work.
+    pub fn load_segment_real_mode(&mut self, segment: usize, selector: u16) {
+        if let Some(cache) = self.segments.get_mut(segment) {
+            cache.selector = selector;
+            cache.base = (selector as u64) << 4;
+        }
+    }
+
+    #[inline(always)]
+    pub fn protected_mode_enabled(&self) -> bool {
+        (self.control_registers[0] & 0x1) != 0
+    }
+
+    /// EFER.LMA: IA-32e mode is active.
+    #[inline(always)]
+    pub fn long_mode_active(&self) -> bool {
+        (self.efer & (1 << 10)) != 0
+    }
+
+    /// 64-bit sub-mode of IA-32e (CS.L=1).
+    #[inline(always)]
+    pub fn is_64bit_mode(&self) -> bool {
+        self.long_mode_active() && self.segments[SEGMENT_CS].is_long()
+    }
+
+    /// Current privilege level, taken from the CS selector's RPL.
+    #[inline(always)]
+    pub fn current_privilege_level(&self) -> u8 {
+        if !self.protected_mode_enabled() {
+            return 0;
+        }
+        (self.segments[SEGMENT_CS].selector & 0x3) as u8
+    }
+
+    /// Translate (segment, offset) to a linear address.
+    ///
+    /// `size` is the access width in bits. Returns (linear, error) where error
+    /// is 0 on success or a packed #GP(0)/#SS(0) on a limit, type or
+    /// privilege violation.
+    pub fn segmented_linear(
+        &self,
+        segment: usize,
+        offset: u64,
+        size: u32,
+        kind: SegmentAccess,
+    ) -> (u64, u32) {
+        let Some(cache) = self.segment_cache(segment) else {
+            return (offset, pack_fault(VECTOR_GP, 0));
+        };
+        let fault = if segment == SEGMENT_SS {
+            pack_fault(VECTOR_SS, 0)
+        } else {
+            pack_fault(VECTOR_GP, 0)
+        };
+        let bytes = (size.max(8) / 8) as u64;
+
+        if self.is_64bit_mode() {
+            let base = if segment == SEGMENT_FS || segment == SEGMENT_GS {
+                cache.base
+            } else {
+                0
+            };
+            let linear = base.wrapping_add(offset);
+            let last = linear.wrapping_add(bytes - 1);
+            if !self.is_canonical(linear) || !self.is_canonical(last) {
+                return (linear, fault);
+            }
+            return (linear, 0);
+        }
+
+        let offset = offset & 0xFFFF_FFFF;
+
+        if self.protected_mode_enabled() {
+            if !cache.is_present() {
+                return (offset, fault);
+            }
+            if let Some(err) = Self::check_segment_type(&cache, kind, fault) {
+                return (offset, err);
+            }
+            let cpl = self.current_privilege_level();
+            if segment == SEGMENT_SS {
+                if cache.dpl() != cpl {
+                    return (offset, fault);
+                }
+            } else if segment != SEGMENT_CS && !cache.is_conforming() && cpl > cache.dpl() {
+                return (offset, fault);
+            }
+        }
+
+        if !Self::within_segment_limit(&cache, offset, bytes) {
+            return (offset, fault);
+        }
+
+        (cache.base.wrapping_add(offset) & 0xFFFF_FFFF, 0)
+    }
+
+    /// Type checks applied on every protected-mode access.
+    #[inline(always)]
+    fn check_segment_type(cache: &SegmentCache, kind: SegmentAccess, fault: u32) -> Option<u32> {
+        let allowed = match kind {
+            SegmentAccess::Read => cache.is_readable(),
+            SegmentAccess::Write => cache.is_writable(),
+            SegmentAccess::Execute => cache.is_code(),
+        };
+        if allowed {
+            None
+        } else {
+            Some(fault)
+        }
+    }
+
+    /// Limit check honouring expand-down data segments.
+    #[inline(always)]
+    fn within_segment_limit(cache: &SegmentCache, offset: u64, bytes: u64) -> bool {
+        let last = offset + bytes - 1;
+        let limit = cache.limit as u64;
+        if cache.is_expand_down() {
+            let upper = if cache.is_default_big() { 0xFFFF_FFFF } else { 0xFFFF };
+            offset > limit && last <= upper
+        } else {
+            last <= limit
+        }
+    }
+
+    /// Canonical-address check for IA-32e linear addresses (48 or 57 bits
+    /// wide depending on CR4.LA57).
+    #[inline(always)]
+    pub fn is_canonical(&self, linear: u64) -> bool {
+        self.canonicalize(linear) == linear
+    }
+}
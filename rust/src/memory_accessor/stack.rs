@@ -0,0 +1,123 @@
+//! Stack push/pop through SS.
+//!
+//! The stack width follows the SS B bit (or is 64-bit in 64-bit mode), the
+//! access goes through segmentation and paging, and RSP is only written once
+//! the memory access has succeeded so a fault leaves it untouched. Only 16-,
+//! 32- and 64-bit operands exist on the stack; any other size is rejected
+//! with #GP(0) rather than silently narrowed.
+
+use super::fault::{pack_fault, VECTOR_GP};
+use super::segment::{SegmentAccess, SEGMENT_SS};
+use super::MemoryAccessor;
+
+/// Register address of RSP/ESP/SP.
+const STACK_POINTER: usize = 4;
+
+impl MemoryAccessor {
+    /// Stack address size in bits: 64 in 64-bit mode, else SS.B ? 32 : 16.
+    #[inline(always)]
+    pub fn stack_address_size(&self) -> u32 {
+        if self.is_64bit_mode() {
+            64
+        } else if self.segments[SEGMENT_SS].is_default_big() {
+            32
+        } else {
+            16
+        }
+    }
+
+    #[inline(always)]
+    fn stack_pointer_mask(address_size: u32) -> u64 {
+        match address_size {
+            16 => 0xFFFF,
+            32 => 0xFFFF_FFFF,
+            _ => u64::MAX,
+        }
+    }
+
+    #[inline(always)]
+    fn is_stack_operand_size(size: u32) -> bool {
+        matches!(size, 16 | 32 | 64)
+    }
+
+    /// Push `value` (`size` bits) onto the stack.
+    ///
+    /// Returns 0 on success or a packed #SS/#GP/#PF (or the MMIO signal);
+    /// RSP is unchanged on any error, including an unsupported `size`.
+    pub fn push(
+        &mut self,
+        value: u64,
+        size: u32,
+        is_user: bool,
+        paging_enabled: bool,
+        linear_mask: u64,
+    ) -> u32 {
+        if !Self::is_stack_operand_size(size) {
+            return pack_fault(VECTOR_GP, 0);
+        }
+        let address_size = self.stack_address_size();
+        let mask = Self::stack_pointer_mask(address_size);
+        let bytes = (size / 8) as u64;
+        let new_sp = (self.registers[STACK_POINTER] as u64).wrapping_sub(bytes) & mask;
+
+        let (linear, err) = self.segmented_linear(SEGMENT_SS, new_sp, size, SegmentAccess::Write);
+        if err != 0 {
+            return err;
+        }
+
+        let err = match size {
+            16 => self.write_memory_16(linear, value as u16, is_user, paging_enabled, linear_mask),
+            32 => self.write_memory_32(linear, value as u32, is_user, paging_enabled, linear_mask),
+            _ => self.write_memory_64(linear, value, is_user, paging_enabled, linear_mask),
+        };
+        if err != 0 {
+            return err;
+        }
+
+        self.write_by_size(STACK_POINTER, new_sp as i64, address_size);
+        0
+    }
+
+    /// Pop a `size`-bit value from the stack.
+    ///
+    /// Returns (value, error); RSP is only advanced when error is 0. An
+    /// unsupported `size` yields #GP(0).
+    pub fn pop(
+        &mut self,
+        size: u32,
+        is_user: bool,
+        paging_enabled: bool,
+        linear_mask: u64,
+    ) -> (u64, u32) {
+        if !Self::is_stack_operand_size(size) {
+            return (0, pack_fault(VECTOR_GP, 0));
+        }
+        let address_size = self.stack_address_size();
+        let mask = Self::stack_pointer_mask(address_size);
+        let bytes = (size / 8) as u64;
+        let sp = (self.registers[STACK_POINTER] as u64) & mask;
+
+        let (linear, err) = self.segmented_linear(SEGMENT_SS, sp, size, SegmentAccess::Read);
+        if err != 0 {
+            return (0, err);
+        }
+
+        let (value, err) = match size {
+            16 => {
+                let (v, e) = self.read_memory_16(linear, is_user, paging_enabled, linear_mask);
+                (v as u64, e)
+            }
+            32 => {
+                let (v, e) = self.read_memory_32(linear, is_user, paging_enabled, linear_mask);
+                (v as u64, e)
+            }
+            _ => self.read_memory_64(linear, is_user, paging_enabled, linear_mask),
+        };
+        if err != 0 {
+            return (0, err);
+        }
+
+        self.write_by_size(STACK_POINTER, (sp.wrapping_add(bytes) & mask) as i64, address_size);
+        (value, 0)
+    }
+}
@@ -0,0 +1,70 @@
+//! Bulk CPU state transfer.
+//!
+//! `CpuState` gathers what PHP otherwise reads one register at a time
+//! through the register address layout: the GPRs in encoding order, RIP,
+//! EFLAGS, segment selectors, control registers and EFER.
+
+use super::segment::{SEGMENT_COUNT, SEGMENT_REGISTER_BASE};
+use super::MemoryAccessor;
+
+/// Register addresses of RAX-RDI and R8-R15, in encoding order.
+pub(crate) const GPR_ADDRESSES: [usize; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 16, 17, 18, 19, 20, 21, 22, 23];
+const RIP_ADDRESS: usize = 24;
+
+/// Architectural register state. Hidden segment descriptor caches are not
+/// included; `load_segment_cache` restores those.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuState {
+    /// RAX, RCX, RDX, RBX, RSP, RBP, RSI, RDI, R8-R15.
+    pub gprs: [u64; 16],
+    pub rip: u64,
+    /// Full EFLAGS image.
+    pub rflags: u64,
+    /// CR0-CR8; CR1 and CR5-CR7 read as zero.
+    pub control_registers: [u64; 9],
+    pub efer: u64,
+    /// ES, CS, SS, DS, FS, GS selectors.
+    pub segments: [u16; SEGMENT_COUNT],
+}
+
+impl MemoryAccessor {
+    /// Capture the register state in one call.
+    pub fn cpu_state(&self) -> CpuState {
+        let mut state = CpuState {
+            rip: self.registers[RIP_ADDRESS] as u64,
+            rflags: self.read_eflags() as u64,
+            control_registers: self.control_registers,
+            efer: self.efer,
+            ..Default::default()
+        };
+        for (gpr, &address) in state.gprs.iter_mut().zip(GPR_ADDRESSES.iter()) {
+            *gpr = self.registers[address] as u64;
+        }
+        for (segment, selector) in state.segments.iter_mut().enumerate() {
+            *selector = self.registers[SEGMENT_REGISTER_BASE + segment] as u16;
+        }
+        state
+    }
+
+    /// Load the register state in one call.
+    ///
+    /// EFER and the control registers go first, through the same paths as
+    /// MOV to CRn (TLB flushes, PDPTE loads), with CR3 after CR0/CR4. The
+    /// selectors are then written like a MOV to Sreg in the resulting mode:
+    /// real-mode loads refresh the cached base.
+    pub fn set_cpu_state(&mut self, state: &CpuState) {
+        self.write_efer(state.efer);
+        for index in [0, 2, 4, 8, 3] {
+            self.write_control_register(index, state.control_registers[index]);
+        }
+        for (&value, &address) in state.gprs.iter().zip(GPR_ADDRESSES.iter()) {
+            self.write_by_size(address, value as i64, 64);
+        }
+        self.write_by_size(RIP_ADDRESS, state.rip as i64, 64);
+        self.write_eflags(state.rflags as u32);
+        for (segment, &selector) in state.segments.iter().enumerate() {
+            self.write_by_size(SEGMENT_REGISTER_BASE + segment, selector as i64, 16);
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! 32-bit hardware task switching.
+//!
+//! Covers JMP/CALL to a TSS or task gate, task gates in the IDT and IRET
+//! with NT set. Checks that can fail before the switch commits leave the
+//! machine untouched; faults raised while loading the new task's segments
+//! are reported after the switch, i.e. in the context of the new task, as
+//! the SDM describes.
+
+use super::descriptor::{
+    DescriptorTableRegister, SegmentDescriptor, DESCRIPTOR_CODE, DESCRIPTOR_DATA, DESCRIPTOR_LDT,
+    DESCRIPTOR_TASK_GATE, DESCRIPTOR_TSS_AVAILABLE, DESCRIPTOR_TSS_BUSY, TABLE_GDTR, TABLE_IDTR,
+    TABLE_LDTR, TABLE_TR,
+};
+use super::fault::{pack_fault, VECTOR_GP, VECTOR_NP, VECTOR_SS, VECTOR_TS};
+use super::segment::{
+    SegmentCache, SEGMENT_COUNT, SEGMENT_CS, SEGMENT_DS, SEGMENT_ES, SEGMENT_FS, SEGMENT_GS,
+    SEGMENT_REGISTER_BASE, SEGMENT_SS,
+};
+use super::{MemoryAccessor, CR0_TS, EFLAGS_NT, EFLAGS_VM};
+
+/// 32-bit TSS field offsets.
+const TSS_BACKLINK: u64 = 0x00;
+const TSS_CR3: u64 = 0x1C;
+const TSS_EIP: u64 = 0x20;
+const TSS_EFLAGS: u64 = 0x24;
+/// EAX, ECX, EDX, EBX, ESP, EBP, ESI, EDI in register-address order.
+const TSS_GPRS: u64 = 0x28;
+/// ES, CS, SS, DS, FS, GS in segment-index order, 4 bytes apart.
+const TSS_SEGMENTS: u64 = 0x48;
+const TSS_LDT: u64 = 0x60;
+
+/// Smallest valid limit of a 32-bit TSS.
+const TSS32_MIN_LIMIT: u32 = 0x67;
+
+/// Attributes of a segment loaded in virtual-8086 mode (DPL 3 data).
+const V86_SEGMENT_ATTRIBUTES: u16 = 0xF3;
+
+/// What initiated a task switch.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskSwitchReason {
+    Jump = 0,
+    Call = 1,
+    Iret = 2,
+    Interrupt = 3,
+}
+
+impl TaskSwitchReason {
+    /// Decode the FFI representation; unknown values are treated as JMP.
+    pub fn from_raw(reason: u32) -> Self {
+        match reason {
+            1 => TaskSwitchReason::Call,
+            2 => TaskSwitchReason::Iret,
+            3 => TaskSwitchReason::Interrupt,
+            _ => TaskSwitchReason::Jump,
+        }
+    }
+
+    /// CALL and interrupts nest the new task (NT + back-link).
+    #[inline(always)]
+    fn nests(self) -> bool {
+        matches!(self, TaskSwitchReason::Call | TaskSwitchReason::Interrupt)
+    }
+}
+
+/// State read from the incoming TSS before anything is committed.
+struct TaskState {
+    cr3: u32,
+    eip: u32,
+    eflags: u32,
+    gprs: [u32; 8],
+    selectors: [u16; SEGMENT_COUNT],
+    ldt: u16,
+}
+
+impl MemoryAccessor {
+    /// JMP/CALL far to `selector`, which names a TSS or a task gate.
+    ///
+    /// `return_eip` is the EIP saved into the outgoing TSS. Returns
+    /// (new_eip, error); when error is non-zero and the switch already
+    /// committed, new_eip is the entry point of the new task.
+    pub fn task_switch(&mut self, selector: u16, reason: TaskSwitchReason, return_eip: u32) -> (u32, u32) {
+        let gp = pack_fault(VECTOR_GP, (selector & 0xFFFC) as u32);
+        let gdtr = self.descriptor_tables[TABLE_GDTR];
+        let ldtr = self.descriptor_tables[TABLE_LDTR];
+        let (descriptor, err) = self.read_segment_descriptor(&gdtr, &ldtr, selector, false);
+        if err != 0 {
+            return (return_eip, err);
+        }
+
+        let cpl = self.current_privilege_level();
+        let rpl = (selector & 0x3) as u8;
+        if cpl.max(rpl) > descriptor.dpl {
+            return (return_eip, gp);
+        }
+
+        let tss_selector = match descriptor.kind {
+            DESCRIPTOR_TASK_GATE => {
+                if !descriptor.present {
+                    return (return_eip, pack_fault(VECTOR_NP, (selector & 0xFFFC) as u32));
+                }
+                descriptor.selector
+            }
+            DESCRIPTOR_TSS_AVAILABLE | DESCRIPTOR_TSS_BUSY => selector,
+            _ => return (return_eip, gp),
+        };
+
+        self.switch_to_task(tss_selector, reason, return_eip)
+    }
+
+    /// Deliver `vector` through an IDT task gate.
+    ///
+    /// The error code, if any, is pushed on the new task's stack.
+    pub fn interrupt_task_switch(&mut self, vector: u8, return_eip: u32, error_code: Option<u32>) -> (u32, u32) {
+        let idt_error = ((vector as u32) << 3) | 0x2;
+        let idtr = self.descriptor_tables[TABLE_IDTR];
+        let (gate, err) = self.read_gate_descriptor(&idtr, vector);
+        if err != 0 {
+            return (return_eip, err);
+        }
+        if gate.kind != DESCRIPTOR_TASK_GATE {
+            return (return_eip, pack_fault(VECTOR_GP, idt_error));
+        }
+        if !gate.present {
+            return (return_eip, pack_fault(VECTOR_NP, idt_error));
+        }
+
+        let (eip, err) = self.switch_to_task(gate.selector, TaskSwitchReason::Interrupt, return_eip);
+        if err != 0 {
+            return (eip, err);
+        }
+        if let Some(code) = error_code {
+            let is_user = self.current_privilege_level() == 3;
+            let paging_enabled = self.paging_enabled();
+            let linear_mask = self.system_linear_mask();
+            let err = self.push(code as u64, 32, is_user, paging_enabled, linear_mask);
+            return (eip, err);
+        }
+        (eip, 0)
+    }
+
+    /// IRET with EFLAGS.NT set: return to the task named by the back-link.
+    pub fn iret_task_switch(&mut self, return_eip: u32) -> (u32, u32) {
+        let tr = self.descriptor_tables[TABLE_TR];
+        let (backlink, err) = self.read_system_16(tr.base.wrapping_add(TSS_BACKLINK));
+        if err != 0 {
+            return (return_eip, err);
+        }
+        self.switch_to_task(backlink, TaskSwitchReason::Iret, return_eip)
+    }
+
+    fn switch_to_task(&mut self, tss_selector: u16, reason: TaskSwitchReason, return_eip: u32) -> (u32, u32) {
+        let is_iret = reason == TaskSwitchReason::Iret;
+        let selector_error = (tss_selector & 0xFFFC) as u32;
+        let invalid = if is_iret {
+            pack_fault(VECTOR_TS, selector_error)
+        } else {
+            pack_fault(VECTOR_GP, selector_error)
+        };
+
+        // The TSS descriptor must live in the GDT.
+        if (tss_selector & 0x4) != 0 {
+            return (return_eip, invalid);
+        }
+        let gdtr = self.descriptor_tables[TABLE_GDTR];
+        let ldtr = self.descriptor_tables[TABLE_LDTR];
+        let (new_tss, err) = self.read_segment_descriptor(&gdtr, &ldtr, tss_selector, false);
+        if err != 0 {
+            return (return_eip, if is_iret { invalid } else { err });
+        }
+
+        let expected = if is_iret { DESCRIPTOR_TSS_BUSY } else { DESCRIPTOR_TSS_AVAILABLE };
+        if new_tss.kind != expected || new_tss.default_size != 32 {
+            return (return_eip, invalid);
+        }
+        if !new_tss.present {
+            return (return_eip, pack_fault(VECTOR_NP, selector_error));
+        }
+        if new_tss.limit < TSS32_MIN_LIMIT {
+            return (return_eip, pack_fault(VECTOR_TS, selector_error));
+        }
+
+        let old_tr = self.descriptor_tables[TABLE_TR];
+        if old_tr.limit < TSS32_MIN_LIMIT {
+            return (return_eip, pack_fault(VECTOR_TS, (old_tr.selector & 0xFFFC) as u32));
+        }
+
+        let state = match self.read_task_state(new_tss.base) {
+            Ok(state) => state,
+            Err(err) => return (return_eip, err),
+        };
+
+        // Save the outgoing task.
+        let mut old_eflags = self.read_eflags();
+        if is_iret {
+            old_eflags &= !EFLAGS_NT;
+        }
+        let err = self.save_task_state(old_tr.base, return_eip, old_eflags);
+        if err != 0 {
+            return (return_eip, err);
+        }
+
+        if matches!(reason, TaskSwitchReason::Jump | TaskSwitchReason::Iret) {
+            let err = self.set_tss_busy(old_tr.selector, false);
+            if err != 0 {
+                return (return_eip, err);
+            }
+        }
+
+        let mut new_eflags = state.eflags;
+        if reason.nests() {
+            let err = self.write_system_16(new_tss.base.wrapping_add(TSS_BACKLINK), old_tr.selector);
+            if err != 0 {
+                return (return_eip, err);
+            }
+            new_eflags |= EFLAGS_NT;
+        }
+        if !is_iret {
+            let err = self.write_system_8(
+                gdtr.base.wrapping_add((tss_selector & 0xFFF8) as u64 + 5),
+                (new_tss.attributes as u8) | 0x2,
+            );
+            if err != 0 {
+                return (return_eip, err);
+            }
+        }
+
+        // Commit: TR, CR0.TS and the new task's registers.
+        self.descriptor_tables[TABLE_TR] = DescriptorTableRegister {
+            base: new_tss.base,
+            limit: new_tss.limit,
+            selector: tss_selector,
+        };
+        self.control_registers[0] |= CR0_TS;
+        if self.paging_enabled() {
+            self.write_control_register(3, state.cr3 as u64);
+        }
+        self.write_eflags(new_eflags);
+        for (index, value) in state.gprs.iter().enumerate() {
+            self.write_by_size(index, *value as i64, 32);
+        }
+        for (segment, selector) in state.selectors.iter().enumerate() {
+            self.segments[segment] = SegmentCache { selector: *selector, ..SegmentCache::default() };
+            self.registers[SEGMENT_REGISTER_BASE + segment] = *selector as i64;
+        }
+
+        let err = self.load_task_segments(&state, (new_eflags & EFLAGS_VM) != 0);
+        (state.eip, err)
+    }
+
+    /// Read the dynamic and static fields of an incoming 32-bit TSS.
+    fn read_task_state(&mut self, base: u64) -> Result<TaskState, u32> {
+        let read32 = |acc: &mut Self, offset: u64| -> Result<u32, u32> {
+            match acc.read_system_32(base.wrapping_add(offset)) {
+                (value, 0) => Ok(value),
+                (_, err) => Err(err),
+            }
+        };
+
+        let mut gprs = [0u32; 8];
+        for (index, slot) in gprs.iter_mut().enumerate() {
+            *slot = read32(self, TSS_GPRS + (index as u64) * 4)?;
+        }
+        let mut selectors = [0u16; SEGMENT_COUNT];
+        for (index, slot) in selectors.iter_mut().enumerate() {
+            *slot = read32(self, TSS_SEGMENTS + (index as u64) * 4)? as u16;
+        }
+
+        Ok(TaskState {
+            cr3: read32(self, TSS_CR3)?,
+            eip: read32(self, TSS_EIP)?,
+            eflags: read32(self, TSS_EFLAGS)?,
+            gprs,
+            selectors,
+            ldt: read32(self, TSS_LDT)? as u16,
+        })
+    }
+
+    /// Write EIP, EFLAGS, the GPRs and the segment selectors to the outgoing TSS.
+    fn save_task_state(&mut self, base: u64, eip: u32, eflags: u32) -> u32 {
+        let err = self.write_system_32(base.wrapping_add(TSS_EIP), eip);
+        if err != 0 {
+            return err;
+        }
+        let err = self.write_system_32(base.wrapping_add(TSS_EFLAGS), eflags);
+        if err != 0 {
+            return err;
+        }
+        for index in 0..8 {
+            let value = self.registers[index] as u32;
+            let err = self.write_system_32(base.wrapping_add(TSS_GPRS + (index as u64) * 4), value);
+            if err != 0 {
+                return err;
+            }
+        }
+        for segment in 0..SEGMENT_COUNT {
+            let selector = self.segments[segment].selector;
+            let err = self.write_system_16(base.wrapping_add(TSS_SEGMENTS + (segment as u64) * 4), selector);
+            if err != 0 {
+                return err;
+            }
+        }
+        0
+    }
+
+    /// Set or clear the busy bit of the TSS descriptor for `selector`.
+    fn set_tss_busy(&mut self, selector: u16, busy: bool) -> u32 {
+        if (selector & 0xFFFC) == 0 {
+            return 0;
+        }
+        let gdtr = self.descriptor_tables[TABLE_GDTR];
+        let ldtr = self.descriptor_tables[TABLE_LDTR];
+        let (descriptor, err) = self.read_segment_descriptor(&gdtr, &ldtr, selector, false);
+        if err != 0 {
+            return err;
+        }
+        let mut access = descriptor.attributes as u8;
+        if busy {
+            access |= 0x2;
+        } else {
+            access &= !0x2;
+        }
+        self.write_system_8(gdtr.base.wrapping_add((selector & 0xFFF8) as u64 + 5), access)
+    }
+
+    /// Load LDTR and the segment caches of the new task (LDTR, CS, SS, then data segments).
+    fn load_task_segments(&mut self, state: &TaskState, v86: bool) -> u32 {
+        let gdtr = self.descriptor_tables[TABLE_GDTR];
+
+        self.descriptor_tables[TABLE_LDTR] = DescriptorTableRegister { selector: state.ldt, ..Default::default() };
+        if (state.ldt & 0xFFFC) != 0 {
+            let ts = pack_fault(VECTOR_TS, (state.ldt & 0xFFFC) as u32);
+            if (state.ldt & 0x4) != 0 {
+                return ts;
+            }
+            let null_ldtr = DescriptorTableRegister::default();
+            let (ldt, err) = self.read_segment_descriptor(&gdtr, &null_ldtr, state.ldt, false);
+            if err != 0 || ldt.kind != DESCRIPTOR_LDT || !ldt.present {
+                return ts;
+            }
+            self.descriptor_tables[TABLE_LDTR] = DescriptorTableRegister {
+                base: ldt.base,
+                limit: ldt.limit,
+                selector: state.ldt,
+            };
+        }
+
+        if v86 {
+            for (segment, selector) in state.selectors.iter().enumerate() {
+                let mut cache = SegmentCache::real_mode(*selector, V86_SEGMENT_ATTRIBUTES);
+                if segment == SEGMENT_CS {
+                    cache.attributes |= 0x8;
+                }
+                self.segments[segment] = cache;
+            }
+            return 0;
+        }
+
+        for segment in [SEGMENT_CS, SEGMENT_SS, SEGMENT_DS, SEGMENT_ES, SEGMENT_FS, SEGMENT_GS] {
+            let err = self.load_task_segment(segment, state.selectors[segment]);
+            if err != 0 {
+                return err;
+            }
+        }
+        0
+    }
+
+    /// Validate and load one segment register during a task switch.
+    fn load_task_segment(&mut self, segment: usize, selector: u16) -> u32 {
+        let selector_error = (selector & 0xFFFC) as u32;
+        let ts = pack_fault(VECTOR_TS, selector_error);
+        if selector_error == 0 {
+            // Null data segments are allowed and left unusable; CS/SS are not.
+            return if segment == SEGMENT_CS || segment == SEGMENT_SS { ts } else { 0 };
+        }
+
+        let gdtr = self.descriptor_tables[TABLE_GDTR];
+        let ldtr = self.descriptor_tables[TABLE_LDTR];
+        let (descriptor, err) = self.read_segment_descriptor(&gdtr, &ldtr, selector, false);
+        if err != 0 {
+            return ts;
+        }
+
+        let rpl = (selector & 0x3) as u8;
+        let valid = match segment {
+            SEGMENT_CS => Self::task_code_segment_valid(&descriptor, rpl),
+            SEGMENT_SS => {
+                let cpl = self.current_privilege_level();
+                descriptor.kind == DESCRIPTOR_DATA
+                    && (descriptor.attributes & 0x2) != 0
+                    && descriptor.dpl == cpl
+                    && rpl == cpl
+            }
+            _ => {
+                let cpl = self.current_privilege_level();
+                let cache = descriptor.to_segment_cache(selector);
+                match descriptor.kind {
+                    DESCRIPTOR_DATA => descriptor.dpl >= cpl.max(rpl),
+                    DESCRIPTOR_CODE => {
+                        cache.is_readable() && (cache.is_conforming() || descriptor.dpl >= cpl.max(rpl))
+                    }
+                    _ => false,
+                }
+            }
+        };
+        if !valid {
+            return ts;
+        }
+        if !descriptor.present {
+            let vector = if segment == SEGMENT_SS { VECTOR_SS } else { VECTOR_NP };
+            return pack_fault(vector, selector_error);
+        }
+
+        let (descriptor, err) = self.read_segment_descriptor(&gdtr, &ldtr, selector, true);
+        if err != 0 {
+            return err;
+        }
+        self.segments[segment] = descriptor.to_segment_cache(selector);
+        0
+    }
+
+    /// CS in a new task: code segment whose DPL matches (or, if conforming, does not exceed) its RPL.
+    fn task_code_segment_valid(descriptor: &SegmentDescriptor, rpl: u8) -> bool {
+        if descriptor.kind != DESCRIPTOR_CODE {
+            return false;
+        }
+        let conforming = (descriptor.attributes & 0x4) != 0;
+        if conforming {
+            descriptor.dpl <= rpl
+        } else {
+            descriptor.dpl == rpl
+        }
+    }
+}
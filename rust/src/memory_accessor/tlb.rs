@@ -0,0 +1,191 @@
+//! Software TLB for `translate_linear`.
+//!
+//! A direct-mapped cache of completed page walks at 4K granularity. Each
+//! entry keeps the walk's combined rights rather than a yes/no answer, so a
+//! hit is re-checked against the current CPL, CR0.WP, SMEP/SMAP and NXE just
+//! like a fresh walk. Faults are never cached: a failed check falls back to
+//! the walker, which sets CR2 and builds the error code. Entries only serve
+//! writes once the leaf's dirty bit has been set.
+//!
+//! With CR4.PCIDE set, entries are tagged with the PCID from CR3[11:0] and
+//! only non-global entries of the current PCID (or global ones) are hit, so
+//! switching address spaces does not have to discard other contexts.
+
+use super::fault::{pack_fault, VECTOR_GP};
+use super::paging::PageRights;
+use super::{MemoryAccessor, CR4_PCIDE};
+
+const TLB_ENTRIES: usize = 512;
+
+/// INVPCID invalidation types.
+pub const INVPCID_INDIVIDUAL_ADDRESS: u64 = 0;
+pub const INVPCID_SINGLE_CONTEXT: u64 = 1;
+pub const INVPCID_ALL_CONTEXTS_GLOBAL: u64 = 2;
+pub const INVPCID_ALL_CONTEXTS: u64 = 3;
+
+#[derive(Clone, Copy)]
+pub(crate) struct TlbEntry {
+    /// 4K-aligned linear address.
+    linear_page: u64,
+    /// 4K-aligned physical address backing `linear_page`.
+    physical_page: u64,
+    /// Mask selecting the mapping's page number (4K, 2M, 4M or 1G pages),
+    /// used so INVLPG drops every 4K slice of a large page.
+    page_mask: u64,
+    rights: PageRights,
+    /// Process-context identifier the walk was done under.
+    pcid: u16,
+    dirty: bool,
+    global: bool,
+    valid: bool,
+}
+
+impl TlbEntry {
+    const EMPTY: TlbEntry = TlbEntry {
+        linear_page: 0,
+        physical_page: 0,
+        page_mask: 0,
+        rights: PageRights::ALL,
+        pcid: 0,
+        dirty: false,
+        global: false,
+        valid: false,
+    };
+}
+
+pub(crate) struct Tlb {
+    entries: Box<[TlbEntry; TLB_ENTRIES]>,
+    /// PCID of the current address space (0 while CR4.PCIDE is clear).
+    pcid: u16,
+}
+
+impl Tlb {
+    pub(crate) fn new() -> Self {
+        Tlb { entries: Box::new([TlbEntry::EMPTY; TLB_ENTRIES]), pcid: 0 }
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_pcid(&mut self, pcid: u16) {
+        self.pcid = pcid;
+    }
+
+    /// Slot for `linear` in the current PCID. Mixing the PCID in keeps
+    /// contexts that share linear pages from evicting each other.
+    #[inline(always)]
+    fn slot(&self, linear: u64) -> usize {
+        (((linear >> 12) as usize) ^ ((self.pcid as usize) * 0x9E)) & (TLB_ENTRIES - 1)
+    }
+
+    /// Cached (physical page, rights) for `linear`, if the entry can serve
+    /// this access (writes need the dirty bit already set).
+    #[inline(always)]
+    pub(crate) fn lookup(&self, linear: u64, is_write: bool) -> Option<(u64, PageRights)> {
+        let entry = &self.entries[self.slot(linear)];
+        if !entry.valid
+            || entry.linear_page != (linear & !0xFFF)
+            || (!entry.global && entry.pcid != self.pcid)
+            || (is_write && !entry.dirty)
+        {
+            return None;
+        }
+        Some((entry.physical_page, entry.rights))
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        linear: u64,
+        physical: u64,
+        page_size: u64,
+        rights: PageRights,
+        dirty: bool,
+        global: bool,
+    ) {
+        let slot = self.slot(linear);
+        self.entries[slot] = TlbEntry {
+            linear_page: linear & !0xFFF,
+            physical_page: physical & !0xFFF,
+            page_mask: !(page_size - 1),
+            rights,
+            pcid: self.pcid,
+            dirty,
+            global,
+            valid: true,
+        };
+    }
+
+    /// Drop every entry of every PCID, or every non-global entry.
+    pub(crate) fn flush(&mut self, include_global: bool) {
+        for entry in self.entries.iter_mut() {
+            if include_global || !entry.global {
+                entry.valid = false;
+            }
+        }
+    }
+
+    /// Drop the non-global entries tagged with `pcid`.
+    pub(crate) fn flush_pcid(&mut self, pcid: u16) {
+        for entry in self.entries.iter_mut() {
+            if !entry.global && entry.pcid == pcid {
+                entry.valid = false;
+            }
+        }
+    }
+
+    /// Drop the entries for the page containing `linear` that belong to
+    /// `pcid`; global entries go too when `include_global` is set.
+    pub(crate) fn invalidate(&mut self, linear: u64, pcid: u16, include_global: bool) {
+        for entry in self.entries.iter_mut() {
+            let owned = if entry.global { include_global } else { entry.pcid == pcid };
+            if entry.valid && owned && (entry.linear_page & entry.page_mask) == (linear & entry.page_mask) {
+                entry.valid = false;
+            }
+        }
+    }
+}
+
+impl MemoryAccessor {
+    /// INVLPG: invalidate the TLB entries for the page containing `linear`
+    /// in the current PCID, including global ones.
+    pub fn invlpg(&mut self, linear: u64) {
+        let pcid = self.tlb.pcid;
+        self.tlb.invalidate(linear, pcid, true);
+    }
+
+    /// INVPCID with the descriptor's first quadword (PCID in bits 11:0) and
+    /// its linear address. Returns 0 or #GP(0).
+    ///
+    /// #GP is raised outside CPL 0, for types above 3, reserved descriptor
+    /// bits, a non-canonical address for type 0, and a non-zero PCID for
+    /// types 0 and 1 while CR4.PCIDE is clear.
+    pub fn invpcid(&mut self, kind: u64, descriptor_pcid: u64, linear: u64) -> u32 {
+        let gp = pack_fault(VECTOR_GP, 0);
+        if self.current_privilege_level() != 0 || kind > INVPCID_ALL_CONTEXTS || descriptor_pcid > 0xFFF {
+            return gp;
+        }
+        let pcid = descriptor_pcid as u16;
+        let pcide = (self.control_registers[4] & CR4_PCIDE) != 0;
+        match kind {
+            INVPCID_INDIVIDUAL_ADDRESS | INVPCID_SINGLE_CONTEXT => {
+                if !pcide && pcid != 0 {
+                    return gp;
+                }
+                if kind == INVPCID_SINGLE_CONTEXT {
+                    self.tlb.flush_pcid(pcid);
+                } else {
+                    if !self.is_canonical(linear) {
+                        return gp;
+                    }
+                    self.tlb.invalidate(linear, pcid, false);
+                }
+            }
+            INVPCID_ALL_CONTEXTS_GLOBAL => self.tlb.flush(true),
+            _ => self.tlb.flush(false),
+        }
+        0
+    }
+
+    /// Flush the TLB; global entries survive unless `include_global`.
+    pub fn flush_tlb(&mut self, include_global: bool) {
+        self.tlb.flush(include_global);
+    }
+}
@@ -0,0 +1,206 @@
+//! Native memory-access tracer.
+//!
+//! When enabled, every translated access made through `read_memory_*`,
+//! `write_memory_*`, `fetch_code` and the stack helpers is appended to a
+//! buffered binary log as a fixed 32-byte `TraceRecord`, faults included.
+//! The log starts with a 16-byte header (magic "PHPMTRCE", version u32,
+//! reserved u32); records follow back to back, little-endian.
+//! `convert_trace_log` turns a log into one line of text per record.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::MemoryAccessor;
+
+/// Access types, used both in records and as the filter mask.
+pub const TRACE_READ: u16 = 1 << 0;
+pub const TRACE_WRITE: u16 = 1 << 1;
+pub const TRACE_EXECUTE: u16 = 1 << 2;
+pub const TRACE_ALL: u16 = TRACE_READ | TRACE_WRITE | TRACE_EXECUTE;
+
+pub const TRACE_LOG_VERSION: u32 = 1;
+const TRACE_LOG_MAGIC: &[u8; 8] = b"PHPMTRCE";
+const TRACE_RECORD_SIZE: usize = 32;
+
+/// One traced access. `physical` is 0 when the access faulted before
+/// translation completed.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub linear: u64,
+    pub physical: u64,
+    pub value: u64,
+    /// Packed fault, 0 on success, 0xFFFFFFFF when PHP handled it as MMIO.
+    pub fault: u32,
+    /// One of `TRACE_READ`, `TRACE_WRITE`, `TRACE_EXECUTE`.
+    pub access: u16,
+    /// Width in bytes. Code fetch windows can be up to 15 bytes wide;
+    /// `value` then holds the first 8.
+    pub width: u16,
+}
+
+impl TraceRecord {
+    fn to_bytes(self) -> [u8; TRACE_RECORD_SIZE] {
+        let mut bytes = [0u8; TRACE_RECORD_SIZE];
+        bytes[0..8].copy_from_slice(&self.linear.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.physical.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.value.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.fault.to_le_bytes());
+        bytes[28..30].copy_from_slice(&self.access.to_le_bytes());
+        bytes[30..32].copy_from_slice(&self.width.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; TRACE_RECORD_SIZE]) -> Self {
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        TraceRecord {
+            linear: u64_at(0),
+            physical: u64_at(8),
+            value: u64_at(16),
+            fault: u32::from_le_bytes(bytes[24..28].try_into().unwrap()),
+            access: u16::from_le_bytes([bytes[28], bytes[29]]),
+            width: u16::from_le_bytes([bytes[30], bytes[31]]),
+        }
+    }
+}
+
+impl fmt::Display for TraceRecord {
+    /// `R4 0000000000401000 -> 000008000 = 00000000cafef00d`, with the
+    /// fault appended as `#13(0000)` when there is one.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.access {
+            TRACE_WRITE => 'W',
+            TRACE_EXECUTE => 'X',
+            _ => 'R',
+        };
+        write!(
+            f,
+            "{}{} {:016x} -> {:09x} = {:0width$x}",
+            kind,
+            self.width,
+            self.linear,
+            self.physical,
+            self.value,
+            width = self.width.min(8) as usize * 2
+        )?;
+        match self.fault {
+            0 => Ok(()),
+            0xFFFF_FFFF => write!(f, " mmio"),
+            fault => write!(f, " #{}({:04x})", fault >> 16, fault & 0xFFFF),
+        }
+    }
+}
+
+pub(crate) struct MemoryTracer {
+    out: BufWriter<File>,
+    access_mask: u16,
+    /// Inclusive linear ranges; empty traces every address.
+    ranges: Vec<(u64, u64)>,
+    failed: bool,
+}
+
+impl MemoryTracer {
+    fn wants(&self, linear: u64, access: u16) -> bool {
+        (self.access_mask & access) != 0
+            && (self.ranges.is_empty() || self.ranges.iter().any(|&(start, end)| (start..=end).contains(&linear)))
+    }
+
+    fn record(&mut self, record: TraceRecord) {
+        if !self.failed && self.out.write_all(&record.to_bytes()).is_err() {
+            self.failed = true;
+        }
+    }
+}
+
+impl MemoryAccessor {
+    /// Start tracing accesses whose type is in `access_mask` to a new log at
+    /// `path`, replacing any running trace. Returns false if the file could
+    /// not be created.
+    pub fn start_trace(&mut self, path: &Path, access_mask: u16) -> bool {
+        self.stop_trace();
+        let Ok(file) = File::create(path) else {
+            return false;
+        };
+        let mut out = BufWriter::with_capacity(1 << 16, file);
+        let mut header = [0u8; 16];
+        header[..8].copy_from_slice(TRACE_LOG_MAGIC);
+        header[8..12].copy_from_slice(&TRACE_LOG_VERSION.to_le_bytes());
+        if out.write_all(&header).is_err() {
+            return false;
+        }
+        self.tracer = Some(Box::new(MemoryTracer { out, access_mask, ranges: Vec::new(), failed: false }));
+        true
+    }
+
+    /// Restrict the running trace to accesses starting in `start..=end`
+    /// (linear); ranges accumulate.
+    pub fn add_trace_range(&mut self, start: u64, end: u64) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.ranges.push((start, end));
+        }
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    /// Flush and close the trace. Returns false if any write failed or no
+    /// trace was running.
+    pub fn stop_trace(&mut self) -> bool {
+        match self.tracer.take() {
+            Some(mut tracer) => tracer.out.flush().is_ok() && !tracer.failed,
+            None => false,
+        }
+    }
+
+    /// Hook called by the linear access paths.
+    #[inline(always)]
+    pub(crate) fn trace_access(&mut self, linear: u64, physical: u64, bytes: usize, is_write: bool, value: u64, fault: u32) {
+        if self.tracer.is_none() {
+            return;
+        }
+        let access = if is_write {
+            TRACE_WRITE
+        } else if self.instruction_fetch {
+            TRACE_EXECUTE
+        } else {
+            TRACE_READ
+        };
+        if let Some(tracer) = self.tracer.as_mut().filter(|tracer| tracer.wants(linear, access)) {
+            tracer.record(TraceRecord { linear, physical, value, fault, access, width: bytes as u16 });
+        }
+    }
+}
+
+/// Decode every record of a trace log.
+pub fn read_trace_log(path: &Path) -> io::Result<Vec<TraceRecord>> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut header = [0u8; 16];
+    input.read_exact(&mut header)?;
+    if &header[..8] != TRACE_LOG_MAGIC || header[8..12] != TRACE_LOG_VERSION.to_le_bytes() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a supported trace log"));
+    }
+    let mut records = Vec::new();
+    let mut bytes = [0u8; TRACE_RECORD_SIZE];
+    loop {
+        match input.read_exact(&mut bytes) {
+            Ok(()) => records.push(TraceRecord::from_bytes(&bytes)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(records),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Write the text form of the trace log at `input` to `output`, one record
+/// per line. Returns the number of records.
+pub fn convert_trace_log(input: &Path, output: &Path) -> io::Result<usize> {
+    let records = read_trace_log(input)?;
+    let mut out = BufWriter::new(File::create(output)?);
+    for record in &records {
+        writeln!(out, "{}", record)?;
+    }
+    out.flush()?;
+    Ok(records.len())
+}
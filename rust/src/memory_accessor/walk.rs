@@ -0,0 +1,235 @@
+//! Read-only page-table introspection for debuggers.
+//!
+//! Built on `walk_tables` in `paging.rs`, the walker behind
+//! `translate_linear`, so nothing here sets accessed/dirty bits, writes CR2
+//! or touches the TLB, and every paging format is decoded the same way as
+//! for a real access. The same enumeration backs the physical-to-linear
+//! reverse lookup.
+
+use std::fmt;
+
+use super::fault::{pack_fault, VECTOR_GP, VECTOR_PF};
+use super::paging::{
+    PageRights, PageWalkLevel, PagingFormat, TableWalkEnd, MAX_WALK_LEVELS, PAGE_ACCESSED, PAGE_DIRTY, PAGE_GLOBAL,
+    PAGE_LARGE, PAGE_PRESENT, PF_PRESENT, PF_RESERVED,
+};
+use super::MemoryAccessor;
+
+/// `PageWalk::status` values.
+pub const WALK_MAPPED: u32 = 0;
+pub const WALK_NOT_PRESENT: u32 = 1;
+pub const WALK_RESERVED_BIT: u32 = 2;
+pub const WALK_NON_CANONICAL: u32 = 3;
+/// CR0.PG is clear: the linear address is the physical address.
+pub const WALK_PAGING_DISABLED: u32 = 4;
+
+/// Decoded rights of a walk or mapping. W, U and XD combine all levels; G,
+/// A and D come from the leaf entry.
+pub const PAGE_FLAG_WRITABLE: u32 = 1 << 0;
+pub const PAGE_FLAG_USER: u32 = 1 << 1;
+pub const PAGE_FLAG_EXECUTE_DISABLE: u32 = 1 << 2;
+pub const PAGE_FLAG_GLOBAL: u32 = 1 << 3;
+pub const PAGE_FLAG_ACCESSED: u32 = 1 << 4;
+pub const PAGE_FLAG_DIRTY: u32 = 1 << 5;
+
+/// Result of `walk_page_tables`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageWalk {
+    /// Entries from the top level down; `level_count` are valid.
+    pub levels: [PageWalkLevel; MAX_WALK_LEVELS],
+    pub level_count: u32,
+    /// One of the `WALK_*` values.
+    pub status: u32,
+    pub physical: u64,
+    /// 4K, 2M, 4M or 1G for a mapped address.
+    pub page_size: u64,
+    /// `PAGE_FLAG_*` bits.
+    pub flags: u32,
+    /// The fault a supervisor read would raise: #PF (not present or RSVD),
+    /// #GP for a non-canonical address, 0 when mapped.
+    pub fault: u32,
+}
+
+/// A run of linear pages mapped to contiguous physical memory with the
+/// same rights.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageMapping {
+    pub linear: u64,
+    pub physical: u64,
+    pub size: u64,
+    /// `PAGE_FLAG_WRITABLE`, `_USER`, `_EXECUTE_DISABLE` and `_GLOBAL`.
+    pub flags: u32,
+}
+
+impl fmt::Display for PageMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |bit: u32, set: char| if (self.flags & bit) != 0 { set } else { '-' };
+        write!(
+            f,
+            "{:016x}-{:016x} -> {:09x} r{}{} {}{}",
+            self.linear,
+            self.linear.wrapping_add(self.size - 1),
+            self.physical,
+            flag(PAGE_FLAG_WRITABLE, 'w'),
+            if (self.flags & PAGE_FLAG_EXECUTE_DISABLE) != 0 { '-' } else { 'x' },
+            if (self.flags & PAGE_FLAG_USER) != 0 { 'u' } else { 's' },
+            flag(PAGE_FLAG_GLOBAL, 'g'),
+        )
+    }
+}
+
+/// `PAGE_FLAG_WRITABLE`, `_USER` and `_EXECUTE_DISABLE` for `rights`.
+#[inline(always)]
+fn rights_flags(rights: PageRights) -> u32 {
+    let mut flags = 0;
+    if rights.writable {
+        flags |= PAGE_FLAG_WRITABLE;
+    }
+    if rights.user {
+        flags |= PAGE_FLAG_USER;
+    }
+    if rights.execute_disable {
+        flags |= PAGE_FLAG_EXECUTE_DISABLE;
+    }
+    flags
+}
+
+impl MemoryAccessor {
+    /// Walk the paging structures for `linear` without side effects.
+    pub fn walk_page_tables(&self, linear: u64) -> PageWalk {
+        let mut walk = PageWalk::default();
+        if !self.paging_enabled() {
+            walk.status = WALK_PAGING_DISABLED;
+            walk.physical = linear & self.system_linear_mask();
+            walk.flags = PAGE_FLAG_WRITABLE | PAGE_FLAG_USER;
+            return walk;
+        }
+        let format = self.paging_format();
+        if format.canonical(linear) != linear {
+            walk.status = WALK_NON_CANONICAL;
+            walk.fault = pack_fault(VECTOR_GP, 0);
+            return walk;
+        }
+
+        let tables = self.walk_tables(format, linear);
+        walk.levels = tables.levels;
+        walk.level_count = tables.level_count as u32;
+        match tables.end {
+            TableWalkEnd::NotPresent => {
+                walk.status = WALK_NOT_PRESENT;
+                walk.fault = pack_fault(VECTOR_PF, 0);
+            }
+            TableWalkEnd::ReservedBit => {
+                walk.status = WALK_RESERVED_BIT;
+                walk.fault = pack_fault(VECTOR_PF, PF_PRESENT | PF_RESERVED);
+            }
+            TableWalkEnd::Mapped => {
+                walk.status = WALK_MAPPED;
+                walk.physical = tables.physical;
+                walk.page_size = tables.page_size;
+                walk.flags = rights_flags(tables.rights) | Self::leaf_flags(tables.last_entry());
+            }
+        }
+        walk
+    }
+
+    #[inline(always)]
+    fn leaf_flags(entry: u64) -> u32 {
+        let mut flags = 0;
+        if (entry & PAGE_GLOBAL) != 0 {
+            flags |= PAGE_FLAG_GLOBAL;
+        }
+        if (entry & PAGE_ACCESSED) != 0 {
+            flags |= PAGE_FLAG_ACCESSED;
+        }
+        if (entry & PAGE_DIRTY) != 0 {
+            flags |= PAGE_FLAG_DIRTY;
+        }
+        flags
+    }
+
+    /// Every mapped range under the current CR3, in linear order. Adjacent
+    /// pages with contiguous frames and equal rights are merged; entries
+    /// with reserved bits set are skipped. Empty when paging is off.
+    pub fn page_mappings(&self) -> Vec<PageMapping> {
+        let mut mappings = Vec::new();
+        if self.paging_enabled() {
+            let format = self.paging_format();
+            let root = self.page_table_root(format);
+            self.collect_mappings(format, 0, root, 0, PageRights::ALL, &mut mappings);
+        }
+        mappings
+    }
+
+    fn collect_mappings(
+        &self,
+        format: PagingFormat,
+        depth: usize,
+        table: u64,
+        linear_base: u64,
+        rights: PageRights,
+        mappings: &mut Vec<PageMapping>,
+    ) {
+        let level = &format.levels[depth];
+        for index in 0..(1usize << level.index_bits) {
+            let (_, entry) = self.read_table_entry(format, depth, table, index);
+            if (entry & PAGE_PRESENT) == 0 {
+                continue;
+            }
+            let large_leaf = level.large && (entry & PAGE_LARGE) != 0;
+            if (entry & format.reserved_bits(level, large_leaf)) != 0 {
+                continue;
+            }
+            let linear = linear_base | ((index as u64) << level.shift);
+            let rights = if format.pdpte_registers && depth == 0 { rights } else { rights.merge(entry) };
+            if !large_leaf && depth + 1 < format.levels.len() {
+                let next = entry & format.address_mask() & 0xFFFF_FFFF;
+                self.collect_mappings(format, depth + 1, next, linear, rights, mappings);
+                continue;
+            }
+
+            let size = 1u64 << level.shift;
+            let mapping = PageMapping {
+                linear: format.canonical(linear),
+                physical: entry & format.address_mask() & !(size - 1) & 0xFFFF_FFFF,
+                size,
+                flags: rights_flags(rights) | (Self::leaf_flags(entry) & PAGE_FLAG_GLOBAL),
+            };
+            match mappings.last_mut() {
+                Some(last)
+                    if last.flags == mapping.flags
+                        && last.linear.wrapping_add(last.size) == mapping.linear
+                        && last.physical + last.size == mapping.physical =>
+                {
+                    last.size += size;
+                }
+                _ => mappings.push(mapping),
+            }
+        }
+    }
+
+    /// Every linear address that maps to `physical` under the current CR3,
+    /// in ascending order. With paging off this is `physical` itself.
+    pub fn physical_to_linear(&self, physical: u64) -> Vec<u64> {
+        if !self.paging_enabled() {
+            return vec![physical];
+        }
+        self.page_mappings()
+            .iter()
+            .filter(|m| physical >= m.physical && physical - m.physical < m.size)
+            .map(|m| m.linear.wrapping_add(physical - m.physical))
+            .collect()
+    }
+
+    /// `page_mappings` as text, one range per line.
+    pub fn page_table_dump(&self) -> String {
+        let mut dump = String::new();
+        for mapping in self.page_mappings() {
+            dump.push_str(&mapping.to_string());
+            dump.push('\n');
+        }
+        dump
+    }
+}
@@ -17,7 +17,7 @@
 const EXPANSION_CHUNK_SIZE: usize = 0x100000;
 
 /// Page size (4KB)
-const PAGE_SIZE: usize = 0x1000;
+pub(crate) const PAGE_SIZE: usize = 0x1000;
 const PAGE_SHIFT: usize = 12;
 const PAGE_MASK: usize = PAGE_SIZE - 1;
 
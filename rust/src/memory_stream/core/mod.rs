@@ -1,6 +1,7 @@
 mod meta;
 mod access;
 mod copy;
+mod pages;
 
 #[cfg(test)]
 mod tests {
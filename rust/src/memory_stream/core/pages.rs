@@ -0,0 +1,29 @@
+use super::super::{MemoryStream, PAGE_SIZE};
+
+impl MemoryStream {
+    /// Allocated pages as (page index, contents); absent pages read as zero.
+    pub(crate) fn allocated_pages(&self) -> impl Iterator<Item = (usize, &[u8; PAGE_SIZE])> {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, page)| page.as_deref().map(|page| (index, page)))
+    }
+
+    /// Replace the whole contents with `pages`, dropping every other page.
+    /// Indices beyond the logical maximum are ignored.
+    pub(crate) fn restore_pages(
+        &mut self,
+        size: usize,
+        offset: usize,
+        pages: Vec<(usize, Box<[u8; PAGE_SIZE]>)>,
+    ) {
+        self.pages.iter_mut().for_each(|page| *page = None);
+        for (index, page) in pages {
+            if let Some(slot) = self.pages.get_mut(index) {
+                *slot = Some(page);
+            }
+        }
+        self.size = size.min(self.logical_max_memory_size());
+        self.offset = offset;
+    }
+}
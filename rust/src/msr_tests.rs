@@ -0,0 +1,65 @@
+use crate::test_support::make_accessor;
+use crate::{
+    SegmentAccess, SegmentCache, MSR_EFER, MSR_FEATURE_CONTROL, MSR_FS_BASE, MSR_LSTAR, MSR_MISC_ENABLE,
+    MSR_MTRRCAP, MSR_MTRR_PHYSBASE0, MSR_PAT, MSR_PLATFORM_INFO, MSR_SFMASK, MSR_TSC, SEGMENT_CS, SEGMENT_FS,
+};
+
+#[test]
+fn msr_writes_reject_reserved_bits_and_unknown_indices() {
+    let (_memory, mut acc) = make_accessor();
+    let gp = 0x0D << 16;
+
+    assert_eq!(acc.read_msr(MSR_PAT), (0x0007_0406_0007_0406, 0));
+    assert_eq!(acc.write_msr(MSR_PAT, 0x0007_0406_0007_0402), gp);
+    assert_eq!(acc.write_msr(MSR_SFMASK, 1 << 32), gp);
+    assert_eq!(acc.write_msr(MSR_LSTAR, 0x0000_8000_0000_0000), gp);
+    assert_eq!(acc.write_msr(MSR_LSTAR, 0xFFFF_8000_0000_1000), 0);
+    assert_eq!(acc.read_msr(MSR_LSTAR), (0xFFFF_8000_0000_1000, 0));
+
+    // Variable MTRR: PHYSBASE needs a valid type, PHYSMASK has bits 0-10 reserved.
+    assert_eq!(acc.write_msr(MSR_MTRR_PHYSBASE0, 0x0010_0002), gp);
+    assert_eq!(acc.write_msr(MSR_MTRR_PHYSBASE0, 0x0010_0006), 0);
+    assert_eq!(acc.write_msr(MSR_MTRR_PHYSBASE0 + 1, 0xF_FFF0_0001), gp);
+
+    assert_eq!(acc.write_msr(MSR_MTRRCAP, 0), gp);
+    assert_eq!(acc.read_msr(0x1234).1, gp);
+
+    // EFER: LMA is read-only and reserved bits fault.
+    assert_eq!(acc.write_msr(MSR_EFER, (1 << 8) | (1 << 10)), 0);
+    assert_eq!(acc.read_msr(MSR_EFER), (1 << 8, 0));
+    assert_eq!(acc.write_msr(MSR_EFER, 1 << 2), gp);
+
+    assert_eq!(acc.write_msr(MSR_TSC, 1_000_000), 0);
+    assert!(acc.read_msr(MSR_TSC).0 >= 1_000_000);
+}
+
+#[test]
+fn firmware_probe_msrs_read_without_faulting() {
+    let (_memory, mut acc) = make_accessor();
+    let gp = 0x0D << 16;
+
+    assert_eq!(acc.read_msr(MSR_PLATFORM_INFO), (0, 0));
+    assert_eq!(acc.read_msr(MSR_FEATURE_CONTROL), (1, 0));
+    assert_eq!(acc.write_msr(MSR_FEATURE_CONTROL, 0), gp);
+
+    // Linux clears "limit CPUID maxval"; BTS/PEBS-unavailable stay read-only.
+    let misc = acc.read_msr(MSR_MISC_ENABLE).0;
+    assert_eq!(acc.write_msr(MSR_MISC_ENABLE, misc | (1 << 22)), 0);
+    assert_eq!(acc.write_msr(MSR_MISC_ENABLE, misc & !(1 << 11)), gp);
+    assert_eq!(acc.read_msr(MSR_MISC_ENABLE), (misc | (1 << 22), 0));
+}
+
+#[test]
+fn fs_base_write_takes_effect_in_translation() {
+    let (_memory, mut acc) = make_accessor();
+    acc.write_control_register(0, acc.read_control_register(0) | 1);
+    acc.write_efer((1 << 8) | (1 << 10));
+    acc.load_segment_cache(
+        SEGMENT_CS,
+        SegmentCache { selector: 0x08, attributes: 0x209B, limit: 0, base: 0 },
+    );
+
+    assert_eq!(acc.write_msr(MSR_FS_BASE, 0x7FFF_0000_1000), 0);
+    assert_eq!(acc.segment_cache(SEGMENT_FS).unwrap().base, 0x7FFF_0000_1000);
+    assert_eq!(acc.segmented_linear(SEGMENT_FS, 0x20, 64, SegmentAccess::Read), (0x7FFF_0000_1020, 0));
+}
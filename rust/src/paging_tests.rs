@@ -1,12 +1,5 @@
-use crate::{MemoryAccessor, MemoryStream};
-
-fn make_accessor() -> (Box<MemoryStream>, MemoryAccessor) {
-    // Keep memory reasonably small; paging structures live in low memory.
-    // Use a Box to keep the MemoryStream address stable for the raw pointer stored in MemoryAccessor.
-    let mut memory = Box::new(MemoryStream::new(0x20000, 0x20000, 0));
-    let accessor = MemoryAccessor::new(memory.as_mut() as *mut MemoryStream);
-    (memory, accessor)
-}
+use crate::test_support::make_accessor;
+use crate::LINEAR_MASK_5_LEVEL;
 
 #[test]
 fn ia32e_translate_linear_maps_4k_page_and_sets_accessed_bits() {
@@ -28,9 +21,9 @@ fn ia32e_translate_linear_maps_4k_page_and_sets_accessed_bits() {
     acc.write_control_register(4, 1 << 5);
 
     let flags = 0x001 | 0x002 | 0x004; // P | RW | US
-    memory.write_qword_at(pml4 + 0 * 8, (pdpt as u64) | flags);
-    memory.write_qword_at(pdpt + 0 * 8, (pd as u64) | flags);
-    memory.write_qword_at(pd + 0 * 8, (pt as u64) | flags);
+    memory.write_qword_at(pml4, (pdpt as u64) | flags);
+    memory.write_qword_at(pdpt, (pd as u64) | flags);
+    memory.write_qword_at(pd, (pt as u64) | flags);
 
     // Map linear 0x00123000 -> physical 0x00123000 (identity).
     let linear: u64 = 0x0012_3000;
@@ -70,8 +63,8 @@ fn ia32e_translate_linear_user_violation_sets_pf_error_bits() {
     let pml4_index = ((linear >> 39) & 0x1FF) as usize;
 
     memory.write_qword_at(pml4 + pml4_index * 8, (pdpt as u64) | flags_us_rw);
-    memory.write_qword_at(pdpt + 0 * 8, (pd as u64) | flags_us_rw);
-    memory.write_qword_at(pd + 0 * 8, (pt as u64) | flags_us_rw);
+    memory.write_qword_at(pdpt, (pd as u64) | flags_us_rw);
+    memory.write_qword_at(pd, (pt as u64) | flags_us_rw);
 
     let pt_index = ((linear >> 12) & 0x1FF) as usize;
     // PTE is supervisor-only (US=0).
@@ -83,3 +76,378 @@ fn ia32e_translate_linear_user_violation_sets_pf_error_bits() {
     // #PF vector (0x0E) plus error code: P=1, U/S=1, W/R=0 => 0b101 = 0x5.
     assert_eq!(err, (0x0E << 16) | 0x5);
 }
+
+#[test]
+fn ia32e_instruction_fetch_from_xd_page_faults_with_id_bit() {
+    let (mut memory, mut acc) = make_accessor();
+
+    let pml4 = 0x1000usize;
+    let pdpt = 0x2000usize;
+    let pd = 0x3000usize;
+    let pt = 0x4000usize;
+
+    // IA-32e with EFER.NXE.
+    acc.write_efer((1 << 8) | (1 << 11));
+    acc.write_control_register(3, pml4 as u64);
+    acc.write_control_register(4, 1 << 5);
+
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(pml4, (pdpt as u64) | flags);
+    memory.write_qword_at(pdpt, (pd as u64) | flags);
+    memory.write_qword_at(pd, (pt as u64) | flags);
+
+    // Data page 0x5000 is XD, code page 0x6000 is executable.
+    memory.write_qword_at(pt + 5 * 8, 0x5000 | flags | (1 << 63));
+    memory.write_qword_at(pt + 6 * 8, 0x6000 | flags);
+
+    // Data accesses to the XD page are fine.
+    assert_eq!(acc.translate_linear(0x5010, false, false, true, 0x0000_FFFF_FFFF_FFFF), (0x5010, 0));
+
+    acc.set_instruction_fetch(true);
+    assert_eq!(acc.translate_linear(0x6010, false, false, true, 0x0000_FFFF_FFFF_FFFF), (0x6010, 0));
+
+    // Fetch from the XD page: P=1, I/D=1.
+    let (_phys, err) = acc.translate_linear(0x5010, false, false, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!(err, (0x0E << 16) | 0x11);
+    assert_eq!(acc.read_control_register(2), 0x5010);
+
+    // Not-present fetches report I/D as well.
+    let (_phys, err) = acc.translate_linear(0x7000, false, false, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!(err, (0x0E << 16) | 0x10);
+}
+
+#[test]
+fn supervisor_checks_follow_wp_smep_and_smap() {
+    let (mut memory, mut acc) = make_accessor();
+
+    // 32-bit paging: PD @ 0x1000, PT @ 0x2000.
+    let pd = 0x1000usize;
+    let pt = 0x2000usize;
+    acc.write_control_register(3, pd as u64);
+    memory.write_dword_at(pd, (pt as u32) | 0x7);
+    // 0x5000: supervisor read-only, 0x6000: user read/write.
+    memory.write_dword_at(pt + 5 * 4, 0x5000 | 0x1);
+    memory.write_dword_at(pt + 6 * 4, 0x6000 | 0x7);
+
+    // WP=0: supervisor writes ignore R/W.
+    assert_eq!(acc.translate_linear(0x5000, true, false, true, 0xFFFF_FFFF), (0x5000, 0));
+    acc.write_control_register(0, acc.read_control_register(0) | (1 << 16));
+    assert_eq!(acc.translate_linear(0x5000, true, false, true, 0xFFFF_FFFF).1, (0x0E << 16) | 0x3);
+
+    // SMEP: supervisor fetch from a user page, with I/D reported.
+    acc.write_control_register(4, 1 << 20);
+    acc.set_instruction_fetch(true);
+    assert_eq!(acc.translate_linear(0x6000, false, false, true, 0xFFFF_FFFF).1, (0x0E << 16) | 0x11);
+    acc.set_instruction_fetch(false);
+    assert_eq!(acc.translate_linear(0x6000, false, false, true, 0xFFFF_FFFF).1, 0);
+
+    // SMAP: supervisor data access to a user page faults unless EFLAGS.AC is set.
+    acc.write_control_register(4, 1 << 21);
+    assert_eq!(acc.translate_linear(0x6000, false, false, true, 0xFFFF_FFFF).1, (0x0E << 16) | 0x1);
+    acc.write_eflags(acc.read_eflags() | (1 << 18));
+    assert_eq!(acc.translate_linear(0x6000, true, false, true, 0xFFFF_FFFF), (0x6000, 0));
+    assert_eq!(acc.translate_linear(0x6000, true, true, true, 0xFFFF_FFFF), (0x6000, 0));
+}
+
+#[test]
+fn tlb_serves_stale_translation_until_invlpg() {
+    let (mut memory, mut acc) = make_accessor();
+
+    let pml4 = 0x1000usize;
+    let pdpt = 0x2000usize;
+    let pd = 0x3000usize;
+    let pt = 0x4000usize;
+
+    acc.write_efer(1 << 8);
+    acc.write_control_register(3, pml4 as u64);
+    acc.write_control_register(4, 1 << 5);
+
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(pml4, (pdpt as u64) | flags);
+    memory.write_qword_at(pdpt, (pd as u64) | flags);
+    memory.write_qword_at(pd, (pt as u64) | flags);
+
+    let linear: u64 = 0x0000_5000;
+    let pte = pt + ((linear >> 12) & 0x1FF) as usize * 8;
+    memory.write_qword_at(pte, 0x8000 | flags);
+
+    let (phys, err) = acc.translate_linear(linear + 0x10, false, false, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!((phys, err), (0x8010, 0));
+
+    // Remap without invalidating: the cached translation is still used.
+    memory.write_qword_at(pte, 0x9000 | flags);
+    let (phys, _) = acc.translate_linear(linear + 0x10, false, false, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!(phys, 0x8010);
+
+    acc.invlpg(linear + 0x800);
+    let (phys, _) = acc.translate_linear(linear + 0x10, false, false, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!(phys, 0x9010);
+
+    // A write through a clean cached entry walks again so D gets set.
+    assert_eq!(memory.read_qword_at(pte) & (1 << 6), 0);
+    let (_, err) = acc.translate_linear(linear, true, false, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!(err, 0);
+    assert_ne!(memory.read_qword_at(pte) & (1 << 6), 0);
+
+    // Cached rights are still checked against the current access.
+    memory.write_qword_at(pte, 0x9000 | 0x001 | 0x002 | (1 << 5) | (1 << 6));
+    acc.invlpg(linear);
+    let (_, err) = acc.translate_linear(linear, false, false, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!(err, 0);
+    let (_, err) = acc.translate_linear(linear, false, true, true, 0x0000_FFFF_FFFF_FFFF);
+    assert_eq!(err, (0x0E << 16) | 0x5);
+}
+
+#[test]
+fn cr3_reload_keeps_global_translations_only_with_pge() {
+    let (mut memory, mut acc) = make_accessor();
+
+    // 32-bit paging: PD @ 0x1000, PT @ 0x2000.
+    let pd = 0x1000usize;
+    let pt = 0x2000usize;
+    acc.write_control_register(3, pd as u64);
+    acc.write_control_register(4, 1 << 7); // PGE
+    acc.write_control_register(0, 0x8000_0001);
+
+    let flags = 0x001 | 0x002;
+    memory.write_dword_at(pd, (pt as u32) | flags);
+    memory.write_dword_at(pt + 4 * 4, 0x8000 | flags | 0x100); // 0x4000: global
+    memory.write_dword_at(pt + 5 * 4, 0x9000 | flags); // 0x5000
+
+    let mask = 0xFFFF_FFFF;
+    assert_eq!(acc.translate_linear(0x4000, false, false, true, mask).0, 0x8000);
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0x9000);
+
+    memory.write_dword_at(pt + 4 * 4, 0xA000 | flags | 0x100);
+    memory.write_dword_at(pt + 5 * 4, 0xB000 | flags);
+    acc.write_control_register(3, pd as u64);
+    assert_eq!(acc.translate_linear(0x4000, false, false, true, mask).0, 0x8000);
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0xB000);
+
+    // Toggling PGE flushes everything, global entries included.
+    acc.write_control_register(4, 0);
+    assert_eq!(acc.translate_linear(0x4000, false, false, true, mask).0, 0xA000);
+}
+
+#[test]
+fn pcid_tags_translations_and_invpcid_flushes_them() {
+    let (mut memory, mut acc) = make_accessor();
+
+    let pml4 = 0x1000usize;
+    let pdpt = 0x2000usize;
+    let pd = 0x3000usize;
+    let pt = 0x4000usize;
+
+    acc.write_efer((1 << 8) | (1 << 10));
+    acc.write_control_register(4, (1 << 5) | (1 << 17));
+    acc.write_control_register(0, 0x8000_0011);
+    acc.write_control_register(3, pml4 as u64 | 1);
+
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(pml4, (pdpt as u64) | flags);
+    memory.write_qword_at(pdpt, (pd as u64) | flags);
+    memory.write_qword_at(pd, (pt as u64) | flags);
+    let pte = pt + 5 * 8;
+    memory.write_qword_at(pte, 0x8000 | flags);
+
+    let mask = 0x0000_FFFF_FFFF_FFFF;
+    let no_flush = 1u64 << 63;
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0x8000);
+
+    // PCID 2 does not see PCID 1's entry.
+    memory.write_qword_at(pte, 0x9000 | flags);
+    acc.write_control_register(3, pml4 as u64 | 2 | no_flush);
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0x9000);
+
+    // Back to PCID 1 without flushing: its entry survived the switch.
+    acc.write_control_register(3, pml4 as u64 | 1 | no_flush);
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0x8000);
+    acc.write_control_register(3, pml4 as u64 | 1);
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0x9000);
+
+    // Single-context INVPCID for another PCID leaves the current one alone.
+    memory.write_qword_at(pte, 0xA000 | flags);
+    assert_eq!(acc.invpcid(1, 2, 0), 0);
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0x9000);
+    assert_eq!(acc.invpcid(0, 1, 0x5000), 0);
+    assert_eq!(acc.translate_linear(0x5000, false, false, true, mask).0, 0xA000);
+
+    assert_eq!(acc.invpcid(4, 0, 0), 0x0D << 16);
+    assert_eq!(acc.invpcid(1, 0x1000, 0), 0x0D << 16);
+    assert_eq!(acc.invpcid(0, 1, 0x0000_8000_0000_0000), 0x0D << 16);
+}
+
+#[test]
+fn reserved_bits_in_paging_entries_fault_with_rsvd() {
+    let (mut memory, mut acc) = make_accessor();
+
+    let pml4 = 0x1000usize;
+    let pdpt = 0x2000usize;
+    let pd = 0x3000usize;
+    let pt = 0x4000usize;
+
+    acc.write_efer(1 << 8);
+    acc.write_control_register(3, pml4 as u64);
+    acc.write_control_register(4, 1 << 5);
+
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(pml4, (pdpt as u64) | flags);
+    memory.write_qword_at(pdpt, (pd as u64) | flags);
+    memory.write_qword_at(pd, (pt as u64) | flags);
+    let mask = 0x0000_FFFF_FFFF_FFFF;
+
+    // Physical address bit above MAXPHYADDR (36) in the PTE.
+    memory.write_qword_at(pt + 8, (1 << 40) | 0x1000 | flags);
+    let (_, err) = acc.translate_linear(0x1000, true, true, true, mask);
+    assert_eq!(err, (0x0E << 16) | 0x0F);
+    assert_eq!(acc.read_control_register(2), 0x1000);
+
+    // XD is reserved while EFER.NXE is clear.
+    memory.write_qword_at(pt + 2 * 8, (1 << 63) | 0x2000 | flags);
+    let (_, err) = acc.translate_linear(0x2000, false, false, true, mask);
+    assert_eq!(err, (0x0E << 16) | 0x09);
+    acc.write_efer((1 << 8) | (1 << 11));
+    let (_, err) = acc.translate_linear(0x2000, false, false, true, mask);
+    assert_eq!(err, 0);
+
+    // PS is reserved in a PML4E; bits 20:13 in a 2MB PDE.
+    memory.write_qword_at(pml4 + 8, (pdpt as u64) | flags | 0x80);
+    let (_, err) = acc.translate_linear(1 << 39, false, false, true, mask);
+    assert_eq!(err, (0x0E << 16) | 0x09);
+    memory.write_qword_at(pd + 8, 0x20_0000 | (1 << 13) | flags | 0x80);
+    let (_, err) = acc.translate_linear(0x20_0000, false, false, true, mask);
+    assert_eq!(err, (0x0E << 16) | 0x09);
+}
+
+#[test]
+fn pae_pdptes_are_loaded_into_registers_on_cr3_write() {
+    let (mut memory, mut acc) = make_accessor();
+
+    // PAE PDPT at 0x1020 (32-byte aligned), PD @ 0x3000, PT @ 0x4000.
+    let pdpt = 0x1020usize;
+    let pd = 0x3000usize;
+    let pt = 0x4000usize;
+    let flags = 0x001 | 0x002 | 0x004;
+
+    // PDPTEs have no R/W or U/S bits: only P is set here.
+    memory.write_qword_at(pdpt, pd as u64 | 0x001);
+    memory.write_qword_at(pd, (pt as u64) | flags);
+    memory.write_qword_at(pt + 8, 0x8000 | flags);
+
+    acc.write_control_register(4, 1 << 5);
+    acc.write_control_register(0, 0x8000_0001);
+    assert_eq!(acc.load_control_register(3, pdpt as u64), 0);
+    assert_eq!(acc.pae_pdptes()[0], pd as u64 | 0x001);
+
+    let mask = 0xFFFF_FFFF;
+    let (phys, err) = acc.translate_linear(0x1000, true, true, true, mask);
+    assert_eq!((phys, err), (0x8000, 0));
+    // No accessed bit is written back to the PDPTE.
+    assert_eq!(memory.read_qword_at(pdpt), pd as u64 | 0x001);
+
+    // Memory changes are not seen until CR3 is reloaded.
+    memory.write_qword_at(pdpt, 0);
+    acc.invlpg(0x1000);
+    assert_eq!(acc.translate_linear(0x1000, false, true, true, mask).1, 0);
+
+    // A present PDPTE with reserved bits (here R/W) makes the load fault.
+    memory.write_qword_at(0x2000, pd as u64 | 0x003);
+    assert_eq!(acc.load_control_register(3, 0x2000), 0x0D << 16);
+    assert_eq!(acc.read_control_register(3), pdpt as u64);
+
+    memory.write_qword_at(pdpt, 0);
+    assert_eq!(acc.load_control_register(3, pdpt as u64), 0);
+    assert_eq!(acc.translate_linear(0x1000, false, true, true, mask).1, (0x0E << 16) | 0x4);
+}
+
+#[test]
+fn la57_walks_pml5_and_sign_extends_cr2_from_bit_56() {
+    let (mut memory, mut acc) = make_accessor();
+
+    let pml5 = 0x1000usize;
+    let pml4 = 0x2000usize;
+    let pdpt = 0x3000usize;
+    let pd = 0x4000usize;
+    let pt = 0x5000usize;
+
+    acc.write_efer((1 << 8) | (1 << 10));
+    acc.write_control_register(3, pml5 as u64);
+    acc.write_control_register(4, (1 << 5) | (1 << 12));
+    assert!(acc.la57_active());
+    assert!(acc.is_canonical(0xFF10_0000_0000_0000));
+    assert!(!acc.is_canonical(0x0100_0000_0000_0000));
+
+    let flags = 0x001 | 0x002 | 0x004;
+    let linear: u64 = 0xFF10_0000_0000_5000;
+    let pml5_index = ((linear >> 48) & 0x1FF) as usize;
+    memory.write_qword_at(pml5 + pml5_index * 8, (pml4 as u64) | flags);
+    memory.write_qword_at(pml4, (pdpt as u64) | flags);
+    memory.write_qword_at(pdpt, (pd as u64) | flags);
+    memory.write_qword_at(pd, (pt as u64) | flags);
+    memory.write_qword_at(pt + 5 * 8, 0x9000 | flags);
+
+    let (phys, err) = acc.translate_linear(linear + 4, false, true, true, LINEAR_MASK_5_LEVEL);
+    assert_eq!((phys, err), (0x9004, 0));
+    assert_ne!(memory.read_qword_at(pml5 + pml5_index * 8) & (1 << 5), 0);
+
+    let (_, err) = acc.translate_linear(linear + 0x1000, false, true, true, LINEAR_MASK_5_LEVEL);
+    assert_eq!(err, (0x0E << 16) | 0x4);
+    assert_eq!(acc.read_control_register(2), linear + 0x1000);
+}
+
+#[test]
+fn accesses_crossing_a_page_boundary_translate_both_pages() {
+    let (mut memory, mut acc) = make_accessor();
+
+    // 32-bit paging: PD @ 0x1000, PT @ 0x2000. Linear 0x3000 -> 0x8000,
+    // 0x4000 -> 0x6000 (frames in reverse order).
+    let pd = 0x1000usize;
+    let pt = 0x2000usize;
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_dword_at(pd, (pt as u32) | flags);
+    memory.write_dword_at(pt + 3 * 4, 0x8000 | flags);
+    memory.write_dword_at(pt + 4 * 4, 0x6000 | flags);
+    acc.write_control_register(3, pd as u64);
+    acc.write_control_register(0, 0x8000_0001);
+
+    let mask = 0xFFFF_FFFF;
+    assert_eq!(acc.write_memory_32(0x3FFE, 0x4433_2211, true, true, mask), 0);
+    assert_eq!(memory.read_dword_at(0x8FFE) & 0xFFFF, 0x2211);
+    assert_eq!(memory.read_dword_at(0x6000) & 0xFFFF, 0x4433);
+    assert_eq!(acc.read_memory_32(0x3FFE, true, true, mask), (0x4433_2211, 0));
+    assert_eq!(acc.read_memory_64(0x3FFC, true, true, mask).1, 0);
+
+    // A read-only second page: the write faults on it and nothing is written.
+    memory.write_dword_at(pt + 4 * 4, 0x6000 | 0x001 | 0x004);
+    acc.invlpg(0x4000);
+    assert_eq!(acc.write_memory_16(0x3FFF, 0xBBAA, true, true, mask), (0x0E << 16) | 0x7);
+    assert_eq!(acc.read_control_register(2), 0x4000);
+    assert_eq!(memory.read_dword_at(0x8FFC) >> 24, 0x22);
+
+    // A non-present second page faults reads too.
+    memory.write_dword_at(pt + 4 * 4, 0);
+    acc.invlpg(0x4000);
+    assert_eq!(acc.read_memory_16(0x3FFF, true, true, mask).1, (0x0E << 16) | 0x4);
+}
+
+#[test]
+fn misaligned_user_accesses_raise_ac_when_enabled() {
+    let (_memory, mut acc) = make_accessor();
+    let mask = 0xFFFF_FFFF;
+
+    // Without CR0.AM nothing is checked.
+    acc.write_eflags(acc.read_eflags() | (1 << 18));
+    assert_eq!(acc.read_memory_32(0x1001, true, false, mask).1, 0);
+
+    acc.write_control_register(0, acc.read_control_register(0) | (1 << 18));
+    assert_eq!(acc.read_memory_32(0x1001, true, false, mask).1, 0x11 << 16);
+    assert_eq!(acc.write_memory_16(0x1003, 0xBEEF, true, false, mask), 0x11 << 16);
+    assert_eq!(acc.read_memory_16(0x1003, false, false, mask), (0, 0));
+    assert_eq!(acc.read_memory_64(0x1008, true, false, mask).1, 0);
+    assert_eq!(acc.read_memory_8(0x1001, true, false, mask).1, 0);
+
+    // Supervisor accesses and EFLAGS.AC=0 are never checked.
+    assert_eq!(acc.read_memory_64(0x1004, false, false, mask).1, 0);
+    acc.write_eflags(acc.read_eflags() & !(1 << 18));
+    assert_eq!(acc.read_memory_64(0x1004, true, false, mask).1, 0);
+}
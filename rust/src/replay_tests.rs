@@ -0,0 +1,85 @@
+use crate::test_support::{make_accessor, temp_path};
+use crate::{
+    MemoryAccessor, REPLAY_DIVERGED, REPLAY_EVENT_INTERRUPT, REPLAY_EVENT_MMIO_READ, REPLAY_EXHAUSTED, REPLAY_OFF,
+    REPLAY_EVENT_TSC, REPLAY_LOG_BAD_MAGIC, REPLAY_LOG_IO_ERROR, REPLAY_LOG_OK, REPLAY_OK, REPLAY_RECORDING,
+    REPLAY_REPLAYING, MSR_TSC,
+};
+
+/// Three instructions: an MMIO read, an interrupt, then RDTSC.
+fn run(acc: &mut MemoryAccessor, mmio: u64) -> (u64, Option<u8>, u64) {
+    let (value, status) = acc.replay_input(REPLAY_EVENT_MMIO_READ, 0xFEC0_0000, 4, mmio);
+    assert_eq!(status, REPLAY_OK);
+    acc.retire_instruction();
+    let vector = if acc.replay_mode() == REPLAY_REPLAYING {
+        acc.replay_interrupt()
+    } else {
+        acc.record_interrupt(0x20);
+        Some(0x20)
+    };
+    acc.retire_instruction();
+    let tsc = acc.read_tsc();
+    acc.retire_instruction();
+    (value, vector, tsc)
+}
+
+#[test]
+fn replay_feeds_back_recorded_inputs() {
+    let (_memory, mut acc) = make_accessor();
+    acc.start_recording();
+    assert_eq!(acc.replay_mode(), REPLAY_RECORDING);
+    let recorded = run(&mut acc, 0x1234);
+    acc.stop_replay();
+    assert_eq!(acc.replay_mode(), REPLAY_OFF);
+    assert_eq!(acc.replay_events().len(), 3);
+    assert_eq!(acc.replay_events()[1].kind, REPLAY_EVENT_INTERRUPT);
+    assert_eq!(acc.replay_events()[1].instruction, 1);
+
+    let path = temp_path("replay-test.log");
+    assert_eq!(acc.save_replay_log(&path), REPLAY_LOG_OK);
+
+    let (_other_memory, mut other) = make_accessor();
+    assert_eq!(other.load_replay_log(&path), REPLAY_LOG_OK);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(acc.load_replay_log(&path), REPLAY_LOG_IO_ERROR);
+    std::fs::write(&path, b"not a replay log at all").unwrap();
+    assert_eq!(acc.load_replay_log(&path), REPLAY_LOG_BAD_MAGIC);
+    let _ = std::fs::remove_file(&path);
+    // The live MMIO value differs; the recorded one wins.
+    assert_eq!(run(&mut other, 0x9999), recorded);
+    assert_eq!(other.instruction_count(), 3);
+
+    // Past the end of the log, live values pass through.
+    assert_eq!(other.replay_input(REPLAY_EVENT_MMIO_READ, 0xFEC0_0000, 4, 7), (7, REPLAY_EXHAUSTED));
+}
+
+#[test]
+fn replay_reports_divergence() {
+    let (_memory, mut acc) = make_accessor();
+    acc.start_recording();
+    acc.replay_input(REPLAY_EVENT_MMIO_READ, 0xFEE0_0030, 4, 0x14);
+    let events = acc.replay_events().to_vec();
+
+    acc.start_replay(events.clone());
+    // A read of another register than the one recorded.
+    assert_eq!(acc.replay_input(REPLAY_EVENT_MMIO_READ, 0xFEE0_0020, 4, 5), (5, REPLAY_DIVERGED));
+
+    acc.start_replay(events);
+    acc.retire_instruction();
+    // Same register, but one instruction later.
+    assert_eq!(acc.replay_input(REPLAY_EVENT_MMIO_READ, 0xFEE0_0030, 4, 5), (5, REPLAY_DIVERGED));
+}
+
+#[test]
+fn rdmsr_of_the_tsc_is_recorded_like_rdtsc() {
+    let (_memory, mut acc) = make_accessor();
+    acc.start_recording();
+    let (recorded, err) = acc.rdmsr(MSR_TSC);
+    assert_eq!(err, 0);
+    assert_eq!(acc.replay_events()[0].kind, REPLAY_EVENT_TSC);
+    let events = acc.replay_events().to_vec();
+
+    // Move the live counter well away from the recorded value.
+    assert_eq!(acc.write_msr(MSR_TSC, recorded.wrapping_add(1 << 40)), 0);
+    acc.start_replay(events);
+    assert_eq!(acc.rdmsr(MSR_TSC), (recorded, 0));
+}
@@ -0,0 +1,86 @@
+use crate::test_support::make_accessor;
+use crate::{
+    MemoryAccessor, MemoryStream, MSR_LSTAR, MSR_PAT, SAVE_STATE_BAD_CHECKSUM, SAVE_STATE_BAD_MAGIC,
+    SAVE_STATE_BAD_VERSION, SAVE_STATE_MEMORY_MISMATCH, SAVE_STATE_OK, SAVE_STATE_TRUNCATED, SEGMENT_FS,
+};
+
+/// Long mode with a PML4 @ 0x1000 mapping 0x400000 -> 0x8000, plus some
+/// state outside the register file.
+fn build_machine(memory: &mut MemoryStream, acc: &mut MemoryAccessor) {
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(0x1000, 0x2000 | flags);
+    memory.write_qword_at(0x2000, 0x3000 | flags);
+    memory.write_qword_at(0x3010, 0x4000 | flags);
+    memory.write_qword_at(0x4000, 0x8000 | flags);
+    memory.write_dword_at(0x8010, 0xCAFE_F00D);
+    memory.write_byte_at(0x1_F000, 0x5A);
+
+    acc.write_efer((1 << 8) | (1 << 10) | (1 << 11));
+    acc.write_control_register(4, 1 << 5);
+    acc.write_control_register(3, 0x1000);
+    acc.write_control_register(0, 0x8000_0011);
+    acc.write_control_register(2, 0xFFFF_8000_DEAD_0000);
+    acc.write_by_size(0, 0x1234_5678_9ABC_DEF0, 64);
+    acc.write_by_size(17, -2, 64);
+    acc.write_by_size(24, 0x40_0010, 64);
+    acc.write_eflags(0x246 | (1 << 8));
+    assert_eq!(acc.write_msr(MSR_LSTAR, 0xFFFF_8000_0010_0000), 0);
+    assert_eq!(acc.write_msr(MSR_PAT, 0x0007_0406_0007_0106), 0);
+    assert_eq!(acc.write_debug_register(0, 0x40_0000), 0);
+    let mut fs = acc.segment_cache(SEGMENT_FS).unwrap();
+    fs.base = 0x7000_0000;
+    acc.load_segment_cache(SEGMENT_FS, fs);
+}
+
+#[test]
+fn save_state_restores_machine_into_fresh_instance() {
+    let (mut memory, mut acc) = make_accessor();
+    build_machine(&mut memory, &mut acc);
+    let image = acc.save_state();
+
+    let (restored_memory, mut restored) = make_accessor();
+    assert_eq!(restored.load_state(&image), SAVE_STATE_OK);
+
+    assert_eq!(restored.cpu_state(), acc.cpu_state());
+    assert_eq!(restored.try_to_fetch(17), -2);
+    assert_eq!(restored.try_to_fetch(5), -1);
+    assert_eq!(restored.read_eflags(), acc.read_eflags());
+    assert_eq!(restored.read_msr(MSR_LSTAR), (0xFFFF_8000_0010_0000, 0));
+    assert_eq!(restored.read_msr(MSR_PAT), (0x0007_0406_0007_0106, 0));
+    assert_eq!(restored.read_debug_register(0), (0x40_0000, 0));
+    assert_eq!(restored.segment_cache(SEGMENT_FS), acc.segment_cache(SEGMENT_FS));
+    assert_eq!(restored_memory.read_byte_at(0x1_F000), 0x5A);
+    // Paging resumes through the restored tables.
+    assert_eq!(restored.read_memory_32(0x40_0010, false, true, 0xFFFF_FFFF_FFFF), (0xCAFE_F00D, 0));
+
+    // Saving the restored machine reproduces the image (TSC aside, which
+    // keeps counting).
+    assert_eq!(restored.save_state().len(), image.len());
+}
+
+#[test]
+fn load_state_refuses_incompatible_images_without_side_effects() {
+    let (mut memory, mut acc) = make_accessor();
+    build_machine(&mut memory, &mut acc);
+    let image = acc.save_state();
+
+    let (_fresh_memory, mut fresh) = make_accessor();
+    let before = fresh.cpu_state();
+
+    let mut wrong_version = image.clone();
+    wrong_version[8] = wrong_version[8].wrapping_add(1);
+    assert_eq!(fresh.load_state(&wrong_version), SAVE_STATE_BAD_VERSION);
+
+    let mut corrupt = image.clone();
+    *corrupt.last_mut().unwrap() ^= 1;
+    assert_eq!(fresh.load_state(&corrupt), SAVE_STATE_BAD_CHECKSUM);
+
+    assert_eq!(fresh.load_state(&image[..image.len() - 1]), SAVE_STATE_TRUNCATED);
+    assert_eq!(fresh.load_state(b"not a save state at all, sorry!!"), SAVE_STATE_BAD_MAGIC);
+
+    let mut larger = Box::new(MemoryStream::new(0x20000, 0x40000, 0));
+    let mut other = MemoryAccessor::new(larger.as_mut() as *mut MemoryStream);
+    assert_eq!(other.load_state(&image), SAVE_STATE_MEMORY_MISMATCH);
+
+    assert_eq!(fresh.cpu_state(), before);
+}
@@ -0,0 +1,218 @@
+use crate::test_support::{enable_ia32e_paging, enter_protected_mode, make_accessor};
+use crate::{
+    DescriptorTableRegister, SegmentAccess, SegmentCache, SegmentDescriptor, DESCRIPTOR_CODE,
+    DESCRIPTOR_INTERRUPT_GATE, DESCRIPTOR_TSS_AVAILABLE, DESCRIPTOR_TSS_BUSY, SEGMENT_CS, SEGMENT_DS, SEGMENT_FS,
+    SEGMENT_SS,
+};
+
+#[test]
+fn real_mode_selector_load_keeps_hidden_limit_for_unreal_mode() {
+    let (_memory, mut acc) = make_accessor();
+
+    // Cache a flat 4GB data segment as if loaded in protected mode, then go back to real mode.
+    acc.load_segment_cache(
+        SEGMENT_DS,
+        SegmentCache { selector: 0x10, attributes: 0xC093, limit: 0xFFFF_FFFF, base: 0 },
+    );
+    acc.write_by_size(8 + SEGMENT_DS, 0x1000, 16);
+
+    let cache = acc.segment_cache(SEGMENT_DS).unwrap();
+    assert_eq!(cache.base, 0x10000);
+    assert_eq!(cache.limit, 0xFFFF_FFFF);
+
+    let (linear, err) = acc.segmented_linear(SEGMENT_DS, 0x0010_0000, 32, SegmentAccess::Read);
+    assert_eq!(err, 0);
+    assert_eq!(linear, 0x0011_0000);
+}
+
+#[test]
+fn real_mode_access_beyond_64k_limit_faults() {
+    let (_memory, acc) = make_accessor();
+
+    let (_linear, err) = acc.segmented_linear(SEGMENT_DS, 0xFFFF, 16, SegmentAccess::Read);
+    assert_eq!(err, 0x0D << 16);
+
+    let (_linear, err) = acc.segmented_linear(SEGMENT_SS, 0xFFFE, 32, SegmentAccess::Write);
+    assert_eq!(err, 0x0C << 16);
+}
+
+#[test]
+fn protected_mode_enforces_type_and_expand_down_limits() {
+    let (_memory, mut acc) = make_accessor();
+    enter_protected_mode(&mut acc);
+
+    // Read-only data segment.
+    acc.load_segment_cache(
+        SEGMENT_DS,
+        SegmentCache { selector: 0x10, attributes: 0x4091, limit: 0xFFFF, base: 0x1000 },
+    );
+    assert_eq!(acc.segmented_linear(SEGMENT_DS, 0x10, 8, SegmentAccess::Read), (0x1010, 0));
+    assert_eq!(acc.segmented_linear(SEGMENT_DS, 0x10, 8, SegmentAccess::Write).1, 0x0D << 16);
+
+    // 32-bit expand-down stack segment: valid offsets are (limit, 0xFFFFFFFF].
+    acc.load_segment_cache(
+        SEGMENT_SS,
+        SegmentCache { selector: 0x18, attributes: 0x4097, limit: 0x0FFF, base: 0 },
+    );
+    assert_eq!(acc.segmented_linear(SEGMENT_SS, 0x1000, 32, SegmentAccess::Write), (0x1000, 0));
+    assert_eq!(acc.segmented_linear(SEGMENT_SS, 0x0FFE, 32, SegmentAccess::Write).1, 0x0C << 16);
+}
+
+#[test]
+fn long_mode_ignores_limits_but_requires_canonical_addresses() {
+    let (_memory, mut acc) = make_accessor();
+    enter_protected_mode(&mut acc);
+    acc.write_efer((1 << 8) | (1 << 10));
+    acc.load_segment_cache(
+        SEGMENT_CS,
+        SegmentCache { selector: 0x08, attributes: 0x209B, limit: 0, base: 0 },
+    );
+    acc.load_segment_cache(
+        SEGMENT_FS,
+        SegmentCache { selector: 0, attributes: 0, limit: 0, base: 0x7000_0000 },
+    );
+
+    assert_eq!(acc.segmented_linear(SEGMENT_FS, 0x10, 64, SegmentAccess::Read), (0x7000_0010, 0));
+    assert_eq!(
+        acc.segmented_linear(SEGMENT_DS, 0x0000_8000_0000_0000, 8, SegmentAccess::Read).1,
+        0x0D << 16
+    );
+}
+
+#[test]
+fn read_segment_descriptor_decodes_flat_code_and_sets_accessed_bit() {
+    let (mut memory, mut acc) = make_accessor();
+    enter_protected_mode(&mut acc);
+
+    let gdt = 0x1000usize;
+    let gdtr = DescriptorTableRegister { base: gdt as u64, limit: 0x17, selector: 0 };
+    let ldtr = DescriptorTableRegister::default();
+    // Flat 32-bit ring-0 code segment, accessed bit clear.
+    memory.write_qword_at(gdt + 8, 0x00CF_9A00_0000_FFFF);
+
+    let (descriptor, err) = acc.read_segment_descriptor(&gdtr, &ldtr, 0x08, true);
+    assert_eq!(err, 0);
+    assert_eq!(descriptor.kind, DESCRIPTOR_CODE);
+    assert_eq!(descriptor.base, 0);
+    assert_eq!(descriptor.limit, 0xFFFF_FFFF);
+    assert_eq!(descriptor.default_size, 32);
+    assert!(descriptor.present);
+    assert_eq!(memory.read_qword_at(gdt + 8), 0x00CF_9B00_0000_FFFF);
+
+    // Beyond the GDT limit, and an LDT selector with a null LDTR.
+    assert_eq!(acc.read_segment_descriptor(&gdtr, &ldtr, 0x18, false).1, (0x0D << 16) | 0x18);
+    assert_eq!(acc.read_segment_descriptor(&gdtr, &ldtr, 0x0F, false).1, (0x0D << 16) | 0x0C);
+}
+
+#[test]
+fn peeking_a_descriptor_leaves_cr2_and_the_accessed_bit_alone() {
+    let (mut memory, mut acc) = make_accessor();
+    enable_ia32e_paging(&mut memory, &mut acc);
+    // The GDT page at 0x5000 is mapped; the one at 0x6000 is not.
+    memory.write_qword_at(0x4000 + 5 * 8, 0x5000 | 0x3);
+    memory.write_qword_at(0x5008, 0x00AF_9A00_0000_FFFF);
+    let ldtr = DescriptorTableRegister::default();
+    acc.write_control_register(2, 0x1234);
+
+    let gdtr = DescriptorTableRegister { base: 0x5000, limit: 0x17, selector: 0 };
+    assert_eq!(acc.peek_segment_descriptor(&gdtr, &ldtr, 0x08).map(|d| d.kind), Some(DESCRIPTOR_CODE));
+    assert_eq!(memory.read_qword_at(0x5008), 0x00AF_9A00_0000_FFFF);
+
+    let gdtr = DescriptorTableRegister { base: 0x6000, limit: 0x17, selector: 0 };
+    assert!(acc.peek_segment_descriptor(&gdtr, &ldtr, 0x08).is_none());
+    assert_eq!(acc.read_control_register(2), 0x1234);
+    assert_eq!(acc.read_segment_descriptor(&gdtr, &ldtr, 0x08, false).1 >> 16, 0x0E);
+    assert_eq!(acc.read_control_register(2), 0x6008);
+}
+
+#[test]
+fn descriptor_reads_wrap_at_one_megabyte_while_a20_is_closed() {
+    let (mut memory, mut acc) = make_accessor();
+    enter_protected_mode(&mut acc);
+
+    let gdtr = DescriptorTableRegister { base: 0x10_1000, limit: 0x17, selector: 0 };
+    let ldtr = DescriptorTableRegister::default();
+    memory.write_qword_at(0x1008, 0x00CF_9A00_0000_FFFF);
+
+    let (descriptor, err) = acc.read_segment_descriptor(&gdtr, &ldtr, 0x08, false);
+    assert_eq!((descriptor.kind, err), (DESCRIPTOR_CODE, 0));
+
+    // With the gate open the GDT really is above 1MB, past the end of memory.
+    acc.set_a20_enabled(true);
+    assert_ne!(acc.read_segment_descriptor(&gdtr, &ldtr, 0x08, false).0.kind, DESCRIPTOR_CODE);
+}
+
+#[test]
+fn long_mode_system_descriptors_are_sixteen_bytes() {
+    let low = 0x0000_8B12_3456_0067u64 | (0x78u64 << 56);
+    let descriptor = SegmentDescriptor::decode(low, 0xFFFF_8000, true);
+    assert_eq!(descriptor.kind, DESCRIPTOR_TSS_BUSY);
+    assert_eq!(descriptor.base, 0xFFFF_8000_7812_3456);
+    assert_eq!(descriptor.limit, 0x67);
+
+    let descriptor = SegmentDescriptor::decode(0x0000_8900_0000_0067, 0, false);
+    assert_eq!(descriptor.kind, DESCRIPTOR_TSS_AVAILABLE);
+    assert_eq!(descriptor.default_size, 32);
+}
+
+#[test]
+fn read_gate_descriptor_decodes_ia32e_interrupt_gate() {
+    let (mut memory, mut acc) = make_accessor();
+    enter_protected_mode(&mut acc);
+    acc.write_efer((1 << 8) | (1 << 10));
+
+    let idt = 0x2000usize;
+    let idtr = DescriptorTableRegister { base: idt as u64, limit: 16 * 0x21 - 1, selector: 0 };
+    // Vector 0x20: present DPL0 interrupt gate, IST=2, target 0x08:0xFFFF_8000_0010_2030.
+    memory.write_qword_at(idt + 0x20 * 16, 0x0010_8E02_0008_2030);
+    memory.write_qword_at(idt + 0x20 * 16 + 8, 0xFFFF_8000);
+
+    let (gate, err) = acc.read_gate_descriptor(&idtr, 0x20);
+    assert_eq!(err, 0);
+    assert_eq!(gate.kind, DESCRIPTOR_INTERRUPT_GATE);
+    assert_eq!(gate.selector, 0x08);
+    assert_eq!(gate.offset, 0xFFFF_8000_0010_2030);
+    assert_eq!(gate.ist, 2);
+
+    assert_eq!(acc.read_gate_descriptor(&idtr, 0x21).1, (0x0D << 16) | (0x21 << 3) | 2);
+}
+
+#[test]
+fn push_and_pop_follow_ss_width_and_wrap_sp() {
+    let (memory, mut acc) = make_accessor();
+
+    // Real mode, SS=0x1000, SP=0x0002: a 32-bit push wraps SP to 0xFFFE, runs past the
+    // 64K limit and must fault without touching SP.
+    acc.write_by_size(8 + SEGMENT_SS, 0x1000, 16);
+    acc.write_by_size(4, 0x0002, 16);
+    assert_eq!(acc.push(0xDEAD_BEEF, 32, false, false, 0xFFFFF), 0x0C << 16);
+    assert_eq!(acc.fetch_by_size(4, 16), 0x0002);
+
+    acc.write_by_size(4, 0x0100, 16);
+    assert_eq!(acc.push(0xDEAD_BEEF, 32, false, false, 0xFFFFF), 0);
+    assert_eq!(acc.fetch_by_size(4, 16), 0x00FC);
+    assert_eq!(memory.read_dword_at(0x100FC), 0xDEAD_BEEF);
+
+    assert_eq!(acc.pop(16, false, false, 0xFFFFF), (0xBEEF, 0));
+    assert_eq!(acc.fetch_by_size(4, 16), 0x00FE);
+
+    // Byte-sized stack operands don't exist; they are rejected without touching SP.
+    assert_eq!(acc.push(0xAA, 8, false, false, 0xFFFFF), 0x0D << 16);
+    assert_eq!(acc.pop(8, false, false, 0xFFFFF), (0, 0x0D << 16));
+    assert_eq!(acc.fetch_by_size(4, 16), 0x00FE);
+}
+
+#[test]
+fn push_uses_full_esp_with_big_stack_segment() {
+    let (memory, mut acc) = make_accessor();
+    enter_protected_mode(&mut acc);
+    acc.load_segment_cache(
+        SEGMENT_SS,
+        SegmentCache { selector: 0x10, attributes: 0xC093, limit: 0xFFFF_FFFF, base: 0 },
+    );
+    acc.write_by_size(4, 0x0001_0000, 32);
+
+    assert_eq!(acc.push(0x1234_5678, 32, false, false, 0xFFFF_FFFF), 0);
+    assert_eq!(acc.fetch_by_size(4, 32), 0xFFFC);
+    assert_eq!(memory.read_dword_at(0xFFFC), 0x1234_5678);
+}
@@ -0,0 +1,57 @@
+use crate::test_support::make_accessor;
+use crate::{memory_accessor_get_state, memory_accessor_set_state, CpuState, SEGMENT_CS, SEGMENT_DS};
+
+#[test]
+fn cpu_state_round_trips_through_register_layout() {
+    let (_memory, mut acc) = make_accessor();
+    let mut state = CpuState {
+        rip: 0x7C00,
+        rflags: 0x0000_0CD7,
+        efer: 1 << 11,
+        segments: [0x10, 0x07C0, 0x20, 0x30, 0x40, 0x50],
+        ..Default::default()
+    };
+    for (i, gpr) in state.gprs.iter_mut().enumerate() {
+        *gpr = 0x1111_1111_1111_0000 + i as u64;
+    }
+    state.control_registers[0] = 0x0000_0010;
+    state.control_registers[2] = 0xDEAD_B000;
+    state.control_registers[4] = 1 << 9;
+    state.control_registers[8] = 3;
+
+    acc.set_cpu_state(&state);
+
+    // GPR layout: R8 lives at register address 16, RIP at 24.
+    assert_eq!(acc.fetch(0), 0x1111_1111_1111_0000);
+    assert_eq!(acc.fetch(7), 0x1111_1111_1111_0007);
+    assert_eq!(acc.fetch(16), 0x1111_1111_1111_0008);
+    assert_eq!(acc.fetch(24), 0x7C00);
+    assert_eq!(acc.try_to_fetch(23), 0x1111_1111_1111_000F);
+    assert!(acc.carry_flag() && acc.zero_flag() && acc.sign_flag() && acc.overflow_flag());
+    assert!(acc.direction_flag() && !acc.interrupt_flag());
+
+    // Real-mode selector loads refresh the cached base.
+    assert_eq!(acc.segment_cache(SEGMENT_CS).unwrap().base, 0x7C00);
+    assert_eq!(acc.segment_cache(SEGMENT_DS).unwrap().base, 0x300);
+
+    assert_eq!(acc.cpu_state(), state);
+}
+
+#[test]
+fn ffi_state_transfer_matches_native() {
+    let (_memory, mut acc) = make_accessor();
+    let mut state = CpuState::default();
+    state.gprs[4] = 0x8000;
+    state.rip = 0xFFF0;
+    state.rflags = 0x202;
+    state.segments[1] = 0xF000;
+
+    unsafe {
+        memory_accessor_set_state(&mut acc, &state);
+        let mut read_back = CpuState::default();
+        memory_accessor_get_state(&acc, &mut read_back);
+        assert_eq!(read_back, state);
+    }
+    assert_eq!(acc.fetch(4), 0x8000);
+    assert!(acc.interrupt_flag());
+}
@@ -0,0 +1,79 @@
+use crate::test_support::{enter_protected_mode, make_accessor};
+use crate::{
+    DescriptorTableRegister, MemoryAccessor, MemoryStream, SegmentCache, TaskSwitchReason, EFLAGS_NT, SEGMENT_CS,
+    SEGMENT_SS, TABLE_GDTR, TABLE_TR,
+};
+
+const GDT: usize = 0x1000;
+const TSS_A: usize = 0x2000;
+const TSS_B: usize = 0x3000;
+
+/// Protected mode with flat ring-0 segments, running in TSS A (selector 0x18).
+/// `tss_b_access` is the access byte of the TSS B descriptor (selector 0x20).
+fn setup_tasks(memory: &mut MemoryStream, acc: &mut MemoryAccessor, tss_b_access: u64) {
+    memory.write_qword_at(GDT + 0x08, 0x00CF_9A00_0000_FFFF);
+    memory.write_qword_at(GDT + 0x10, 0x00CF_9200_0000_FFFF);
+    memory.write_qword_at(GDT + 0x18, 0x0000_8B00_2000_0067);
+    memory.write_qword_at(GDT + 0x20, (tss_b_access << 40) | 0x3000_0067);
+
+    // TSS B: EIP=0x4000, EFLAGS=0x2, EAX=0x11, ESP=0x8000, flat selectors.
+    memory.write_dword_at(TSS_B + 0x20, 0x4000);
+    memory.write_dword_at(TSS_B + 0x24, 0x2);
+    memory.write_dword_at(TSS_B + 0x28, 0x11);
+    memory.write_dword_at(TSS_B + 0x38, 0x8000);
+    for (index, selector) in [0x10, 0x08, 0x10, 0x10, 0x10, 0x10].iter().enumerate() {
+        memory.write_dword_at(TSS_B + 0x48 + index * 4, *selector);
+    }
+
+    enter_protected_mode(acc);
+    acc.load_segment_cache(
+        SEGMENT_SS,
+        SegmentCache { selector: 0x10, attributes: 0xC093, limit: 0xFFFF_FFFF, base: 0 },
+    );
+    acc.load_descriptor_table(TABLE_GDTR, DescriptorTableRegister { base: GDT as u64, limit: 0x27, selector: 0 });
+    acc.load_descriptor_table(TABLE_TR, DescriptorTableRegister { base: TSS_A as u64, limit: 0x67, selector: 0x18 });
+    acc.write_by_size(0, 0x99, 32);
+}
+
+#[test]
+fn call_to_tss_nests_and_iret_returns() {
+    let (mut memory, mut acc) = make_accessor();
+    setup_tasks(&mut memory, &mut acc, 0x89);
+
+    assert_eq!(acc.task_switch(0x20, TaskSwitchReason::Call, 0x1234), (0x4000, 0));
+    assert_eq!(acc.fetch_by_size(0, 32), 0x11);
+    assert_eq!(acc.fetch_by_size(4, 32), 0x8000);
+    assert_ne!(acc.read_eflags() & EFLAGS_NT, 0);
+    assert_ne!(acc.read_control_register(0) & (1 << 3), 0);
+    assert_eq!(acc.descriptor_table(TABLE_TR).unwrap().selector, 0x20);
+    assert_eq!(acc.segment_cache(SEGMENT_CS).unwrap().limit, 0xFFFF_FFFF);
+
+    // Outgoing state saved, back-link written, new TSS marked busy.
+    assert_eq!(memory.read_dword_at(TSS_A + 0x20), 0x1234);
+    assert_eq!(memory.read_dword_at(TSS_A + 0x28), 0x99);
+    assert_eq!(memory.read_dword_at(TSS_B) & 0xFFFF, 0x18);
+    assert_eq!(memory.read_byte_at(GDT + 0x20 + 5), 0x8B);
+
+    assert_eq!(acc.iret_task_switch(0x4010), (0x1234, 0));
+    assert_eq!(acc.fetch_by_size(0, 32), 0x99);
+    assert_eq!(acc.read_eflags() & EFLAGS_NT, 0);
+    assert_eq!(acc.descriptor_table(TABLE_TR).unwrap().selector, 0x18);
+    assert_eq!(memory.read_byte_at(GDT + 0x20 + 5), 0x89);
+    assert_eq!(memory.read_dword_at(TSS_B + 0x20), 0x4010);
+}
+
+#[test]
+fn task_switch_checks_fault_before_commit() {
+    let (mut memory, mut acc) = make_accessor();
+
+    // Not-present TSS: #NP(selector) with the current task untouched.
+    setup_tasks(&mut memory, &mut acc, 0x09);
+    assert_eq!(acc.task_switch(0x20, TaskSwitchReason::Jump, 0x1234).1, (0x0B << 16) | 0x20);
+    assert_eq!(acc.descriptor_table(TABLE_TR).unwrap().selector, 0x18);
+    assert_eq!(acc.fetch_by_size(0, 32), 0x99);
+    assert_eq!(memory.read_dword_at(TSS_A + 0x20), 0);
+
+    // JMP to a busy TSS is #GP(selector).
+    setup_tasks(&mut memory, &mut acc, 0x8B);
+    assert_eq!(acc.task_switch(0x20, TaskSwitchReason::Jump, 0x1234).1, (0x0D << 16) | 0x20);
+}
@@ -0,0 +1,43 @@
+//! Fixtures shared by the `*_tests` modules.
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use crate::{MemoryAccessor, MemoryStream, SegmentCache, SEGMENT_CS};
+
+pub(crate) fn make_accessor() -> (Box<MemoryStream>, MemoryAccessor) {
+    // Keep memory reasonably small; paging structures live in low memory.
+    // Use a Box to keep the MemoryStream address stable for the raw pointer stored in MemoryAccessor.
+    let mut memory = Box::new(MemoryStream::new(0x20000, 0x20000, 0));
+    let accessor = MemoryAccessor::new(memory.as_mut() as *mut MemoryStream);
+    (memory, accessor)
+}
+
+/// IA-32e paging with NXE: PML4 @ 0x1000 -> PDPT @ 0x2000 -> PD @ 0x3000 ->
+/// PT @ 0x4000, all P | RW | US, covering linear 0-2MB. The PTEs are left
+/// to the caller.
+pub(crate) fn enable_ia32e_paging(memory: &mut MemoryStream, acc: &mut MemoryAccessor) {
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(0x1000, 0x2000 | flags);
+    memory.write_qword_at(0x2000, 0x3000 | flags);
+    memory.write_qword_at(0x3000, 0x4000 | flags);
+    acc.write_efer((1 << 8) | (1 << 10) | (1 << 11));
+    acc.write_control_register(4, 1 << 5);
+    acc.write_control_register(3, 0x1000);
+    acc.write_control_register(0, 0x8000_0001);
+}
+
+/// Set CR0.PE and load a flat 32-bit ring-0 code segment (selector 0x08).
+pub(crate) fn enter_protected_mode(acc: &mut MemoryAccessor) {
+    acc.write_control_register(0, acc.read_control_register(0) | 1);
+    acc.load_segment_cache(
+        SEGMENT_CS,
+        SegmentCache { selector: 0x08, attributes: 0xC09B, limit: 0xFFFF_FFFF, base: 0 },
+    );
+}
+
+/// Per-process scratch file path.
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("{}-{}", name, process::id()))
+}
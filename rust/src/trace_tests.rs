@@ -0,0 +1,62 @@
+use std::fs;
+
+use crate::test_support::{make_accessor, temp_path};
+use crate::{convert_trace_log, read_trace_log, TraceRecord, TRACE_ALL, TRACE_EXECUTE, TRACE_READ, TRACE_WRITE};
+
+#[test]
+fn trace_records_accesses_and_faults() {
+    let (_memory, mut acc) = make_accessor();
+    let log = temp_path("trace-all.bin");
+    let mask = 0xFFFF_FFFF;
+    assert!(acc.start_trace(&log, TRACE_ALL));
+
+    assert_eq!(acc.write_memory_32(0x1000, 0xCAFE_F00D, false, false, mask), 0);
+    assert_eq!(acc.read_memory_16(0x1002, false, false, mask), (0xCAFE, 0));
+    acc.set_instruction_fetch(true);
+    assert_eq!(acc.read_memory_8(0x1000, false, false, mask), (0x0D, 0));
+    acc.set_instruction_fetch(false);
+    // Paging on with no tables: #PF(0) on the read.
+    acc.write_control_register(0, 0x8000_0001);
+    assert_eq!(acc.read_memory_8(0x5000, false, true, mask).1, 0x0E << 16);
+    assert!(acc.stop_trace());
+
+    let records = read_trace_log(&log).unwrap();
+    assert_eq!(
+        records,
+        vec![
+            TraceRecord { linear: 0x1000, physical: 0x1000, value: 0xCAFE_F00D, fault: 0, access: TRACE_WRITE, width: 4 },
+            TraceRecord { linear: 0x1002, physical: 0x1002, value: 0xCAFE, fault: 0, access: TRACE_READ, width: 2 },
+            TraceRecord { linear: 0x1000, physical: 0x1000, value: 0x0D, fault: 0, access: TRACE_EXECUTE, width: 1 },
+            TraceRecord { linear: 0x5000, physical: 0, value: 0, fault: 0x0E << 16, access: TRACE_READ, width: 1 },
+        ]
+    );
+
+    let text = temp_path("trace-all.txt");
+    assert_eq!(convert_trace_log(&log, &text).unwrap(), 4);
+    let lines = fs::read_to_string(&text).unwrap();
+    let _ = fs::remove_file(&log);
+    let _ = fs::remove_file(&text);
+    assert_eq!(lines.lines().next(), Some("W4 0000000000001000 -> 000001000 = cafef00d"));
+    assert_eq!(lines.lines().nth(3), Some("R1 0000000000005000 -> 000000000 = 00 #14(0000)"));
+}
+
+#[test]
+fn trace_filters_by_range_and_type() {
+    let (_memory, mut acc) = make_accessor();
+    let log = temp_path("trace-filtered.bin");
+    let mask = 0xFFFF_FFFF;
+    assert!(acc.start_trace(&log, TRACE_WRITE));
+    acc.add_trace_range(0x2000, 0x2FFF);
+
+    acc.write_memory_8(0x1FFF, 1, false, false, mask);
+    acc.write_memory_8(0x2000, 2, false, false, mask);
+    acc.read_memory_8(0x2000, false, false, mask);
+    acc.write_memory_8(0x3000, 3, false, false, mask);
+    assert!(acc.stop_trace());
+    assert!(!acc.is_tracing());
+
+    let records = read_trace_log(&log).unwrap();
+    let _ = fs::remove_file(&log);
+    assert_eq!(records.len(), 1);
+    assert_eq!((records[0].linear, records[0].value), (0x2000, 2));
+}
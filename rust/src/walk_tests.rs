@@ -0,0 +1,90 @@
+use crate::test_support::{enable_ia32e_paging, make_accessor};
+use crate::{
+    MemoryAccessor, MemoryStream, PAGE_FLAG_DIRTY, PAGE_FLAG_EXECUTE_DISABLE, PAGE_FLAG_USER, PAGE_FLAG_WRITABLE,
+    WALK_MAPPED, WALK_NON_CANONICAL, WALK_NOT_PRESENT, WALK_PAGING_DISABLED, WALK_RESERVED_BIT,
+};
+
+/// 0x1000-0x2FFF -> 0x8000-0x9FFF user RW; 0x3000 -> 0xB000 supervisor RO,
+/// XD; 0x200000-0x3FFFFF: 2MB page at 0x400000, supervisor RW.
+fn map_ia32e(memory: &mut MemoryStream, acc: &mut MemoryAccessor) {
+    enable_ia32e_paging(memory, acc);
+    let flags = 0x001 | 0x002 | 0x004;
+    memory.write_qword_at(0x4008, 0x8000 | flags | 0x40);
+    memory.write_qword_at(0x4010, 0x9000 | flags);
+    memory.write_qword_at(0x4018, (1 << 63) | 0xB000 | 0x001);
+    memory.write_qword_at(0x3008, 0x40_0000 | 0x001 | 0x002 | 0x080);
+}
+
+#[test]
+fn walk_reports_every_level_without_side_effects() {
+    let (mut memory, mut acc) = make_accessor();
+    map_ia32e(&mut memory, &mut acc);
+    acc.write_control_register(2, 0x1234);
+
+    let walk = acc.walk_page_tables(0x1ABC);
+    assert_eq!(walk.status, WALK_MAPPED);
+    assert_eq!(walk.level_count, 4);
+    assert_eq!(walk.levels[0].entry_address, 0x1000);
+    assert_eq!(walk.levels[3].entry_address, 0x4008);
+    assert_eq!(walk.levels[3].entry & !0xFFF, 0x8000);
+    assert_eq!((walk.physical, walk.page_size), (0x8ABC, 0x1000));
+    assert_eq!(walk.flags, PAGE_FLAG_WRITABLE | PAGE_FLAG_USER | PAGE_FLAG_DIRTY);
+    assert_eq!(walk.fault, 0);
+
+    let walk = acc.walk_page_tables(0x3000);
+    assert_eq!(walk.flags, PAGE_FLAG_EXECUTE_DISABLE);
+
+    let walk = acc.walk_page_tables(0x21_2345);
+    assert_eq!((walk.level_count, walk.physical, walk.page_size), (3, 0x41_2345, 0x20_0000));
+
+    let walk = acc.walk_page_tables(0x5000);
+    assert_eq!((walk.status, walk.level_count, walk.fault), (WALK_NOT_PRESENT, 4, 0x0E << 16));
+
+    memory.write_qword_at(0x4020, (1 << 40) | 0xC000 | 0x001);
+    let walk = acc.walk_page_tables(0x4000);
+    assert_eq!((walk.status, walk.fault), (WALK_RESERVED_BIT, (0x0E << 16) | 0x9));
+    assert_eq!(acc.walk_page_tables(0x0000_8000_0000_0000).status, WALK_NON_CANONICAL);
+
+    // Nothing was marked accessed and CR2 is untouched.
+    assert_eq!(memory.read_qword_at(0x1000) & 0x20, 0);
+    assert_eq!(memory.read_qword_at(0x4010) & 0x20, 0);
+    assert_eq!(acc.read_control_register(2), 0x1234);
+}
+
+#[test]
+fn page_table_dump_merges_contiguous_ranges() {
+    let (mut memory, mut acc) = make_accessor();
+    assert_eq!(acc.walk_page_tables(0x1234).status, WALK_PAGING_DISABLED);
+    assert!(acc.page_mappings().is_empty());
+
+    map_ia32e(&mut memory, &mut acc);
+    let mappings = acc.page_mappings();
+    assert_eq!(mappings.len(), 3);
+    assert_eq!((mappings[0].linear, mappings[0].physical, mappings[0].size), (0x1000, 0x8000, 0x2000));
+    assert_eq!(mappings[1].flags, PAGE_FLAG_EXECUTE_DISABLE);
+    assert_eq!((mappings[2].linear, mappings[2].size), (0x20_0000, 0x20_0000));
+
+    let dump = acc.page_table_dump();
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(lines[0], "0000000000001000-0000000000002fff -> 000008000 rwx u-");
+    assert_eq!(lines[1], "0000000000003000-0000000000003fff -> 00000b000 r-- s-");
+    assert_eq!(lines[2], "0000000000200000-00000000003fffff -> 000400000 rwx s-");
+}
+
+#[test]
+fn physical_to_linear_finds_every_alias() {
+    let (mut memory, mut acc) = make_accessor();
+    assert_eq!(acc.physical_to_linear(0x400), vec![0x400]);
+
+    map_ia32e(&mut memory, &mut acc);
+    // Alias physical 0x9000 at linear 0x7000 and in the high half.
+    memory.write_qword_at(0x4038, 0x9000 | 0x001);
+    memory.write_qword_at(0x1FF8, 0x5000 | 0x003);
+    memory.write_qword_at(0x5FF8, 0x6000 | 0x003);
+    memory.write_qword_at(0x6000, 0x7000 | 0x003);
+    memory.write_qword_at(0x7000, 0x9000 | 0x003);
+
+    assert_eq!(acc.physical_to_linear(0x9123), vec![0x2123, 0x7123, 0xFFFF_FFFF_C000_0123]);
+    assert_eq!(acc.physical_to_linear(0x45_6789), vec![0x25_6789]);
+    assert!(acc.physical_to_linear(0xA000).is_empty());
+}
@@ -6,7 +6,6 @@ namespace PHPMachineEmulator\Display\Writer;
 
 use PHPMachineEmulator\Display\Pixel\ColorInterface;
 use PHPMachineEmulator\Exception\HaltException;
-use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\LogicBoard\Debug\ScreenDebugConfig;
 use PHPMachineEmulator\LogicBoard\Debug\ScreenDumpCodeConfig;
 use PHPMachineEmulator\LogicBoard\Debug\ScreenDumpMemoryConfig;
@@ -400,20 +399,20 @@ class TerminalScreenWriter implements ScreenWriterInterface
             }
         }
 
-        $ma = $this->runtime->memoryAccessor();
         $linearIp = $this->runtime->memory()->offset() & 0xFFFFFFFF;
-        $cs = $ma->fetch(RegisterType::CS)->asByte() & 0xFFFF;
-        $ds = $ma->fetch(RegisterType::DS)->asByte() & 0xFFFF;
-        $es = $ma->fetch(RegisterType::ES)->asByte() & 0xFFFF;
-        $ss = $ma->fetch(RegisterType::SS)->asByte() & 0xFFFF;
-        $sp = $ma->fetch(RegisterType::ESP)->asBytesBySize(32) & 0xFFFFFFFF;
-        $eax = $ma->fetch(RegisterType::EAX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $ebx = $ma->fetch(RegisterType::EBX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $ecx = $ma->fetch(RegisterType::ECX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $edx = $ma->fetch(RegisterType::EDX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $esi = $ma->fetch(RegisterType::ESI)->asBytesBySize(32) & 0xFFFFFFFF;
-        $edi = $ma->fetch(RegisterType::EDI)->asBytesBySize(32) & 0xFFFFFFFF;
-        $ebp = $ma->fetch(RegisterType::EBP)->asBytesBySize(32) & 0xFFFFFFFF;
+        $state = $this->runtime->memoryAccessor()->cpuState();
+        $cs = $state['cs'];
+        $ds = $state['ds'];
+        $es = $state['es'];
+        $ss = $state['ss'];
+        $sp = $state['rsp'] & 0xFFFFFFFF;
+        $eax = $state['rax'] & 0xFFFFFFFF;
+        $ebx = $state['rbx'] & 0xFFFFFFFF;
+        $ecx = $state['rcx'] & 0xFFFFFFFF;
+        $edx = $state['rdx'] & 0xFFFFFFFF;
+        $esi = $state['rsi'] & 0xFFFFFFFF;
+        $edi = $state['rdi'] & 0xFFFFFFFF;
+        $ebp = $state['rbp'] & 0xFFFFFFFF;
         $pm = $this->runtime->context()->cpu()->isProtectedMode() ? 1 : 0;
         $pg = $this->runtime->context()->cpu()->isPagingEnabled() ? 1 : 0;
         $disk = $this->runtime->addressMap()->getDiskByAddress($linearIp);
@@ -9,12 +9,17 @@ use PHPMachineEmulator\Runtime\RuntimeInterface;
 
 class FrameSet implements FrameSetInterface
 {
+    /** @var array<string, int> */
+    protected array $cpuState;
+
     public function __construct(
         protected RuntimeInterface $runtime,
         protected InstructionInterface $instruction,
         protected int $pos,
         protected mixed $value = null,
     ) {
+        // The clone shares the native register file, so snapshot it here.
+        $this->cpuState = $runtime->memoryAccessor()->cpuState();
         $this->runtime = clone $runtime;
         $this->instruction = clone $this->instruction;
     }
@@ -38,4 +43,9 @@ class FrameSet implements FrameSetInterface
     {
         return $this->value;
     }
+
+    public function cpuState(): array
+    {
+        return $this->cpuState;
+    }
 }
@@ -13,4 +13,11 @@ interface FrameSetInterface
     public function runtime(): RuntimeInterface;
     public function instruction(): InstructionInterface;
     public function value(): mixed;
+
+    /**
+     * Registers at the time the frame was appended.
+     *
+     * @return array<string, int>
+     */
+    public function cpuState(): array;
 }
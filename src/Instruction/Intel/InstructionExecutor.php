@@ -13,6 +13,9 @@ use PHPMachineEmulator\Instruction\InstructionListInterface;
 use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\InstructionExecutorInterface;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
+use PHPMachineEmulator\Stream\MemoryStreamInterface;
+use PHPMachineEmulator\Stream\PagedMemoryStream;
 use PHPMachineEmulator\Instruction\Intel\TranslationBlock;
 use PHPMachineEmulator\Instruction\Intel\PatternedInstruction\PatternedInstructionsList;
 use PHPMachineEmulator\Instruction\Intel\PatternedInstruction\PatternedInstructionsListStats;
@@ -26,6 +29,15 @@ class InstructionExecutor implements InstructionExecutorInterface
     private int $prevInstructionPointer = 0;
     private int $zeroOpcodeCount = 0;
 
+    /**
+     * A prefix-only instruction returned CONTINUE; the debug-register
+     * bracket opened at the first prefix stays open until the real opcode.
+     */
+    private bool $instructionInProgress = false;
+
+    /** The current instruction was opened with debugInstructionBegin(). */
+    private bool $debugBracketed = false;
+
     /**
      * Instruction decode cache: IP => [instruction, opcodes, length]
      * @var array<int, array{InstructionInterface, array<int>, int}>
@@ -72,8 +84,23 @@ class InstructionExecutor implements InstructionExecutorInterface
         $this->kernelProbeCountdown = self::KERNEL_DECOMPRESS_PROBE_EVERY;
     }
 
+    /**
+     * Hot patterns and the kernel decompression shortcut run guest code
+     * without executeInstruction(), so they would skip breakpoints and
+     * single-step traps and leave instructions uncounted for replay.
+     */
+    private function shortcutsAllowed(RuntimeInterface $runtime): bool
+    {
+        $ma = $runtime->memoryAccessor();
+        return !$ma instanceof RustMemoryAccessor
+            || (!$ma->debugArmed() && $ma->replayMode() === RustMemoryAccessor::REPLAY_OFF);
+    }
+
     private function maybeProbeKernelDecompress(RuntimeInterface $runtime, int $ip): ?ExecutionStatus
     {
+        if (!$this->shortcutsAllowed($runtime)) {
+            return null;
+        }
         if ($this->kernelProbeCountdown > 0) {
             $this->kernelProbeCountdown--;
             return null;
@@ -116,8 +143,10 @@ class InstructionExecutor implements InstructionExecutorInterface
             return $this->executeSingleInstruction($runtime, $ip);
         }
 
+        $shortcutsAllowed = $this->shortcutsAllowed($runtime);
+
         // Try hot pattern detection first (fastest path for known patterns)
-        $patternResult = $this->patterns($runtime)->tryExecutePattern($runtime, $ip);
+        $patternResult = $shortcutsAllowed ? $this->patterns($runtime)->tryExecutePattern($runtime, $ip) : null;
         if ($patternResult !== null && $patternResult->isSuccess()) {
             $debug->recordExecution($runtime, $ip);
             $debug->maybeTraceControlFlowTarget($runtime, $ip, $patternResult->ip(), 'pattern');
@@ -134,7 +163,7 @@ class InstructionExecutor implements InstructionExecutorInterface
         $hits = ($this->hitCount[$ip] ?? 0) + 1;
         $this->hitCount[$ip] = $hits;
 
-        if ($hits === self::KERNEL_DECOMPRESS_HIT_THRESHOLD) {
+        if ($hits === self::KERNEL_DECOMPRESS_HIT_THRESHOLD && $shortcutsAllowed) {
             $env = UEFIRuntimeRegistry::environment($runtime);
             if ($env !== null && $env->maybeFastDecompressKernel($runtime, $ip, $hits)) {
                 return ExecutionStatus::SUCCESS;
@@ -204,15 +233,15 @@ class InstructionExecutor implements InstructionExecutorInterface
             // actual opcode. Cap at 15 bytes (architectural maximum instruction length).
             $peekBytes = [];
             try {
-                for ($i = 0; $i < $maxOpcodeLength && !$memory->isEOF(); $i++) {
-                    $peekBytes[] = $memory->byte();
-                }
+                $peekBytes = $this->peekCode($memory, $maxOpcodeLength);
 
                 $instruction = null;
                 $lastException = null;
                 $length = 0;
 
-                $canExtend = isset($peekBytes[0]) && $this->isLegacyPrefixByte($peekBytes[0]);
+                // A short window stopped before an unfetchable page: extending raises its fault.
+                $canExtend = count($peekBytes) < $maxOpcodeLength
+                    || (isset($peekBytes[0]) && $this->isLegacyPrefixByte($peekBytes[0]));
                 while (true) {
                     [$instruction, $length, $lastException] = $this->tryFindInstructionFromPeekBytes(
                         $instructionList,
@@ -328,7 +357,7 @@ class InstructionExecutor implements InstructionExecutorInterface
             $hits = ($this->hitCount[$startIp] ?? 0) + 1;
             $this->hitCount[$startIp] = $hits;
 
-            if ($hits === self::KERNEL_DECOMPRESS_HIT_THRESHOLD) {
+            if ($hits === self::KERNEL_DECOMPRESS_HIT_THRESHOLD && $this->shortcutsAllowed($runtime)) {
                 $env = UEFIRuntimeRegistry::environment($runtime);
                 if ($env !== null && $env->maybeFastDecompressKernel($runtime, $startIp, $hits)) {
                     return ExecutionStatus::SUCCESS;
@@ -350,7 +379,9 @@ class InstructionExecutor implements InstructionExecutorInterface
             // When a TB ends at a hot call target, chaining would otherwise bypass the pattern engine.
             $debug->maybeTraceIp($runtime, $exitIp);
             $debug->maybeStopAtIp($runtime, $exitIp, $this->prevInstructionPointer, $this->lastInstruction, $this->lastOpcodes);
-            $patternResult = $this->patternedInstructionsList->tryExecutePattern($runtime, $exitIp);
+            $patternResult = $this->shortcutsAllowed($runtime)
+                ? $this->patternedInstructionsList->tryExecutePattern($runtime, $exitIp)
+                : null;
             if ($patternResult !== null && $patternResult->isSuccess()) {
                 $debug->recordExecution($runtime, $exitIp);
                 $debug->maybeTraceControlFlowTarget($runtime, $exitIp, $patternResult->ip(), 'pattern');
@@ -472,16 +503,14 @@ class InstructionExecutor implements InstructionExecutorInterface
                     $memory->setOffset($instrIp + $length);
                 } else {
                     // Decode instruction
-                    $peekBytes = [];
                     $peekStart = $memory->offset();
-                    for ($j = 0; $j < $maxOpcodeLength && !$memory->isEOF(); $j++) {
-                        $peekBytes[] = $memory->byte();
-                    }
+                    $peekBytes = $this->peekCode($memory, $maxOpcodeLength);
 
                     $instruction = null;
                     $length = 0;
 
-                    $canExtend = isset($peekBytes[0]) && $this->isLegacyPrefixByte($peekBytes[0]);
+                    $canExtend = count($peekBytes) < $maxOpcodeLength
+                        || (isset($peekBytes[0]) && $this->isLegacyPrefixByte($peekBytes[0]));
                     while (true) {
                         [$instruction, $length] = $this->tryFindInstructionFromPeekBytes($instructionList, $peekBytes);
                         if ($instruction !== null) {
@@ -610,6 +639,24 @@ class InstructionExecutor implements InstructionExecutorInterface
         return [$instruction, $length, $lastException];
     }
 
+    /**
+     * Read up to $count opcode bytes, through the native fetch window when available.
+     *
+     * @return array<int>
+     */
+    private function peekCode(MemoryStreamInterface $memory, int $count): array
+    {
+        if ($memory instanceof PagedMemoryStream) {
+            return $memory->fetchCode($count);
+        }
+
+        $bytes = [];
+        for ($i = 0; $i < $count && !$memory->isEOF(); $i++) {
+            $bytes[] = $memory->byte();
+        }
+        return $bytes;
+    }
+
     /**
      * Check if two-byte opcode (0x0F xx) is control flow.
      */
@@ -624,9 +671,45 @@ class InstructionExecutor implements InstructionExecutorInterface
      */
     private function executeInstruction(RuntimeInterface $runtime, InstructionInterface $instruction, array $opcodes): ExecutionStatus
     {
+        $ma = $runtime->memoryAccessor();
+        $native = $ma instanceof RustMemoryAccessor ? $ma : null;
         try {
-            return $instruction->process($runtime, $opcodes);
+            if ($native !== null && !$this->instructionInProgress) {
+                // Nothing to check unless a breakpoint or TF is armed.
+                $this->debugBracketed = $native->debugArmed();
+                if ($this->debugBracketed) {
+                    // Instruction breakpoints are faults: the instruction does not run.
+                    $start = ($runtime->memory()->offset() - count($opcodes)) & $runtime->context()->cpu()->linearMask();
+                    $fault = $native->debugInstructionBegin($start);
+                    if ($fault !== 0) {
+                        throw new FaultException(($fault >> 16) & 0xFF, null, 'Instruction breakpoint');
+                    }
+                }
+            }
+
+            $status = $instruction->process($runtime, $opcodes);
+            $this->instructionInProgress = $status === ExecutionStatus::CONTINUE;
+
+            if ($native !== null && !$this->instructionInProgress) {
+                if ($this->debugBracketed) {
+                    // Data breakpoints and single-step are traps, reported after the instruction.
+                    $trap = $native->finishInstruction();
+                    if ($trap !== 0) {
+                        $runtime->interruptDeliveryHandler()->raiseFault(
+                            $runtime,
+                            ($trap >> 16) & 0xFF,
+                            $runtime->memory()->offset(),
+                            null,
+                        );
+                    }
+                } elseif ($native->replayMode() !== RustMemoryAccessor::REPLAY_OFF) {
+                    $native->retireInstruction();
+                }
+            }
+
+            return $status;
         } catch (FaultException $e) {
+            $this->instructionInProgress = false;
             $ip = $runtime->memory()->offset();
             $faultIp = $ip;
             $opcodeLen = count($opcodes);
@@ -283,20 +283,21 @@ final class InstructionExecutorDebug
             (int) (($ssCached['base'] ?? 0) & 0xFFFFFFFF),
         ));
 
+        $state = $ma->cpuState();
         $runtime->option()->logger()->warning(sprintf(
             'TRACE_IP: regs EAX=%08X EBX=%08X ECX=%08X EDX=%08X ESI=%08X EDI=%08X EBP=%08X ESP=%08X FL[CF=%d ZF=%d SF=%d OF=%d]',
-            $ma->fetch(RegisterType::EAX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EBX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::ECX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EDX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::ESI)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EDI)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EBP)->asBytesBySize(32),
-            $ma->fetch(RegisterType::ESP)->asBytesBySize(32),
-            $ma->shouldCarryFlag() ? 1 : 0,
-            $ma->shouldZeroFlag() ? 1 : 0,
-            $ma->shouldSignFlag() ? 1 : 0,
-            $ma->shouldOverflowFlag() ? 1 : 0,
+            $state['rax'] & 0xFFFFFFFF,
+            $state['rbx'] & 0xFFFFFFFF,
+            $state['rcx'] & 0xFFFFFFFF,
+            $state['rdx'] & 0xFFFFFFFF,
+            $state['rsi'] & 0xFFFFFFFF,
+            $state['rdi'] & 0xFFFFFFFF,
+            $state['rbp'] & 0xFFFFFFFF,
+            $state['rsp'] & 0xFFFFFFFF,
+            $state['rflags'] & 1,
+            ($state['rflags'] >> 6) & 1,
+            ($state['rflags'] >> 7) & 1,
+            ($state['rflags'] >> 11) & 1,
         ));
     }
 
@@ -437,6 +438,7 @@ final class InstructionExecutorDebug
 
         $hex = implode(' ', array_map(static fn (int $b): string => sprintf('%02X', $b & 0xFF), $bytes));
 
+        $state = $ma->cpuState();
         $runtime->option()->logger()->warning(sprintf(
             'STOP_AT_IP: ip=0x%08X bytes=%s PM=%d PG=%d LM=%d op=%d addr=%d A20=%d CS=0x%04X prevIP=0x%08X prevIns=%s prevOp=%s',
             $masked,
@@ -447,29 +449,29 @@ final class InstructionExecutorDebug
             $cpu->operandSize(),
             $cpu->addressSize(),
             $cpu->isA20Enabled() ? 1 : 0,
-            $ma->fetch(RegisterType::CS)->asByte() & 0xFFFF,
+            $state['cs'],
             $prevIp & 0xFFFFFFFF,
             $prevInstructionName,
             $prevOpcodeStr,
         ));
         $runtime->option()->logger()->warning(sprintf(
             'STOP_AT_IP: regs EAX=%08X EBX=%08X ECX=%08X EDX=%08X ESI=%08X EDI=%08X EBP=%08X ESP=%08X',
-            $ma->fetch(RegisterType::EAX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EBX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::ECX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EDX)->asBytesBySize(32),
-            $ma->fetch(RegisterType::ESI)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EDI)->asBytesBySize(32),
-            $ma->fetch(RegisterType::EBP)->asBytesBySize(32),
-            $ma->fetch(RegisterType::ESP)->asBytesBySize(32),
+            $state['rax'] & 0xFFFFFFFF,
+            $state['rbx'] & 0xFFFFFFFF,
+            $state['rcx'] & 0xFFFFFFFF,
+            $state['rdx'] & 0xFFFFFFFF,
+            $state['rsi'] & 0xFFFFFFFF,
+            $state['rdi'] & 0xFFFFFFFF,
+            $state['rbp'] & 0xFFFFFFFF,
+            $state['rsp'] & 0xFFFFFFFF,
         ));
-        $edi = $ma->fetch(RegisterType::EDI)->asBytesBySize(32);
-        $eax = $ma->fetch(RegisterType::EAX)->asBytesBySize(32);
-        $esi = $ma->fetch(RegisterType::ESI)->asBytesBySize(32);
-        $edx = $ma->fetch(RegisterType::EDX)->asBytesBySize(32);
-        $ds = $ma->fetch(RegisterType::DS)->asByte() & 0xFFFF;
-        $es = $ma->fetch(RegisterType::ES)->asByte() & 0xFFFF;
-        $ss = $ma->fetch(RegisterType::SS)->asByte() & 0xFFFF;
+        $edi = $state['rdi'] & 0xFFFFFFFF;
+        $eax = $state['rax'] & 0xFFFFFFFF;
+        $esi = $state['rsi'] & 0xFFFFFFFF;
+        $edx = $state['rdx'] & 0xFFFFFFFF;
+        $ds = $state['ds'];
+        $es = $state['es'];
+        $ss = $state['ss'];
         $dsCached = $cpu->getCachedSegmentDescriptor(RegisterType::DS);
         $esCached = $cpu->getCachedSegmentDescriptor(RegisterType::ES);
         $offsetMask = $cpu->addressSize() === 32 ? 0xFFFFFFFF : 0xFFFF;
@@ -523,7 +525,7 @@ final class InstructionExecutorDebug
         if ($this->stackPreviewOnIpStopBytes > 0) {
             $len = $this->stackPreviewOnIpStopBytes;
             $rsp = $ma->fetch(RegisterType::ESP)->asBytesBySize(64);
-            $linearMask = $cpu->isLongMode() ? 0x0000FFFFFFFFFFFF : ($cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF);
+            $linearMask = $cpu->linearMask();
             $linear = $rsp & $linearMask;
             [$phys, $err] = $ma->translateLinear($linear, false, $cpu->cpl() === 3, $cpu->isPagingEnabled(), $linearMask);
             if (((int) $err) === 0) {
@@ -694,20 +696,21 @@ final class InstructionExecutorDebug
         $cpu = $runtime->context()->cpu();
         $ma = $runtime->memoryAccessor();
 
-        $cs = $ma->fetch(RegisterType::CS)->asByte() & 0xFFFF;
-        $ss = $ma->fetch(RegisterType::SS)->asByte() & 0xFFFF;
-        $rsp = $ma->fetch(RegisterType::ESP)->asBytesBySize(64);
-        $rax = $ma->fetch(RegisterType::EAX)->asBytesBySize(64);
-        $rbx = $ma->fetch(RegisterType::EBX)->asBytesBySize(64);
-        $rcx = $ma->fetch(RegisterType::ECX)->asBytesBySize(64);
-        $rdx = $ma->fetch(RegisterType::EDX)->asBytesBySize(64);
-        $rsi = $ma->fetch(RegisterType::ESI)->asBytesBySize(64);
-        $rdi = $ma->fetch(RegisterType::EDI)->asBytesBySize(64);
-        $cr0 = $ma->readControlRegister(0);
-        $cr2 = $ma->readControlRegister(2);
-        $cr3 = $ma->readControlRegister(3);
-        $cr4 = $ma->readControlRegister(4);
-        $efer = $ma->readEfer();
+        $state = $ma->cpuState();
+        $cs = $state['cs'];
+        $ss = $state['ss'];
+        $rsp = $state['rsp'];
+        $rax = $state['rax'];
+        $rbx = $state['rbx'];
+        $rcx = $state['rcx'];
+        $rdx = $state['rdx'];
+        $rsi = $state['rsi'];
+        $rdi = $state['rdi'];
+        $cr0 = $state['cr0'];
+        $cr2 = $state['cr2'];
+        $cr3 = $state['cr3'];
+        $cr4 = $state['cr4'];
+        $efer = $state['efer'];
 
         $idtr = $cpu->idtr();
         $gdtr = $cpu->gdtr();
@@ -795,7 +798,7 @@ final class InstructionExecutorDebug
             ));
         }
 
-        $linearMask = $cpu->isLongMode() ? 0x0000FFFFFFFFFFFF : ($cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF);
+        $linearMask = $cpu->linearMask();
         $isUser = $cpu->cpl() === 3;
         $pagingEnabled = $cpu->isPagingEnabled();
         $idtrBase = (int) ($idtr['base'] ?? 0);
@@ -869,17 +872,13 @@ final class InstructionExecutorDebug
         $dumpIdtGate(0x0E);
         $dumpIdtGate(0x08);
 
-        $linear = $cr2 & 0x0000FFFFFFFFFFFF;
+        $linear = $cr2 & $linearMask;
         $pml4Index = ($linear >> 39) & 0x1FF;
         $pdptIndex = ($linear >> 30) & 0x1FF;
         $pdIndex = ($linear >> 21) & 0x1FF;
         $ptIndex = ($linear >> 12) & 0x1FF;
         $pageOffset = $linear & 0xFFF;
 
-        $pml4Base = $cr3 & 0xFFFFF000;
-        $pml4eAddr = ($pml4Base + ($pml4Index * 8)) & 0xFFFFFFFF;
-        $pml4e = $readPhys64($runtime, $pml4eAddr);
-
         $runtime->option()->logger()->warning(sprintf(
             'PFTBL: cr3=0x%016X cr2=0x%016X idx[pml4=%d pdpt=%d pd=%d pt=%d off=0x%03X]',
             $cr3,
@@ -906,6 +905,19 @@ final class InstructionExecutorDebug
             ));
         };
 
+        $pml4Base = $cr3 & 0xFFFFF000;
+        if ($cpu->isLa57()) {
+            $pml5eAddr = ($pml4Base + ((($linear >> 48) & 0x1FF) * 8)) & 0xFFFFFFFF;
+            $pml5e = $readPhys64($runtime, $pml5eAddr);
+            $dumpEntry('PML5E', $pml5eAddr, $pml5e);
+            if (($pml5e & 0x1) === 0) {
+                return;
+            }
+            $pml4Base = $pml5e & 0x000FFFFFFFFFF000;
+        }
+
+        $pml4eAddr = ($pml4Base + ($pml4Index * 8)) & 0xFFFFFFFF;
+        $pml4e = $readPhys64($runtime, $pml4eAddr);
         $dumpEntry('PML4E', $pml4eAddr, $pml4e);
         if (($pml4e & 0x1) === 0) {
             return;
@@ -956,19 +968,19 @@ final class InstructionExecutorDebug
 
     public function logExecution(RuntimeInterface $runtime, int $ipBefore, array $opcodes): void
     {
-        $memoryAccessor = $runtime->memoryAccessor();
-        $cf = $memoryAccessor->shouldCarryFlag() ? 1 : 0;
-        $zf = $memoryAccessor->shouldZeroFlag() ? 1 : 0;
-        $sf = $memoryAccessor->shouldSignFlag() ? 1 : 0;
-        $of = $memoryAccessor->shouldOverflowFlag() ? 1 : 0;
-        $eax = $memoryAccessor->fetch(RegisterType::EAX)->asBytesBySize(32);
-        $ebx = $memoryAccessor->fetch(RegisterType::EBX)->asBytesBySize(32);
-        $ecx = $memoryAccessor->fetch(RegisterType::ECX)->asBytesBySize(32);
-        $edx = $memoryAccessor->fetch(RegisterType::EDX)->asBytesBySize(32);
-        $esi = $memoryAccessor->fetch(RegisterType::ESI)->asBytesBySize(32);
-        $edi = $memoryAccessor->fetch(RegisterType::EDI)->asBytesBySize(32);
-        $ebp = $memoryAccessor->fetch(RegisterType::EBP)->asBytesBySize(32);
-        $esp = $memoryAccessor->fetch(RegisterType::ESP)->asBytesBySize(32);
+        $state = $runtime->memoryAccessor()->cpuState();
+        $cf = $state['rflags'] & 1;
+        $zf = ($state['rflags'] >> 6) & 1;
+        $sf = ($state['rflags'] >> 7) & 1;
+        $of = ($state['rflags'] >> 11) & 1;
+        $eax = $state['rax'] & 0xFFFFFFFF;
+        $ebx = $state['rbx'] & 0xFFFFFFFF;
+        $ecx = $state['rcx'] & 0xFFFFFFFF;
+        $edx = $state['rdx'] & 0xFFFFFFFF;
+        $esi = $state['rsi'] & 0xFFFFFFFF;
+        $edi = $state['rdi'] & 0xFFFFFFFF;
+        $ebp = $state['rbp'] & 0xFFFFFFFF;
+        $esp = $state['rsp'] & 0xFFFFFFFF;
         $opcodeStr = implode(' ', array_map(fn($b) => sprintf('0x%02X', $b), $opcodes));
         $runtime->option()->logger()->debug(sprintf(
             'EXEC: IP=0x%05X op=%-12s FL[CF=%d ZF=%d SF=%d OF=%d] EAX=%08X EBX=%08X ECX=%08X EDX=%08X ESI=%08X EDI=%08X EBP=%08X ESP=%08X',
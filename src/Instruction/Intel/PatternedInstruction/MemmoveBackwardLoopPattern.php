@@ -115,7 +115,7 @@ class MemmoveBackwardLoopPattern extends AbstractPatternedInstruction
                     return PatternedInstructionResult::skip($ip);
                 }
 
-                $linearMask = self::linearMask($runtime);
+                $linearMask = $runtime->context()->cpu()->linearMask();
                 $srcLinear = ($dsBase + $srcOff) & $linearMask;
                 $dstLinear = ($dsBase + $dstOff) & $linearMask;
 
@@ -292,15 +292,6 @@ class MemmoveBackwardLoopPattern extends AbstractPatternedInstruction
         return [(int) $descriptor['base'], (int) $descriptor['limit']];
     }
 
-    private static function linearMask(RuntimeInterface $runtime): int
-    {
-        $cpu = $runtime->context()->cpu();
-        if ($cpu->isLongMode()) {
-            return 0x0000FFFFFFFFFFFF;
-        }
-        return $cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF;
-    }
-
     /**
      * Minimal segment descriptor reader (GDT/LDT) for protected mode.
      *
@@ -125,7 +125,7 @@ class MemmoveForwardLoopPattern extends AbstractPatternedInstruction
                 return PatternedInstructionResult::skip($ip);
             }
 
-            $linearMask = self::linearMask($runtime);
+            $linearMask = $runtime->context()->cpu()->linearMask();
             $srcLinear = ($dsBase + $srcOff) & $linearMask;
             $dstLinear = ($esBase + $dstOff) & $linearMask;
 
@@ -280,15 +280,6 @@ class MemmoveForwardLoopPattern extends AbstractPatternedInstruction
         return [(int) $descriptor['base'], (int) $descriptor['limit']];
     }
 
-    private static function linearMask(RuntimeInterface $runtime): int
-    {
-        $cpu = $runtime->context()->cpu();
-        if ($cpu->isLongMode()) {
-            return 0x0000FFFFFFFFFFFF;
-        }
-        return $cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF;
-    }
-
     /**
      * Minimal segment descriptor reader (GDT/LDT) for protected mode.
      *
@@ -110,7 +110,7 @@ class StrcpyLoopPattern extends AbstractPatternedInstruction
             }
             $maxScan = (int) min($maxLen, $remainingSrc, $remainingDst);
 
-            $linearMask = self::linearMask($runtime);
+            $linearMask = $runtime->context()->cpu()->linearMask();
             $srcLinear = ($segBase + $srcOff) & $linearMask;
             $dstLinear = ($segBase + $dstOff) & $linearMask;
 
@@ -206,13 +206,4 @@ class StrcpyLoopPattern extends AbstractPatternedInstruction
 
         return null;
     }
-
-    private static function linearMask(RuntimeInterface $runtime): int
-    {
-        $cpu = $runtime->context()->cpu();
-        if ($cpu->isLongMode()) {
-            return 0x0000FFFFFFFFFFFF;
-        }
-        return $cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF;
-    }
 }
@@ -139,12 +139,14 @@ use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\ImulRegRm;
 use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\JccNear;
 use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\Lxs;
 use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\MovFromCr;
+use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\MovFromDr;
 use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\Movaps;
 use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\MovdMovq;
 use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\Movdqa;
 use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\Movdqu;
     use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\Movsx;
     use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\MovToCr;
+    use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\MovToDr;
     use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\Movups;
     use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\Movzx;
     use PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp\NopModrm;
@@ -447,6 +449,8 @@ class X86 implements InstructionListInterface
             // Two-byte instructions (0x0F prefix)
             MovFromCr::class,
             MovToCr::class,
+            MovFromDr::class,
+            MovToDr::class,
             Cpuid::class,
             Rdtsc::class,
             Movzx::class,
@@ -19,6 +19,8 @@ class ApicState
     private int $divide = 0;
     private float $lastUpdateTimeSec = 0.0;
     private float $fractionalTicks = 0.0;
+    /** @var (\Closure(): float)|null Guest time in seconds; null reads the host clock. */
+    private ?\Closure $clock = null;
     private array $lapicRegs = [];
 
     // IOAPIC
@@ -114,13 +116,23 @@ class ApicState
         $this->currentCount = $val;
     }
 
+    /**
+     * Count time from $clock instead of the host clock; see Pit::useClock().
+     */
+    public function useClock(?\Closure $clock): void
+    {
+        $this->clock = $clock;
+        $this->lastUpdateTimeSec = 0.0;
+        $this->fractionalTicks = 0.0;
+    }
+
     public function advanceFromHostTime(?callable $deliverInterrupt = null): void
     {
         if (!$this->apicEnabled) {
             return;
         }
 
-        $now = microtime(true);
+        $now = $this->clock !== null ? ($this->clock)() : microtime(true);
         if ($this->lastUpdateTimeSec <= 0.0) {
             $this->lastUpdateTimeSec = $now;
             return;
@@ -25,6 +25,25 @@ class Pit
     private float $lastUpdateTimeSec = 0.0;
     private float $fractionalBaseTicks = 0.0;
 
+    /** @var (\Closure(): float)|null Guest time in seconds; null reads the host clock. */
+    private ?\Closure $clock = null;
+
+    /**
+     * Count time from $clock instead of the host clock, e.g. from retired
+     * instructions while recording or replaying. null restores host time.
+     */
+    public function useClock(?\Closure $clock): void
+    {
+        $this->clock = $clock;
+        $this->lastUpdateTimeSec = 0.0;
+        $this->fractionalBaseTicks = 0.0;
+    }
+
+    public function now(): float
+    {
+        return $this->clock !== null ? ($this->clock)() : microtime(true);
+    }
+
     public function writeControl(int $value): void
     {
         $this->advanceFromHostTime();
@@ -106,7 +125,7 @@ class Pit
 
     public function advanceFromHostTime(?callable $irq0 = null): void
     {
-        $now = microtime(true);
+        $now = $this->now();
         if ($this->lastUpdateTimeSec <= 0.0) {
             $this->lastUpdateTimeSec = $now;
             return;
@@ -300,11 +300,15 @@ class IntInstruction implements InstructionInterface
             ($ma->shouldInterruptFlag() ? (1 << 9) : 0) |
             ($ma->shouldDirectionFlag() ? (1 << 10) : 0) |
             ($ma->shouldOverflowFlag() ? (1 << 11) : 0);
+        $system = $this->nativeSystemFlags($runtime);
+        $flags |= $system & (1 << 8);
 
         $ma = $runtime->memoryAccessor();
         $ma->push(RegisterType::ESP, $flags, 16);
         $ma->push(RegisterType::ESP, $runtime->memoryAccessor()->fetch(RegisterType::CS)->asByte(), 16);
         $ma->push(RegisterType::ESP, $returnOffset, 16);
+        // Delivery clears TF and RF.
+        $this->applyNativeSystemFlags($runtime, $system & (1 << 18));
 
         // Capture the real-mode interrupt frame so we can restore it if a bulk fill overwrites it.
         if (!$runtime->context()->cpu()->isProtectedMode()) {
@@ -477,7 +481,7 @@ class IntInstruction implements InstructionInterface
             }
 
             if ($isTaskGate) {
-                $this->taskSwitch($runtime, $selector, true, $selector);
+                $this->interruptTaskSwitch($runtime, $vector, $selector, $errorCode);
                 return;
             }
 
@@ -548,6 +552,8 @@ class IntInstruction implements InstructionInterface
                 ($ma->shouldInterruptFlag() ? (1 << 9) : 0) |
                 ($ma->shouldDirectionFlag() ? (1 << 10) : 0) |
                 ($ma->shouldOverflowFlag() ? (1 << 11) : 0);
+            $system = $this->nativeSystemFlags($runtime);
+            $flags |= $system;
 
             if ($privilegeChange) {
                 // On privilege change, old SS/ESP are pushed after loading the new stack.
@@ -563,6 +569,8 @@ class IntInstruction implements InstructionInterface
             if ($errorCode !== null) {
                 $ma->push(RegisterType::ESP, $errorCode & 0xFFFF, $operandSize);
             }
+            // Delivery clears TF and RF.
+            $this->applyNativeSystemFlags($runtime, $system & (1 << 18));
 
             if ($gateType === 0xE) {
                 $runtime->memoryAccessor()->setInterruptFlag(false);
@@ -692,6 +700,8 @@ class IntInstruction implements InstructionInterface
             ($ma->shouldInterruptFlag() ? (1 << 9) : 0) |
             ($ma->shouldDirectionFlag() ? (1 << 10) : 0) |
             ($ma->shouldOverflowFlag() ? (1 << 11) : 0);
+        $system = $this->nativeSystemFlags($runtime);
+        $flags |= $system;
 
         $ma->push(RegisterType::ESP, $oldSs, 64);
         $ma->push(RegisterType::ESP, $oldRsp, 64);
@@ -701,6 +711,8 @@ class IntInstruction implements InstructionInterface
         if ($errorCode !== null) {
             $ma->push(RegisterType::ESP, $errorCode & 0xFFFFFFFF, 64);
         }
+        // Delivery clears TF and RF.
+        $this->applyNativeSystemFlags($runtime, $system & (1 << 18));
 
         if ($isInterruptGate) {
             $ma->setInterruptFlag(false);
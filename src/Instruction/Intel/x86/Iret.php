@@ -84,11 +84,19 @@ class Iret implements InstructionInterface
             // IOPL and NT bits
             $cpu->setIopl(($flags >> 12) & 0x3);
             $cpu->setNt(($flags & (1 << 14)) !== 0);
+            $this->applyNativeSystemFlags($runtime, $flags);
 
             $popInterruptFrame($runtime, $cpu);
             return ExecutionStatus::SUCCESS;
         }
 
+        // IRET with NT set returns to the task in the current TSS back-link;
+        // the interrupt frame is left on the stack.
+        if ($cpu->isProtectedMode() && $cpu->nt() && ($cpu->taskRegister()['selector'] ?? 0) !== 0) {
+            $this->iretTaskSwitch($runtime);
+            return ExecutionStatus::SUCCESS;
+        }
+
         // Real-mode trampolines sometimes return via IRET even when
         // chained through a far CALL without a preceding PUSHF.
         // In that case, the stack does not contain a flags word for this IRET.
@@ -239,17 +247,6 @@ class Iret implements InstructionInterface
             }
         }
 
-        if ($cpu->isProtectedMode() && (($flags & (1 << 14)) !== 0)) {
-            // Task switch via IRET when NT set: use backlink from current TSS.
-            $tr = $runtime->context()->cpu()->taskRegister();
-            $tssSelector = $tr['selector'] ?? 0;
-            if ($tssSelector !== 0) {
-                $backlink = $this->readMemory16($runtime, $tr['base']);
-                $this->taskSwitch($runtime, $backlink, setBusy: false, gateSelector: null, isJump: true);
-                return ExecutionStatus::SUCCESS;
-            }
-        }
-
         $newCpl = $cs & 0x3;
         $returningToOuter = $cpu->isProtectedMode()
             && ($newCpl > $cpu->cpl());
@@ -288,6 +285,11 @@ class Iret implements InstructionInterface
         // IOPL and NT bits
         $runtime->context()->cpu()->setIopl(($flags >> 12) & 0x3);
         $runtime->context()->cpu()->setNt(($flags & (1 << 14)) !== 0);
+        // A 16-bit IRET leaves RF and AC alone.
+        if ($opSize === 16) {
+            $flags = ($flags & 0xFFFF) | ($this->nativeSystemFlags($runtime) & ~0xFFFF);
+        }
+        $this->applyNativeSystemFlags($runtime, $flags);
 
         $popInterruptFrame($runtime, $cpu);
         return ExecutionStatus::SUCCESS;
@@ -67,6 +67,10 @@ class Popf implements InstructionInterface
             $cpu->setIdFlag(($flags & (1 << 21)) !== 0);
         }
 
+        // TF always; AC only from a 32-bit image. POPF leaves RF clear.
+        $ac = $size >= 32 ? $flags : $this->nativeSystemFlags($runtime);
+        $this->applyNativeSystemFlags($runtime, ($flags & (1 << 8)) | ($ac & (1 << 18)));
+
         return ExecutionStatus::SUCCESS;
     }
 }
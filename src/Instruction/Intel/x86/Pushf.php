@@ -51,6 +51,9 @@ class Pushf implements InstructionInterface
             $flags |= (1 << 21);
         }
 
+        // TF and AC; the pushed image never carries RF.
+        $flags |= $this->nativeSystemFlags($runtime) & ($size >= 32 ? (1 << 8) | (1 << 18) : (1 << 8));
+
         $runtime
             ->memoryAccessor()
             ->push(RegisterType::ESP, $flags, $size);
@@ -44,7 +44,7 @@ class Cpuid implements InstructionInterface
                 // Basic feature bits: keep x86_64 baseline features present.
                 $this->writeRegisterBySize($runtime, RegisterType::EAX, 0x00000601, 32);
                 $this->writeRegisterBySize($runtime, RegisterType::EBX, 0, 32);
-                $this->writeRegisterBySize($runtime, RegisterType::ECX, 0, 32);
+                $this->writeRegisterBySize($runtime, RegisterType::ECX, (1 << 17), 32); // PCID
                 $features = (1 << 0) // FPU
                     | (1 << 1) // VME
                     | (1 << 2) // DE
@@ -94,10 +94,11 @@ class Cpuid implements InstructionInterface
                 break;
 
             case 0x7:
-                // Structured extended feature flags (subleaf 0)
+                // Structured extended feature flags (subleaf 0). Must match the
+                // CR4 bits MOV to CR4 accepts.
                 $this->writeRegisterBySize($runtime, RegisterType::EAX, 0, 32);
-                $this->writeRegisterBySize($runtime, RegisterType::EBX, 0, 32);
-                $this->writeRegisterBySize($runtime, RegisterType::ECX, 0, 32);
+                $this->writeRegisterBySize($runtime, RegisterType::EBX, (1 << 7) | (1 << 20), 32); // SMEP, SMAP
+                $this->writeRegisterBySize($runtime, RegisterType::ECX, (1 << 16), 32); // LA57
                 $this->writeRegisterBySize($runtime, RegisterType::EDX, 0, 32);
                 break;
 
@@ -137,8 +138,8 @@ class Cpuid implements InstructionInterface
                 break;
 
             case 0x80000008:
-                // Physical address bits 36, linear address bits 48, no extra cores
-                $this->writeRegisterBySize($runtime, RegisterType::EAX, 0x00003024, 32);
+                // Physical address bits 36, linear address bits 57 (LA57), no extra cores
+                $this->writeRegisterBySize($runtime, RegisterType::EAX, 0x00003924, 32);
                 $this->writeRegisterBySize($runtime, RegisterType::EBX, 0, 32);
                 $this->writeRegisterBySize($runtime, RegisterType::ECX, 0, 32);
                 $this->writeRegisterBySize($runtime, RegisterType::EDX, 0, 32);
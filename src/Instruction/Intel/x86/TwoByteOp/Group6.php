@@ -4,6 +4,7 @@ declare(strict_types=1);
 
 namespace PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp;
 
+use PHPMachineEmulator\Exception\FaultException;
 use PHPMachineEmulator\Instruction\PrefixClass;
 use PHPMachineEmulator\Instruction\ExecutionStatus;
 use PHPMachineEmulator\Instruction\InstructionInterface;
@@ -14,7 +15,7 @@ use PHPMachineEmulator\Runtime\RuntimeInterface;
 
 /**
  * Group 6 (0x0F 0x01)
- * SGDT, SIDT, LGDT, LIDT, LMSW, INVLPG
+ * SGDT, SIDT, LGDT, LIDT, LMSW, INVLPG, CLAC, STAC
  */
 class Group6 implements InstructionInterface
 {
@@ -31,6 +32,14 @@ class Group6 implements InstructionInterface
         $memory = $runtime->memory();
         $modrm = $memory->byteAsModRegRM();
 
+        if ($modrm->mode() === 0b11 && $modrm->registerOrOPCode() === 0b001) {
+            return match ($modrm->registerOrMemoryAddress()) {
+                0b010 => $this->setAlignmentCheck($runtime, false),
+                0b011 => $this->setAlignmentCheck($runtime, true),
+                default => ExecutionStatus::SUCCESS,
+            };
+        }
+
         return match ($modrm->registerOrOPCode()) {
             0b000 => $this->sgdt($runtime, $memory, $modrm),
             0b001 => $this->sidt($runtime, $memory, $modrm),
@@ -132,10 +141,22 @@ class Group6 implements InstructionInterface
         return ExecutionStatus::SUCCESS;
     }
 
+    /**
+     * CLAC (0F 01 CA) / STAC (0F 01 CB): EFLAGS.AC lifts SMAP for supervisor accesses.
+     */
+    private function setAlignmentCheck(RuntimeInterface $runtime, bool $set): ExecutionStatus
+    {
+        if ($runtime->context()->cpu()->cpl() !== 0) {
+            throw new FaultException(0x06, 0, $set ? 'STAC outside ring 0' : 'CLAC outside ring 0');
+        }
+        $flags = $this->nativeSystemFlags($runtime) & ~(1 << 18);
+        $this->applyNativeSystemFlags($runtime, $flags | ($set ? (1 << 18) : 0));
+        return ExecutionStatus::SUCCESS;
+    }
+
     private function invlpg(RuntimeInterface $runtime, MemoryStreamInterface $memory, ModRegRMInterface $modrm): ExecutionStatus
     {
-        // Just consume the memory operand; no TLB modeled
-        $this->rmLinearAddress($runtime, $memory, $modrm);
+        $runtime->memoryAccessor()->invlpg($this->rmLinearAddress($runtime, $memory, $modrm));
         return ExecutionStatus::SUCCESS;
     }
 
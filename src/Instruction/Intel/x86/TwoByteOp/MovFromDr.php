@@ -0,0 +1,64 @@
+<?php
+
+declare(strict_types=1);
+
+namespace PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp;
+
+use PHPMachineEmulator\Instruction\PrefixClass;
+use PHPMachineEmulator\Exception\ExecutionException;
+use PHPMachineEmulator\Exception\FaultException;
+use PHPMachineEmulator\Instruction\ExecutionStatus;
+use PHPMachineEmulator\Instruction\InstructionInterface;
+use PHPMachineEmulator\Instruction\Intel\Register;
+use PHPMachineEmulator\Instruction\Intel\x86\Instructable;
+use PHPMachineEmulator\Instruction\Stream\ModType;
+use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
+
+/**
+ * MOV r32, DRn (0x0F 0x21)
+ * Move from debug register to general-purpose register.
+ */
+class MovFromDr implements InstructionInterface
+{
+    use Instructable;
+
+    public function opcodes(): array
+    {
+        return $this->applyPrefixes([[0x0F, 0x21]]);
+    }
+
+    public function process(RuntimeInterface $runtime, array $opcodes): ExecutionStatus
+    {
+        $opcodes = $opcodes = $this->parsePrefixes($runtime, $opcodes);
+        $memory = $runtime->memory();
+        $modrm = $memory->byteAsModRegRM();
+
+        if (ModType::from($modrm->mode()) !== ModType::REGISTER_TO_REGISTER) {
+            throw new ExecutionException('MOV from DR requires register addressing');
+        }
+
+        $ma = $runtime->memoryAccessor();
+        if (!$ma instanceof RustMemoryAccessor) {
+            throw new ExecutionException('MOV from DR requires the native memory accessor');
+        }
+
+        $cpu = $runtime->context()->cpu();
+        $is64 = $cpu->isLongMode() && !$cpu->isCompatibilityMode();
+
+        // REX.R would select DR8-DR15, which do not exist; the native side raises #UD.
+        $dr = ($modrm->registerOrOPCode() & 0b111) | (($is64 && $cpu->rexR()) ? 0b1000 : 0);
+
+        // Privilege, DR4/DR5 aliasing and DR7.GD are checked natively.
+        [$val, $error] = $ma->readDebugRegister($dr);
+        if ($error !== 0) {
+            throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, sprintf('MOV from DR%d', $dr));
+        }
+
+        // Like MOV CR, always r32 (or r64 in 64-bit mode).
+        $gpr = Register::findGprByCode($modrm->registerOrMemoryAddress(), $is64 && $cpu->rexB());
+        $ma->writeBySize($gpr, $val, $is64 ? 64 : 32);
+
+        return ExecutionStatus::SUCCESS;
+    }
+}
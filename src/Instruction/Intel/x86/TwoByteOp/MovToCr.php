@@ -6,11 +6,13 @@ namespace PHPMachineEmulator\Instruction\Intel\x86\TwoByteOp;
 
 use PHPMachineEmulator\Instruction\PrefixClass;
 use PHPMachineEmulator\Exception\ExecutionException;
+use PHPMachineEmulator\Exception\FaultException;
 use PHPMachineEmulator\Instruction\ExecutionStatus;
 use PHPMachineEmulator\Instruction\InstructionInterface;
 use PHPMachineEmulator\Instruction\Intel\Register;
 use PHPMachineEmulator\Instruction\Intel\x86\Instructable;
 use PHPMachineEmulator\Instruction\Stream\ModType;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
 
 /**
@@ -65,7 +67,16 @@ class MovToCr implements InstructionInterface
             )
         );
 
-        $runtime->memoryAccessor()->writeControlRegister($cr, $val);
+        $ma = $runtime->memoryAccessor();
+        if ($ma instanceof RustMemoryAccessor) {
+            $error = $ma->loadControlRegister($cr, $val);
+            if ($error !== 0) {
+                throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, sprintf('MOV to CR%d fault', $cr));
+            }
+            $val = $ma->readControlRegister($cr);
+        } else {
+            $ma->writeControlRegister($cr, $val);
+        }
 
         if ($cr === 0) {
             $runtime->context()->cpu()->setProtectedMode((bool) ($val & 0x1));
@@ -11,6 +11,7 @@ use PHPMachineEmulator\Instruction\InstructionInterface;
 use PHPMachineEmulator\Instruction\Intel\x86\Instructable;
 use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Util\UInt64;
 use PHPMachineEmulator\Util\Tsc;
 
@@ -37,6 +38,23 @@ class Rdmsr implements InstructionInterface
         $ma = $runtime->memoryAccessor();
         $ecx = $ma->fetch(RegisterType::ECX)->asBytesBySize(32);
         $cpu = $runtime->context()->cpu();
+
+        if ($ma instanceof RustMemoryAccessor) {
+            // The TSC comes from the same recorded source as RDTSC.
+            [$raw, $error] = $ma->readMsr($ecx);
+            if ($error !== 0) {
+                throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, sprintf('RDMSR 0x%08X rejected', $ecx));
+            }
+            $value = UInt64::of($raw);
+            if ($ecx === 0x1B) { // APIC_BASE
+                $value = UInt64::of($cpu->apicState()->readMsrApicBase());
+            }
+            $this->writeRegisterBySize($runtime, RegisterType::EAX, $value->low32(), 32);
+            $this->writeRegisterBySize($runtime, RegisterType::EDX, $value->high32(), 32);
+
+            return ExecutionStatus::SUCCESS;
+        }
+
         $value = $cpu->readMsr($ecx);
 
         if ($ecx === 0x10) { // TSC MSR
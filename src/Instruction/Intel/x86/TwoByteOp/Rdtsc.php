@@ -10,6 +10,7 @@ use PHPMachineEmulator\Instruction\InstructionInterface;
 use PHPMachineEmulator\Instruction\Intel\x86\Instructable;
 use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Util\Tsc;
 
 /**
@@ -28,8 +29,10 @@ class Rdtsc implements InstructionInterface
     public function process(RuntimeInterface $runtime, array $opcodes): ExecutionStatus
     {
         $opcodes = $opcodes = $this->parsePrefixes($runtime, $opcodes);
-        // Use a monotonic counter so guest probes observe progress.
-        $tsc = Tsc::read();
+        // Use a monotonic counter so guest probes observe progress; the native
+        // TSC is recorded and replayed along with the other inputs.
+        $ma = $runtime->memoryAccessor();
+        $tsc = $ma instanceof RustMemoryAccessor ? $ma->readTsc() : Tsc::read();
         $low = $tsc & 0xFFFFFFFF;
         $high = ($tsc >> 32) & 0xFFFFFFFF;
 
@@ -33,13 +33,13 @@ class Sysenter implements InstructionInterface
         }
 
         $cpu = $runtime->context()->cpu();
-        $csMsr = $cpu->readMsr(0x174);  // IA32_SYSENTER_CS
-        $espMsr = $cpu->readMsr(0x175); // IA32_SYSENTER_ESP
-        $eipMsr = $cpu->readMsr(0x176); // IA32_SYSENTER_EIP
+        $csMsr = $this->readModelSpecificRegister($runtime, 0x174);  // IA32_SYSENTER_CS
+        $espMsr = $this->readModelSpecificRegister($runtime, 0x175); // IA32_SYSENTER_ESP
+        $eipMsr = $this->readModelSpecificRegister($runtime, 0x176); // IA32_SYSENTER_EIP
 
-        $cs = $csMsr->low32() & 0xFFFC; // RPL forced to 0
-        $esp = $espMsr->low32() & 0xFFFFFFFF;
-        $eip = $eipMsr->low32() & 0xFFFFFFFF;
+        $cs = $csMsr & 0xFFFC; // RPL forced to 0
+        $esp = $espMsr & 0xFFFFFFFF;
+        $eip = $eipMsr & 0xFFFFFFFF;
 
         if ($esp === 0 || $eip === 0) {
             throw new FaultException(0x0D, 0, 'SYSENTER MSRs not set');
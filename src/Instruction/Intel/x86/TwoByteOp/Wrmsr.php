@@ -11,6 +11,7 @@ use PHPMachineEmulator\Instruction\InstructionInterface;
 use PHPMachineEmulator\Instruction\Intel\x86\Instructable;
 use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Util\UInt64;
 
 /**
@@ -39,14 +40,24 @@ class Wrmsr implements InstructionInterface
         $edx = $ma->fetch(RegisterType::EDX)->asBytesBySize(32);
         $value = UInt64::fromParts($eax, $edx);
 
-        $runtime->context()->cpu()->writeMsr($ecx, $value);
+        if ($ma instanceof RustMemoryAccessor) {
+            // The native MSR file validates and holds the write; FS/GS_BASE land in the segment caches.
+            $error = $ma->writeMsr($ecx, $value->toInt());
+            if ($error !== 0) {
+                throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, sprintf('WRMSR 0x%08X rejected', $ecx));
+            }
+        } else {
+            if ($ecx === 0xC0000080) { // EFER
+                // EFER.LMA (bit 10) is read-only; it is set/cleared by the CPU when IA-32e becomes active/inactive.
+                $ma->writeEfer($value->toInt() & ~(1 << 10));
+            }
+            $runtime->context()->cpu()->writeMsr($ecx, $value);
+        }
 
         if ($ecx === 0x1B) { // APIC_BASE
             $enable = !$value->and(1 << 11)->isZero();
             $runtime->context()->cpu()->apicState()->setApicBase($value->and(0xFFFFF000)->low32(), $enable);
         } elseif ($ecx === 0xC0000080) { // EFER
-            // EFER.LMA (bit 10) is read-only; it is set/cleared by the CPU when IA-32e becomes active/inactive.
-            $ma->writeEfer($value->toInt() & ~(1 << 10));
             $this->updateIa32eMode($runtime);
         }
 
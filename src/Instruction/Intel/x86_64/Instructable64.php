@@ -142,6 +142,11 @@ trait Instructable64
      */
     protected function effectiveAddressInfo(RuntimeInterface $runtime, MemoryStreamInterface $memory, ModRegRMInterface $modRegRM): array
     {
+        $native = $this->nativeEffectiveAddressInfo($runtime, $memory, $modRegRM);
+        if ($native !== null) {
+            return $native;
+        }
+
         $cpu = $runtime->context()->cpu();
 
         if ($cpu->isLongMode() && !$cpu->isCompatibilityMode()) {
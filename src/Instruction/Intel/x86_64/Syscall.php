@@ -63,7 +63,6 @@ class Syscall implements InstructionInterface
         $rflags = $this->readRflags($runtime);
         $memAccessor->writeBySize(RegisterType::R11, $rflags, 64);
 
-        // Read MSRs (simplified - in real implementation, these would be stored in CPU context)
         $star = $this->readMsr($runtime, self::IA32_STAR);
         $lstar = $this->readMsr($runtime, self::IA32_LSTAR);
         $fmask = $this->readMsr($runtime, self::IA32_FMASK);
@@ -239,6 +238,7 @@ class Syscall implements InstructionInterface
         if ($memAccessor->shouldOverflowFlag()) {
             $flags |= 1 << 11; // OF
         }
+        $flags |= $this->nativeSystemFlags($runtime); // TF, RF, AC
 
         return $flags;
     }
@@ -258,20 +258,14 @@ class Syscall implements InstructionInterface
         $memAccessor->setInterruptFlag(($flags & (1 << 9)) !== 0);
         $memAccessor->setDirectionFlag(($flags & (1 << 10)) !== 0);
         $memAccessor->setOverflowFlag(($flags & (1 << 11)) !== 0);
+        $this->applyNativeSystemFlags($runtime, $flags);
     }
 
     /**
-     * Read MSR (simplified - returns default values).
+     * Read STAR, LSTAR or FMASK as last written by WRMSR.
      */
     private function readMsr(RuntimeInterface $runtime, int $msr): int
     {
-        // In a full implementation, these would be stored in CPU context
-        return match ($msr) {
-            self::IA32_STAR => 0x0023001000000000,  // User CS=0x23, Kernel CS=0x10
-            self::IA32_LSTAR => 0x0000000000000000, // Kernel entry point
-            self::IA32_CSTAR => 0x0000000000000000,
-            self::IA32_FMASK => 0x0000000000000000, // Don't mask any flags
-            default => 0,
-        };
+        return $this->readModelSpecificRegister($runtime, $msr);
     }
 }
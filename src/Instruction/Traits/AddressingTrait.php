@@ -8,6 +8,7 @@ use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Instruction\Stream\ModRegRMInterface;
 use PHPMachineEmulator\Instruction\Stream\ModType;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Stream\MemoryStreamInterface;
 use PHPMachineEmulator\Util\UInt64;
 
@@ -39,6 +40,11 @@ trait AddressingTrait
      */
     protected function effectiveAddressInfo(RuntimeInterface $runtime, MemoryStreamInterface $memory, ModRegRMInterface $modRegRM): array
     {
+        $native = $this->nativeEffectiveAddressInfo($runtime, $memory, $modRegRM);
+        if ($native !== null) {
+            return $native;
+        }
+
         $addrSize = $runtime->context()->cpu()->addressSize();
 
         if ($addrSize === 64) {
@@ -55,6 +61,29 @@ trait AddressingTrait
         return [$offset, $seg];
     }
 
+    /**
+     * Effective address and default segment computed by the Rust accessor,
+     * or null when the runtime uses the PHP accessor.
+     *
+     * @return array{int, RegisterType}|null [offset, defaultSegment]
+     */
+    protected function nativeEffectiveAddressInfo(RuntimeInterface $runtime, MemoryStreamInterface $memory, ModRegRMInterface $modRegRM): ?array
+    {
+        $ma = $runtime->memoryAccessor();
+        if (!$ma instanceof RustMemoryAccessor) {
+            return null;
+        }
+
+        $cpu = $runtime->context()->cpu();
+        $long = $cpu->isLongMode() && !$cpu->isCompatibilityMode();
+        $modrm = ($modRegRM->mode() << 6)
+            | ($modRegRM->registerOrOPCode() << 3)
+            | $modRegRM->registerOrMemoryAddress();
+        $rex = $long ? (($cpu->rexX() ? 0b10 : 0) | ($cpu->rexB() ? 0b01 : 0)) : 0;
+
+        return $ma->modRmEffectiveAddress($memory, $modrm, $cpu->addressSize(), $rex, $long);
+    }
+
     /**
      * Calculate linear address from ModR/M.
      */
@@ -202,7 +231,7 @@ trait AddressingTrait
         $mode = ModType::from($modRegRM->mode());
         $rm = $modRegRM->registerOrMemoryAddress();
         $cpu = $runtime->context()->cpu();
-        $linearMask = 0x0000FFFFFFFFFFFF;
+        $linearMask = $this->linearMask($runtime);
         $rexB = $cpu->rexB();
         $rexX = $cpu->rexX();
         $disp = 0;
@@ -262,7 +291,7 @@ trait AddressingTrait
             } else {
                 $baseVal = $regVal64($runtime, $sib->base(), $rexB);
                 // RSP/R12 or RBP/R13 use SS segment by default
-                $defaultSegment = in_array($baseCode, [4, 5, 12, 13], true) ? RegisterType::SS : RegisterType::DS;
+                $defaultSegment = in_array($baseCode, [4, 5], true) ? RegisterType::SS : RegisterType::DS;
             }
         } else {
             // No SIB
@@ -285,7 +314,7 @@ trait AddressingTrait
                 return [$offset, RegisterType::DS];
             } else {
                 $baseVal = $regVal64($runtime, $rm, $rexB);
-                $defaultSegment = in_array($rmWithRex, [4, 5, 12, 13], true) ? RegisterType::SS : RegisterType::DS;
+                $defaultSegment = in_array($rmWithRex, [4, 5], true) ? RegisterType::SS : RegisterType::DS;
             }
         }
 
@@ -313,5 +342,7 @@ trait AddressingTrait
     }
 
     // Abstract methods that must be implemented by using class/trait
+    abstract protected function linearMask(RuntimeInterface $runtime): int;
+
     abstract protected function segmentOffsetAddress(RuntimeInterface $runtime, RegisterType $segment, int $offset): int;
 }
@@ -5,6 +5,7 @@ declare(strict_types=1);
 namespace PHPMachineEmulator\Instruction\Traits;
 
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Util\UInt64;
 
 /**
@@ -56,6 +57,7 @@ trait FlagsTrait
         if ($runtime->context()->cpu()->idFlag()) {
             $flags |= (1 << 21);
         }
+        $flags |= $this->nativeSystemFlags($runtime) & ~(1 << 16);
         return $flags & 0xFFFFFFFF;
     }
 
@@ -85,6 +87,29 @@ trait FlagsTrait
         $runtime->context()->cpu()->setNt(($flags & (1 << 14)) !== 0);
         if ($size >= 32) {
             $runtime->context()->cpu()->setIdFlag(($flags & (1 << 21)) !== 0);
+            $this->applyNativeSystemFlags($runtime, $flags);
+        } else {
+            $this->applyNativeSystemFlags($runtime, ($flags & 0xFFFF) | ($this->nativeSystemFlags($runtime) & ~0xFFFF));
+        }
+    }
+
+    /**
+     * TF, RF and AC, which only the native accessor keeps (0 otherwise).
+     */
+    protected function nativeSystemFlags(RuntimeInterface $runtime): int
+    {
+        $ma = $runtime->memoryAccessor();
+        return $ma instanceof RustMemoryAccessor ? $ma->systemFlags() : 0;
+    }
+
+    /**
+     * Hand TF, RF and AC from a flags image to the native accessor.
+     */
+    protected function applyNativeSystemFlags(RuntimeInterface $runtime, int $flags): void
+    {
+        $ma = $runtime->memoryAccessor();
+        if ($ma instanceof RustMemoryAccessor) {
+            $ma->writeSystemFlags($flags);
         }
     }
 
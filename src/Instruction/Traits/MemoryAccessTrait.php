@@ -9,6 +9,7 @@ use PHPMachineEmulator\Display\Writer\TerminalScreenWriter;
 use PHPMachineEmulator\Exception\FaultException;
 use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Util\UInt64;
 
 /**
@@ -319,20 +320,9 @@ trait MemoryAccessTrait
     {
         $vector = ($error >> 16) & 0xFF;
         $errorCode = $error & 0xFFFF;
-        $cpu = $runtime->context()->cpu();
-
-        // CR2 holds the faulting linear address.
-        // In IA-32e (64-bit mode), it is a canonical 64-bit address (sign-extended from bit 47).
-        // In legacy modes, CR2 is effectively 32-bit.
-        if ($cpu->isLongMode() && !$cpu->isCompatibilityMode()) {
-            $masked = $linear & 0x0000FFFFFFFFFFFF;
-            $canonical = ($masked & 0x0000800000000000) !== 0
-                ? ($masked | (-1 << 48))
-                : $masked;
-            $runtime->memoryAccessor()->writeControlRegister(2, $canonical);
-        } else {
-            $runtime->memoryAccessor()->writeControlRegister(2, $linear & 0xFFFFFFFF);
-        }
+
+        // The memory accessor has already loaded CR2 with the faulting address,
+        // sign-extended from bit 47 or bit 56 as the paging mode requires.
         throw new FaultException($vector, $errorCode, 'Page fault');
     }
 
@@ -413,6 +403,21 @@ trait MemoryAccessTrait
      * Read from MMIO region if applicable.
      */
     private function readMmio(RuntimeInterface $runtime, int $address, int $width): ?int
+    {
+        $value = $this->readMmioDevice($runtime, $address, $width);
+        $ma = $runtime->memoryAccessor();
+        if ($value === null || !$ma instanceof RustMemoryAccessor) {
+            return $value;
+        }
+
+        // Device registers (e.g. the LAPIC timer count) are recorded and replayed.
+        return $ma->replayInput(RustMemoryAccessor::REPLAY_EVENT_MMIO_READ, $address, intdiv($width, 8), $value);
+    }
+
+    /**
+     * Read the device register behind an MMIO address, if any.
+     */
+    private function readMmioDevice(RuntimeInterface $runtime, int $address, int $width): ?int
     {
         $video = $runtime->context()->devices()->video();
         $lfb = $video->linearFramebufferInfo();
@@ -6,6 +6,7 @@ namespace PHPMachineEmulator\Instruction\Traits;
 
 use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 
 /**
  * Trait for register access operations.
@@ -233,4 +234,17 @@ trait RegisterAccessTrait
     {
         return $runtime->memoryAccessor()->shouldDirectionFlag() ? -$bytes : $bytes;
     }
+
+    /**
+     * Read an MSR for implicit uses such as SYSCALL and SYSENTER.
+     * With the Rust accessor the native MSR file is the only copy.
+     */
+    protected function readModelSpecificRegister(RuntimeInterface $runtime, int $index): int
+    {
+        $ma = $runtime->memoryAccessor();
+        if ($ma instanceof RustMemoryAccessor) {
+            return $ma->readMsr($index)[0];
+        }
+        return $runtime->context()->cpu()->readMsr($index)->toInt();
+    }
 }
@@ -8,6 +8,7 @@ use PHPMachineEmulator\Exception\FaultException;
 use PHPMachineEmulator\Exception\HaltException;
 use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 
 /**
  * Trait for segment-related operations.
@@ -61,6 +62,10 @@ trait SegmentTrait
         $linearMask = $this->linearMask($runtime);
 
         if ($cpu->isLongMode() && !$cpu->isCompatibilityMode()) {
+            // Only FS and GS keep a base in 64-bit mode.
+            if ($segment === RegisterType::FS || $segment === RegisterType::GS) {
+                return (($offset & $offsetMask) + $this->longModeSegmentBase($runtime, $segment)) & $linearMask;
+            }
             return ($offset & $offsetMask) & $linearMask;
         }
 
@@ -99,16 +104,25 @@ trait SegmentTrait
         return ($this->segmentBase($runtime, $segment) + ($offset & $offsetMask)) & $linearMask;
     }
 
+    /**
+     * FS/GS base in 64-bit mode: the native segment cache, which WRMSR
+     * FS_BASE/GS_BASE and selector loads both update, or FS_BASE/GS_BASE.
+     */
+    protected function longModeSegmentBase(RuntimeInterface $runtime, RegisterType $segment): int
+    {
+        $ma = $runtime->memoryAccessor();
+        if ($ma instanceof RustMemoryAccessor) {
+            return $ma->segmentBase($segment);
+        }
+        return $runtime->context()->cpu()->readMsr($segment === RegisterType::FS ? 0xC0000100 : 0xC0000101)->toInt();
+    }
+
     /**
      * Get the linear address mask based on A20 gate status.
      */
     protected function linearMask(RuntimeInterface $runtime): int
     {
-        // In IA-32e mode (long mode, including compatibility mode), linear addresses are 48 bits (canonical)
-        if ($runtime->context()->cpu()->isLongMode()) {
-            return 0x0000FFFFFFFFFFFF;
-        }
-        return $runtime->context()->cpu()->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF;
+        return $runtime->context()->cpu()->linearMask();
     }
 
     /**
@@ -335,6 +349,7 @@ trait SegmentTrait
             'dpl' => $dpl,
             'default' => ($gran & 0x40) !== 0 ? 32 : 16,
             'long' => $long,
+            'granularity' => ($gran & 0x80) !== 0,
         ];
     }
 
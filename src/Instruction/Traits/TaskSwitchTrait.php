@@ -6,7 +6,9 @@ namespace PHPMachineEmulator\Instruction\Traits;
 
 use PHPMachineEmulator\Exception\FaultException;
 use PHPMachineEmulator\Instruction\RegisterType;
+use PHPMachineEmulator\Runtime\RuntimeCPUContextObserverInterface;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 
 /**
  * Trait for task switching operations.
@@ -121,6 +123,15 @@ trait TaskSwitchTrait
         }
 
         if ($gate['isTaskGate'] ?? false) {
+            $ma = $runtime->memoryAccessor();
+            if ($ma instanceof RustMemoryAccessor) {
+                $this->completeTaskSwitch($runtime, $ma, fn (int $eip): array => $ma->taskSwitch(
+                    $gate['gateSelector'],
+                    $pushReturn ? RustMemoryAccessor::TASK_SWITCH_CALL : RustMemoryAccessor::TASK_SWITCH_JUMP,
+                    $eip,
+                ));
+                return;
+            }
             $this->taskSwitch($runtime, $gate['selector'], true, $gate['gateSelector'], !$pushReturn);
             return;
         }
@@ -196,6 +207,83 @@ trait TaskSwitchTrait
         $this->writeCodeSegment($runtime, $targetSelector, $newCpl, $targetDesc);
     }
 
+    /**
+     * Deliver an interrupt through its IDT task gate.
+     */
+    protected function interruptTaskSwitch(RuntimeInterface $runtime, int $vector, int $tssSelector, ?int $errorCode): void
+    {
+        $ma = $runtime->memoryAccessor();
+        if ($ma instanceof RustMemoryAccessor) {
+            $this->completeTaskSwitch($runtime, $ma, fn (int $eip): array => $ma->interruptTaskSwitch($vector, $eip, $errorCode));
+            return;
+        }
+        $this->taskSwitch($runtime, $tssSelector, true, $tssSelector);
+    }
+
+    /**
+     * IRET with NT set: return to the task named by the current TSS back-link.
+     */
+    protected function iretTaskSwitch(RuntimeInterface $runtime): void
+    {
+        $ma = $runtime->memoryAccessor();
+        if ($ma instanceof RustMemoryAccessor) {
+            $this->completeTaskSwitch($runtime, $ma, fn (int $eip): array => $ma->iretTaskSwitch($eip));
+            return;
+        }
+        $tr = $runtime->context()->cpu()->taskRegister();
+        $backlink = $this->readMemory16($runtime, $tr['base']);
+        $this->taskSwitch($runtime, $backlink, setBusy: false, gateSelector: null, isJump: true);
+    }
+
+    /**
+     * Run a native task switch and bring the PHP-side CPU state in line with it.
+     *
+     * The native switch saves and loads the TSS, CR3, registers and segment
+     * caches itself; flags, CPL, LDTR/TR and the PHP segment caches are
+     * refreshed here. A fault raised before the switch committed leaves TR
+     * unchanged and is thrown as is; one raised while loading the new task's
+     * segments is thrown after the new task's state is in place.
+     *
+     * @param callable(int): array{int, int} $switch Receives the EIP to save, returns [new_eip, error_code]
+     */
+    private function completeTaskSwitch(RuntimeInterface $runtime, RustMemoryAccessor $ma, callable $switch): void
+    {
+        $cpu = $runtime->context()->cpu();
+        $oldTrSelector = $cpu->taskRegister()['selector'] ?? 0;
+        $csSel = $ma->fetch(RegisterType::CS)->asByte();
+        $returnEip = $this->codeOffsetFromLinear($runtime, $csSel, $runtime->memory()->offset(), 32);
+
+        // IOPL/NT/ID live in the PHP CPU context; the native switch saves and tests them.
+        $ma->writeEflags($this->packFlags($runtime));
+        [$newEip, $error] = $switch($returnEip);
+
+        $tr = $ma->descriptorTable(RuntimeCPUContextObserverInterface::TABLE_TR);
+        if ($error !== 0 && $tr['selector'] === $oldTrSelector) {
+            throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, 'Task switch fault');
+        }
+
+        $this->applyFlags($runtime, $ma->readEflags(), 32);
+        $ldtr = $ma->descriptorTable(RuntimeCPUContextObserverInterface::TABLE_LDTR);
+        $cpu->setLdtr($ldtr['selector'], $ldtr['base'], $ldtr['limit']);
+        $cpu->setTaskRegister($tr['selector'], $tr['base'], $tr['limit']);
+
+        // Reloading each selector refreshes the PHP segment caches the same way a MOV would.
+        foreach ([RegisterType::ES, RegisterType::CS, RegisterType::SS, RegisterType::DS, RegisterType::FS, RegisterType::GS] as $segment) {
+            $ma->write16Bit($segment, $ma->fetch($segment)->asByte() & 0xFFFF);
+        }
+        $cs = $ma->fetch(RegisterType::CS)->asByte() & 0xFFFF;
+        $cpu->setCpl($cs & 0x3);
+        $cpu->setUserMode($cpu->cpl() === 3);
+
+        if ($runtime->option()->shouldChangeOffset()) {
+            $runtime->memory()->setOffset($this->linearCodeAddress($runtime, $cs, $newEip, 32));
+        }
+
+        if ($error !== 0) {
+            throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, 'Task switch fault in new task');
+        }
+    }
+
     /**
      * Perform a task switch.
      */
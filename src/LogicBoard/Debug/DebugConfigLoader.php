@@ -191,9 +191,32 @@ final class DebugConfigLoader
         return new MemoryAccessDebugConfig(
             renderLfbToTerminal: $this->parser->parseBool($data['render_lfb_terminal'] ?? false),
             stopOnLfbWrite: $this->parser->parseBool($data['stop_on_lfb_write'] ?? false),
+            traceAccessMask: $this->parseTraceAccess($data['trace_access'] ?? null),
+            traceRanges: $this->parser->parseRangeList($data['trace_ranges'] ?? []),
         );
     }
 
+    /**
+     * "rwx"-style letters (or an int mask) to MemoryAccessDebugConfig::TRACE_* bits.
+     */
+    private function parseTraceAccess(mixed $value): int
+    {
+        if (!is_string($value)) {
+            return $this->parser->parseInt($value) ?? MemoryAccessDebugConfig::TRACE_ALL;
+        }
+        $letters = strtolower(trim($value));
+        if ($letters === '') {
+            return MemoryAccessDebugConfig::TRACE_ALL;
+        }
+        if (!preg_match('/^[rwx]+$/', $letters)) {
+            return $this->parser->parseInt($letters) ?? MemoryAccessDebugConfig::TRACE_ALL;
+        }
+
+        return (str_contains($letters, 'r') ? MemoryAccessDebugConfig::TRACE_READ : 0)
+            | (str_contains($letters, 'w') ? MemoryAccessDebugConfig::TRACE_WRITE : 0)
+            | (str_contains($letters, 'x') ? MemoryAccessDebugConfig::TRACE_EXECUTE : 0);
+    }
+
     /**
      * @param array<string,mixed> $data
      */
@@ -226,6 +249,8 @@ final class DebugConfigLoader
             stopOnVbeSetMode: $this->parser->parseBool($data['stop_on_vbe_setmode'] ?? false),
             stopOnInt16Wait: $this->parser->parseBool($data['stop_on_int16_wait'] ?? false),
             traceInterruptFlag: $this->parser->parseBool($data['trace_interrupt_flag'] ?? false),
+            memoryTraceLog: $this->parsePath($data['memory_trace_log'] ?? null),
+            memoryTraceText: $this->parsePath($data['memory_trace_text'] ?? null),
         );
     }
 
@@ -457,6 +482,18 @@ final class DebugConfigLoader
         return $width;
     }
 
+    /**
+     * Non-empty path, relative ones resolved against the project root.
+     */
+    private function parsePath(mixed $value): ?string
+    {
+        if (!is_string($value) || trim($value) === '') {
+            return null;
+        }
+        $path = trim($value);
+        return str_starts_with($path, '/') ? $path : $this->projectRoot . '/' . $path;
+    }
+
     private function clamp(int $value, int $min, int $max): int
     {
         return max($min, min($max, $value));
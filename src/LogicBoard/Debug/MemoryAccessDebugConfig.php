@@ -6,9 +6,20 @@ namespace PHPMachineEmulator\LogicBoard\Debug;
 
 final class MemoryAccessDebugConfig
 {
+    // Access types of the native memory tracer (see rust/src/memory_accessor/trace.rs).
+    public const TRACE_READ = 1 << 0;
+    public const TRACE_WRITE = 1 << 1;
+    public const TRACE_EXECUTE = 1 << 2;
+    public const TRACE_ALL = self::TRACE_READ | self::TRACE_WRITE | self::TRACE_EXECUTE;
+
+    /**
+     * @param array<int,array{start:int,end:int}> $traceRanges linear ranges; empty traces every address
+     */
     public function __construct(
         public readonly bool $renderLfbToTerminal = false,
         public readonly bool $stopOnLfbWrite = false,
+        public readonly int $traceAccessMask = self::TRACE_ALL,
+        public readonly array $traceRanges = [],
     ) {
     }
 }
@@ -21,6 +21,8 @@ final class TraceDebugConfig
         public readonly bool $stopOnVbeSetMode = false,
         public readonly bool $stopOnInt16Wait = false,
         public readonly bool $traceInterruptFlag = false,
+        public readonly ?string $memoryTraceLog = null,
+        public readonly ?string $memoryTraceText = null,
     ) {
     }
 }
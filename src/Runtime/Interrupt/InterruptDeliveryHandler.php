@@ -9,6 +9,7 @@ use PHPMachineEmulator\Exception\FaultException;
 use PHPMachineEmulator\Exception\HaltException;
 use PHPMachineEmulator\Instruction\Intel\x86\IntInstruction;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 
 /**
  * Handles delivery of pending interrupts from registered interrupt sources.
@@ -54,7 +55,43 @@ class InterruptDeliveryHandler implements InterruptDeliveryHandlerInterface
             return false;
         }
 
-        // Try each interrupt source in priority order
+        $native = $memoryAccessor instanceof RustMemoryAccessor ? $memoryAccessor : null;
+        if ($native !== null && $native->replayMode() === RustMemoryAccessor::REPLAY_REPLAYING) {
+            // Replayed interrupts arrive at the recorded instruction boundaries.
+            $vector = $native->replayInterrupt();
+            if ($vector === null) {
+                return false;
+            }
+
+            // Still acknowledge at the source so the in-service state matches the recording.
+            $acknowledged = $this->acknowledgePendingInterrupt($runtime);
+            if ($acknowledged !== $vector) {
+                $runtime->option()->logger()->warning(sprintf(
+                    'REPLAY: logged interrupt 0x%02X but the sources acknowledged %s',
+                    $vector,
+                    $acknowledged === null ? 'none' : sprintf('0x%02X', $acknowledged),
+                ));
+            }
+
+            return $this->raiseInterrupt($runtime, $vector);
+        }
+
+        $vector = $this->acknowledgePendingInterrupt($runtime);
+        if ($vector === null || !$this->raiseInterrupt($runtime, $vector)) {
+            return false;
+        }
+        $native?->recordInterrupt($vector);
+
+        return true;
+    }
+
+    /**
+     * Acknowledge the highest-priority pending interrupt at its source
+     * (IRR to ISR) and return its vector, or null if none is pending.
+     * Sources are tried in priority order.
+     */
+    private function acknowledgePendingInterrupt(RuntimeInterface $runtime): ?int
+    {
         foreach ($this->sources as $source) {
             if (!$source->isEnabled($runtime)) {
                 continue;
@@ -62,11 +99,11 @@ class InterruptDeliveryHandler implements InterruptDeliveryHandlerInterface
 
             $vector = $source->pendingVector($runtime);
             if ($vector !== null) {
-                return $this->raiseInterrupt($runtime, $vector);
+                return $vector;
             }
         }
 
-        return false;
+        return null;
     }
 
     public function raiseFault(RuntimeInterface $runtime, int $vector, int $ip, ?int $errorCode): bool
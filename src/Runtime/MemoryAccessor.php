@@ -619,6 +619,8 @@ class MemoryAccessor implements MemoryAccessorInterface
         // Invalidate decoder/translation caches when CR0 is modified.
         if ($index === 0 && $previous !== $value) {
             $this->runtime->architectureProvider()->instructionExecutor()->invalidateCaches();
+        } elseif ($index === 4) {
+            $this->runtime->context()->cpu()->setLa57(($value & (1 << 12)) !== 0);
         }
     }
 
@@ -634,6 +636,41 @@ class MemoryAccessor implements MemoryAccessorInterface
         $this->efer = $value;
     }
 
+    public function cpuState(): array
+    {
+        $gprs = [
+            RegisterType::EAX, RegisterType::ECX, RegisterType::EDX, RegisterType::EBX,
+            RegisterType::ESP, RegisterType::EBP, RegisterType::ESI, RegisterType::EDI,
+            RegisterType::R8, RegisterType::R9, RegisterType::R10, RegisterType::R11,
+            RegisterType::R12, RegisterType::R13, RegisterType::R14, RegisterType::R15,
+        ];
+        $segments = [RegisterType::ES, RegisterType::CS, RegisterType::SS, RegisterType::DS, RegisterType::FS, RegisterType::GS];
+
+        $state = [];
+        foreach (self::CPU_STATE_GPRS as $i => $name) {
+            $state[$name] = $this->tryToFetch($gprs[$i])?->asBytesBySize(64) ?? 0;
+        }
+        $state['rip'] = $this->tryToFetch(RegisterType::RIP)?->asBytesBySize(64) ?? 0;
+        $state['rflags'] = ($this->carryFlag ? 1 : 0)
+            | 0x2
+            | ($this->parityFlag ? (1 << 2) : 0)
+            | ($this->auxiliaryCarryFlag ? (1 << 4) : 0)
+            | ($this->zeroFlag ? (1 << 6) : 0)
+            | ($this->signFlag ? (1 << 7) : 0)
+            | ($this->interruptFlag ? (1 << 9) : 0)
+            | ($this->directionFlag ? (1 << 10) : 0)
+            | ($this->overflowFlag ? (1 << 11) : 0);
+        for ($i = 0; $i <= 8; $i++) {
+            $state['cr' . $i] = $this->controlRegisters[$i] ?? 0;
+        }
+        $state['efer'] = $this->efer;
+        foreach (self::CPU_STATE_SEGMENTS as $i => $name) {
+            $state[$name] = ($this->tryToFetch($segments[$i])?->asByte() ?? 0) & 0xFFFF;
+        }
+
+        return $state;
+    }
+
     private function processRegisterWrite(int|RegisterType $registerType, int|null $value): array
     {
         $address = $this->asAddress($registerType);
@@ -733,7 +770,7 @@ class MemoryAccessor implements MemoryAccessorInterface
         $cpu = $this->runtime->context()->cpu();
         $ssSelector = $this->fetch(RegisterType::SS)->asByte();
         $mask = $this->stackPointerMask($stackAddrSize);
-        $linearMask = $cpu->isLongMode() ? 0x0000FFFFFFFFFFFF : ($cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF);
+        $linearMask = $cpu->linearMask();
         $isUser = $cpu->cpl() === 3;
         $pagingEnabled = $cpu->isPagingEnabled();
 
@@ -931,6 +968,11 @@ class MemoryAccessor implements MemoryAccessorInterface
         return [$physical, 0];
     }
 
+    public function invlpg(int $linear): void
+    {
+        // No TLB in the pure PHP accessor
+    }
+
     public function readMemory8(int $linear, bool $isUser, bool $pagingEnabled, int $linearMask): array
     {
         $physical = $linear & $linearMask;
@@ -1046,6 +1088,7 @@ class MemoryAccessor implements MemoryAccessorInterface
             'type' => $type,
             'executable' => $executable,
             'default' => $default,
+            'granularity' => ($b6 & 0x80) !== 0,
         ];
     }
 }
@@ -8,6 +8,14 @@ use PHPMachineEmulator\Instruction\RegisterType;
 
 interface MemoryAccessorInterface
 {
+    /** cpuState() keys of the GPRs, in encoding order. */
+    public const CPU_STATE_GPRS = [
+        'rax', 'rcx', 'rdx', 'rbx', 'rsp', 'rbp', 'rsi', 'rdi',
+        'r8', 'r9', 'r10', 'r11', 'r12', 'r13', 'r14', 'r15',
+    ];
+    /** cpuState() keys of the segment selectors. */
+    public const CPU_STATE_SEGMENTS = ['es', 'cs', 'ss', 'ds', 'fs', 'gs'];
+
     public function allocate(int $address, int $size = 1, bool $safe = true): self;
     public function fetch(int|RegisterType $registerType): MemoryAccessorFetchResultInterface;
     public function tryToFetch(int|RegisterType $registerType): MemoryAccessorFetchResultInterface|null;
@@ -48,6 +56,14 @@ interface MemoryAccessorInterface
     public function writeEfer(int $value): void;
     public function readEfer(): int;
 
+    /**
+     * Snapshot of the registers: CPU_STATE_GPRS, rip, rflags, cr0-cr8, efer
+     * and CPU_STATE_SEGMENTS.
+     *
+     * @return array<string, int>
+     */
+    public function cpuState(): array;
+
     // Physical memory access
     public function readPhysical8(int $address): int;
     public function readPhysical16(int $address): int;
@@ -58,6 +74,7 @@ interface MemoryAccessorInterface
 
     // Linear address translation and memory access with paging
     public function translateLinear(int $linear, bool $isWrite, bool $isUser, bool $pagingEnabled, int $linearMask): array;
+    public function invlpg(int $linear): void;
     public function readMemory8(int $linear, bool $isUser, bool $pagingEnabled, int $linearMask): array;
     public function readMemory16(int $linear, bool $isUser, bool $pagingEnabled, int $linearMask): array;
     public function readMemory32(int $linear, bool $isUser, bool $pagingEnabled, int $linearMask): array;
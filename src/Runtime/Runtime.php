@@ -101,6 +101,10 @@ class Runtime implements RuntimeInterface
             $screenContext,
             $deviceManager,
         );
+        // Keep the native segment state in step with the PHP-side caches.
+        if ($this->memoryAccessor instanceof RuntimeCPUContextObserverInterface) {
+            $this->context->cpu()->attachObserver($this->memoryAccessor);
+        }
 
         // Initialize ticker registry with default tickers
         $this->tickerRegistry = new TickerRegistry();
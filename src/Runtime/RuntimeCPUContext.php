@@ -55,6 +55,7 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
     private bool $a20Enabled = false;
     private bool $waitingA20OutputPort = false;
     private bool $pagingEnabled = false;
+    private bool $la57 = false;
 
     // Privilege
     private bool $userMode = false;
@@ -83,6 +84,8 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
     // - Access memory above 1MB using 32-bit addressing
     private array $segmentDescriptorCache = [];
 
+    private ?RuntimeCPUContextObserverInterface $observer = null;
+
     // Hardware state
     private PicState $picState;
     private ApicState $apicState;
@@ -426,6 +429,7 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
     public function setGdtr(int $base, int $limit): void
     {
         $this->gdtr = ['base' => $base, 'limit' => $limit];
+        $this->observer?->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_GDTR, $base, $limit, 0);
     }
 
     public function gdtr(): array
@@ -436,6 +440,7 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
     public function setIdtr(int $base, int $limit): void
     {
         $this->idtr = ['base' => $base, 'limit' => $limit];
+        $this->observer?->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_IDTR, $base, $limit, 0);
     }
 
     public function idtr(): array
@@ -446,6 +451,7 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
     public function enableA20(bool $enabled = true): void
     {
         $this->a20Enabled = $enabled;
+        $this->observer?->a20Changed($enabled);
     }
 
     public function isA20Enabled(): bool
@@ -473,6 +479,31 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
         return $this->pagingEnabled;
     }
 
+    /**
+     * Mirror of CR4.LA57, kept by the memory accessors on CR4 writes.
+     */
+    public function setLa57(bool $enabled): void
+    {
+        $this->la57 = $enabled;
+    }
+
+    public function isLa57(): bool
+    {
+        return $this->la57;
+    }
+
+    /**
+     * Linear address mask: 57 or 48 bits in IA-32e mode depending on
+     * CR4.LA57, otherwise 32 bits, or 20 while the A20 gate is closed.
+     */
+    public function linearMask(): int
+    {
+        if ($this->longMode) {
+            return $this->la57 ? 0x01FFFFFFFFFFFFFF : 0x0000FFFFFFFFFFFF;
+        }
+        return $this->a20Enabled ? 0xFFFFFFFF : 0xFFFFF;
+    }
+
     public function setUserMode(bool $user): void
     {
         $this->userMode = $user;
@@ -503,6 +534,12 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
             'base' => $baseValue,
             'limit' => $limit & 0xFFFFFFFF,
         ];
+        $this->observer?->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_TR,
+            $this->taskRegister['base'],
+            $this->taskRegister['limit'],
+            $this->taskRegister['selector'],
+        );
     }
 
     public function taskRegister(): array
@@ -520,6 +557,12 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
             'base' => $baseValue,
             'limit' => $limit & 0xFFFFFFFF,
         ];
+        $this->observer?->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_LDTR,
+            $this->ldtr['base'],
+            $this->ldtr['limit'],
+            $this->ldtr['selector'],
+        );
     }
 
     public function ldtr(): array
@@ -537,6 +580,7 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
     public function cacheSegmentDescriptor(RegisterType $segment, array $descriptor): void
     {
         $this->segmentDescriptorCache[$segment->name] = $descriptor;
+        $this->observer?->segmentCached($segment, $descriptor);
     }
 
     /**
@@ -551,6 +595,38 @@ class RuntimeCPUContext implements RuntimeCPUContextInterface
         return $this->segmentDescriptorCache[$segment->name] ?? null;
     }
 
+    /**
+     * Attach the observer notified of hidden segment, descriptor-table and A20 changes.
+     * State cached so far is replayed to it.
+     */
+    public function attachObserver(RuntimeCPUContextObserverInterface $observer): void
+    {
+        $this->observer = $observer;
+        $observer->a20Changed($this->a20Enabled);
+        $this->replayDescriptorTables($observer);
+        foreach ($this->segmentDescriptorCache as $name => $descriptor) {
+            $observer->segmentCached(constant(RegisterType::class . '::' . $name), $descriptor);
+        }
+    }
+
+    private function replayDescriptorTables(RuntimeCPUContextObserverInterface $observer): void
+    {
+        $observer->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_GDTR, $this->gdtr['base'], $this->gdtr['limit'], 0);
+        $observer->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_IDTR, $this->idtr['base'], $this->idtr['limit'], 0);
+        $observer->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_LDTR,
+            $this->ldtr['base'],
+            $this->ldtr['limit'],
+            $this->ldtr['selector'],
+        );
+        $observer->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_TR,
+            $this->taskRegister['base'],
+            $this->taskRegister['limit'],
+            $this->taskRegister['selector'],
+        );
+    }
+
     /**
      * Check if a segment has a cached descriptor with extended limit.
      * This indicates Big Real Mode capability for that segment.
@@ -76,6 +76,7 @@ interface RuntimeCPUContextInterface
     // ========================================
     public function cacheSegmentDescriptor(\PHPMachineEmulator\Instruction\RegisterType $segment, array $descriptor): void;
     public function getCachedSegmentDescriptor(\PHPMachineEmulator\Instruction\RegisterType $segment): ?array;
+    public function attachObserver(RuntimeCPUContextObserverInterface $observer): void;
 
     // ========================================
     // Address line and paging
@@ -87,6 +88,9 @@ interface RuntimeCPUContextInterface
     public function isWaitingA20OutputPort(): bool;
     public function setPagingEnabled(bool $enabled): void;
     public function isPagingEnabled(): bool;
+    public function setLa57(bool $enabled): void;
+    public function isLa57(): bool;
+    public function linearMask(): int;
 
     // ========================================
     // Privilege and protection
@@ -0,0 +1,38 @@
+<?php
+
+declare(strict_types=1);
+
+namespace PHPMachineEmulator\Runtime;
+
+use PHPMachineEmulator\Instruction\RegisterType;
+
+/**
+ * Notified when the CPU context's hidden segment state, descriptor-table
+ * registers or A20 gate change, so a native copy of them (used for segmented
+ * translation, descriptor reads and task switches) can be kept in step.
+ */
+interface RuntimeCPUContextObserverInterface
+{
+    public const TABLE_GDTR = 0;
+    public const TABLE_IDTR = 1;
+    public const TABLE_LDTR = 2;
+    public const TABLE_TR = 3;
+
+    /**
+     * A segment register's hidden descriptor cache was (re)loaded.
+     *
+     * @param array $descriptor Same shape as passed to cacheSegmentDescriptor()
+     */
+    public function segmentCached(RegisterType $segment, array $descriptor): void;
+
+    /**
+     * GDTR, IDTR, LDTR or TR was loaded (one of the TABLE_* constants).
+     * The selector is 0 for GDTR and IDTR.
+     */
+    public function descriptorTableLoaded(int $table, int $base, int $limit, int $selector): void;
+
+    /**
+     * The A20 gate was opened or closed.
+     */
+    public function a20Changed(bool $enabled): void;
+}
@@ -9,6 +9,7 @@ use PHPMachineEmulator\Collection\MemoryAccessorObserverCollectionInterface;
 use PHPMachineEmulator\Exception\FaultException;
 use PHPMachineEmulator\Exception\HaltException;
 use PHPMachineEmulator\Instruction\RegisterType;
+use PHPMachineEmulator\Stream\MemoryStreamInterface;
 use PHPMachineEmulator\Stream\RustFFIContext;
 use PHPMachineEmulator\Stream\RustMemoryStream;
 
@@ -18,7 +19,7 @@ use PHPMachineEmulator\Stream\RustMemoryStream;
  * This class wraps the Rust MemoryAccessor implementation via FFI for
  * significantly improved performance in register and flag operations.
  */
-class RustMemoryAccessor implements MemoryAccessorInterface
+class RustMemoryAccessor implements MemoryAccessorInterface, RuntimeCPUContextObserverInterface
 {
     private const VIDEO_MEMORY_MIN = 0xA0000;
     private const VIDEO_MEMORY_MAX = 0xBFFFF;
@@ -26,6 +27,24 @@ class RustMemoryAccessor implements MemoryAccessorInterface
     private const TEXT_VIDEO_MAX = 0xBFFFF;
     private const VIDEO_TYPE_FLAG_ADDRESS = 0xFF0000;
 
+    /** Reasons accepted by taskSwitch(). */
+    public const TASK_SWITCH_JUMP = 0;
+    public const TASK_SWITCH_CALL = 1;
+
+    public const REPLAY_EVENT_MMIO_READ = 0;
+    public const REPLAY_EVENT_TSC = 1;
+    public const REPLAY_EVENT_INTERRUPT = 2;
+    public const REPLAY_OFF = 0;
+    public const REPLAY_RECORDING = 1;
+    public const REPLAY_REPLAYING = 2;
+    private const REPLAY_OK = 0;
+    private const REPLAY_DIVERGED = 1;
+    /** Guest clock rate of the PIT and APIC timer while recording or replaying. */
+    private const REPLAY_INSTRUCTIONS_PER_SECOND = 1_000_000;
+
+    /** EFLAGS.TF, RF and AC: kept only by the native accessor. */
+    public const EFLAGS_NATIVE_ONLY = (1 << 8) | (1 << 16) | (1 << 18);
+
     private RustFFIContext $ffiContext;
     private ?bool $watchMsDosBoot = null;
     private bool $watchAccessConfigResolved = false;
@@ -47,6 +66,29 @@ class RustMemoryAccessor implements MemoryAccessorInterface
     /** @var FFI\CData Pointer to the Rust MemoryAccessor */
     private FFI\CData $handle;
 
+    /** memory_accessor_debug_armed(), refreshed whenever DR7 or EFLAGS change. */
+    private bool $debugArmed = false;
+
+    /** REPLAY_* mode; only the wrappers below change it. */
+    private int $replayMode = self::REPLAY_OFF;
+
+    /** Reusable out-parameters for native descriptor and segment cache reads. */
+    private ?FFI\CData $descriptorResult = null;
+    private ?FFI\CData $segmentResult = null;
+
+    /** Reusable out-parameters for DRn and MSR reads and task switches. */
+    private ?FFI\CData $resultValue = null;
+    private ?FFI\CData $resultError = null;
+    private ?FFI\CData $taskEip = null;
+
+    /** Reusable out-parameters for native stack pops. */
+    private ?FFI\CData $stackValue = null;
+    private ?FFI\CData $stackError = null;
+    private ?FFI\CData $replayValue = null;
+    private ?FFI\CData $memoryOperand = null;
+    private ?FFI\CData $codeWindow = null;
+    private ?FFI\CData $cpuStateBuffer = null;
+
     private function shouldWatchMsDosBoot(): bool
     {
         if ($this->watchMsDosBoot === null) {
@@ -328,17 +370,18 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         $linearIp = $this->runtime->memory()->offset() & 0xFFFFFFFF;
         $pm = $this->runtime->context()->cpu()->isProtectedMode() ? 1 : 0;
         $pg = $this->runtime->context()->cpu()->isPagingEnabled() ? 1 : 0;
-        $cs = $this->fetch(RegisterType::CS)->asByte() & 0xFFFF;
-        $ds = $this->fetch(RegisterType::DS)->asByte() & 0xFFFF;
-        $es = $this->fetch(RegisterType::ES)->asByte() & 0xFFFF;
-        $ss = $this->fetch(RegisterType::SS)->asByte() & 0xFFFF;
-        $sp = $this->fetch(RegisterType::ESP)->asBytesBySize(16) & 0xFFFF;
-        $eax = $this->fetch(RegisterType::EAX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $ebx = $this->fetch(RegisterType::EBX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $ecx = $this->fetch(RegisterType::ECX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $edx = $this->fetch(RegisterType::EDX)->asBytesBySize(32) & 0xFFFFFFFF;
-        $esi = $this->fetch(RegisterType::ESI)->asBytesBySize(32) & 0xFFFFFFFF;
-        $edi = $this->fetch(RegisterType::EDI)->asBytesBySize(32) & 0xFFFFFFFF;
+        $state = $this->cpuState();
+        $cs = $state['cs'];
+        $ds = $state['ds'];
+        $es = $state['es'];
+        $ss = $state['ss'];
+        $sp = $state['rsp'] & 0xFFFF;
+        $eax = $state['rax'] & 0xFFFFFFFF;
+        $ebx = $state['rbx'] & 0xFFFFFFFF;
+        $ecx = $state['rcx'] & 0xFFFFFFFF;
+        $edx = $state['rdx'] & 0xFFFFFFFF;
+        $esi = $state['rsi'] & 0xFFFFFFFF;
+        $edi = $state['rdi'] & 0xFFFFFFFF;
 
         $executor = $this->runtime->architectureProvider()->instructionExecutor();
         $lastIp = $executor->lastInstructionPointer() & 0xFFFFFFFF;
@@ -441,11 +484,12 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         }
 
         $linearIp = $this->runtime->memory()->offset() & 0xFFFFFFFF;
-        $cs = $this->fetch(RegisterType::CS)->asByte() & 0xFFFF;
-        $ds = $this->fetch(RegisterType::DS)->asByte() & 0xFFFF;
-        $es = $this->fetch(RegisterType::ES)->asByte() & 0xFFFF;
-        $ss = $this->fetch(RegisterType::SS)->asByte() & 0xFFFF;
-        $sp = $this->fetch(RegisterType::ESP)->asBytesBySize(16) & 0xFFFF;
+        $state = $this->cpuState();
+        $cs = $state['cs'];
+        $ds = $state['ds'];
+        $es = $state['es'];
+        $ss = $state['ss'];
+        $sp = $state['rsp'] & 0xFFFF;
         $ip = ($linearIp - (($cs << 4) & 0xFFFFF)) & 0xFFFF;
 
         $this->runtime->option()->logger()->debug(sprintf(
@@ -493,6 +537,7 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         }
 
         $this->announceWatchAccessConfigIfRequested();
+        $this->startMemoryTraceIfRequested();
     }
 
     private function announceWatchAccessConfigIfRequested(): void
@@ -649,6 +694,129 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         return $registerType;
     }
 
+    /**
+     * Native segment index (SEGMENT_ES..SEGMENT_GS) for a segment register.
+     */
+    private function nativeSegmentIndex(RegisterType $segment): ?int
+    {
+        return match ($segment) {
+            RegisterType::ES => 0,
+            RegisterType::CS => 1,
+            RegisterType::SS => 2,
+            RegisterType::DS => 3,
+            RegisterType::FS => 4,
+            RegisterType::GS => 5,
+            default => null,
+        };
+    }
+
+    /**
+     * Hidden base of a segment register as held by the native segment cache.
+     * WRMSR FS_BASE/GS_BASE writes land here as well as selector loads.
+     */
+    public function segmentBase(RegisterType $segment): int
+    {
+        $index = $this->nativeSegmentIndex($segment);
+        if ($index === null) {
+            return 0;
+        }
+        $this->segmentResult ??= $this->ffiContext->new('SegmentCache');
+        if (!$this->ffiContext->memory_accessor_segment_cache($this->handle, $index, FFI::addr($this->segmentResult))) {
+            return 0;
+        }
+        return $this->segmentResult->base;
+    }
+
+    /**
+     * Offset and default segment of a ModR/M memory operand, with the
+     * addressing rules and register reads done natively. The SIB byte and
+     * displacement that follow the ModR/M byte are consumed from $memory.
+     *
+     * @param int $rex low four bits of the REX prefix (W, R, X, B)
+     * @param bool $long 64-bit mode, where mod=00 rm=101 is RIP-relative
+     * @return array{int, RegisterType} [offset, default segment]
+     */
+    public function modRmEffectiveAddress(MemoryStreamInterface $memory, int $modrm, int $addressSize, int $rex, bool $long): array
+    {
+        $operand = $this->memoryOperand ??= $this->ffiContext->new('DecodedInstruction');
+        $operand->modrm = $modrm & 0xFF;
+        $operand->rex = $rex & 0x0F;
+        $operand->address_size = $addressSize;
+        $operand->has_sib = $addressSize !== 16 && ($modrm & 0xC0) !== 0xC0 && ($modrm & 0x07) === 0b100;
+        $operand->sib = $operand->has_sib ? $memory->byte() : 0;
+        $this->ffiContext->x86_resolve_memory_operand(FFI::addr($operand), $long);
+
+        $operand->displacement = match ($operand->displacement_size) {
+            1 => $memory->signedByte(),
+            2 => $memory->signedShort(),
+            4 => $memory->signedDword(),
+            default => 0,
+        };
+
+        // RIP-relative operands are relative to the end of the displacement.
+        $offset = $this->ffiContext->memory_accessor_effective_offset($this->handle, FFI::addr($operand), $memory->offset());
+        $segment = match ($operand->segment) {
+            0 => RegisterType::ES,
+            1 => RegisterType::CS,
+            2 => RegisterType::SS,
+            4 => RegisterType::FS,
+            5 => RegisterType::GS,
+            default => RegisterType::DS,
+        };
+
+        return [$offset, $segment];
+    }
+
+    // ========================================
+    // RuntimeCPUContextObserverInterface implementation
+    // ========================================
+
+    /**
+     * Mirror a hidden segment cache load into the native segment cache, which
+     * segmented translation, the stack helpers and effective addresses use.
+     */
+    public function segmentCached(RegisterType $segment, array $descriptor): void
+    {
+        $index = $this->nativeSegmentIndex($segment);
+        if ($index === null) {
+            return;
+        }
+        $selector = $this->ffiContext->memory_accessor_fetch($this->handle, $this->asAddress($segment)) & 0xFFFF;
+        $limit = ($descriptor['limit'] ?? 0xFFFF) & 0xFFFFFFFF;
+
+        if (!$this->runtime->context()->cpu()->isProtectedMode()) {
+            // Real-mode loads leave a present, writable (or executable) segment.
+            $attributes = $segment === RegisterType::CS ? 0x9B : 0x93;
+        } else {
+            $attributes = (($descriptor['type'] ?? 0) & 0x0F)
+                | (($descriptor['system'] ?? false) ? 0 : 0x10)
+                | ((($descriptor['dpl'] ?? 0) & 0x3) << 5)
+                | (($descriptor['present'] ?? false) ? 0x80 : 0)
+                | (($descriptor['long'] ?? false) ? 0x2000 : 0)
+                | (($descriptor['default'] ?? 16) === 32 ? 0x4000 : 0)
+                | (($descriptor['granularity'] ?? false) ? 0x8000 : 0);
+        }
+
+        $this->ffiContext->memory_accessor_load_segment_cache(
+            $this->handle,
+            $index,
+            $selector,
+            $descriptor['base'] ?? 0,
+            $limit,
+            $attributes,
+        );
+    }
+
+    public function descriptorTableLoaded(int $table, int $base, int $limit, int $selector): void
+    {
+        $this->ffiContext->memory_accessor_load_descriptor_table($this->handle, $table, $base, $limit, $selector);
+    }
+
+    public function a20Changed(bool $enabled): void
+    {
+        $this->ffiContext->memory_accessor_set_a20($this->handle, $enabled);
+    }
+
     // ========================================
     // MemoryAccessorInterface implementation
     // ========================================
@@ -902,23 +1070,42 @@ class RustMemoryAccessor implements MemoryAccessorInterface
 
     public function pop(int|RegisterType $registerType, int $size = 16): MemoryAccessorFetchResultInterface
     {
-        // Stack operations still need PHP-side handling for complex logic
-        // This is a simplified version - full implementation would need more work
         $address = $this->asAddress($registerType);
 
         if ($registerType instanceof RegisterType && $registerType === RegisterType::ESP) {
+            $cpu = $this->runtime->context()->cpu();
+            $this->stackValue ??= $this->ffiContext->new('uint64_t');
+            $this->stackError ??= $this->ffiContext->new('uint32_t');
+
+            $this->ffiContext->memory_accessor_pop(
+                $this->handle,
+                $size,
+                $cpu->cpl() === 3,
+                $cpu->isPagingEnabled(),
+                $this->runtime->context()->cpu()->linearMask(),
+                FFI::addr($this->stackValue),
+                FFI::addr($this->stackError)
+            );
+
+            $error = $this->stackError->cdata;
+            if ($error === 0) {
+                return new MemoryAccessorFetchResult($this->stackValue->cdata, $size, alreadyDecoded: true);
+            }
+            if ($error !== 0xFFFFFFFF) {
+                throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, 'Stack pop fault');
+            }
+
+            // MMIO-backed stack: read it byte by byte.
             $stackAddrSize = $this->stackAddressSize();
             $sp = $this->fetch(RegisterType::ESP)->asBytesBySize($stackAddrSize);
             $bytes = intdiv($size, 8);
             $address = $this->stackLinearAddress($sp, $stackAddrSize, false);
 
-            // Read value from stack
             $value = 0;
             for ($i = 0; $i < $bytes; $i++) {
                 $value |= $this->ffiContext->memory_accessor_read_from_memory($this->handle, $address + $i) << ($i * 8);
             }
 
-            // Update SP
             $mask = $this->stackPointerMask($stackAddrSize);
             $newSp = ($sp + $bytes) & $mask;
             $this->writeBySize(RegisterType::ESP, $newSp, $stackAddrSize);
@@ -961,13 +1148,37 @@ class RustMemoryAccessor implements MemoryAccessorInterface
                 throw new HaltException('Stopped by PHPME_STOP_ON_STACK_UNDERFLOW');
             }
 
+            $cpu = $this->runtime->context()->cpu();
+            $masked = $value & $this->valueMask($size);
             $newSp = ($sp - $bytes) & $mask;
+
+            $error = $this->ffiContext->memory_accessor_push(
+                $this->handle,
+                $masked,
+                $size,
+                $cpu->cpl() === 3,
+                $cpu->isPagingEnabled(),
+                $this->runtime->context()->cpu()->linearMask()
+            );
+            if ($error === 0) {
+                // The native push bypasses writeBySize, so run its write hooks here.
+                $address = $this->stackLinearAddress($newSp, $stackAddrSize, true);
+                for ($i = 0; $i < $bytes; $i++) {
+                    $this->postProcessWhenWrote($address + $i, null, ($masked >> ($i * 8)) & 0xFF);
+                }
+                $this->invalidateInstructionCachesOnWrite($address, $bytes);
+                return $this;
+            }
+            if ($error !== 0xFFFFFFFF) {
+                throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, 'Stack push fault');
+            }
+
+            // MMIO-backed stack: write it byte by byte.
             $address = $this->stackLinearAddress($newSp, $stackAddrSize, true);
 
             $this->writeBySize(RegisterType::ESP, $newSp, $stackAddrSize);
             $this->allocate($address, $bytes, safe: false);
 
-            $masked = $value & $this->valueMask($size);
             for ($i = 0; $i < $bytes; $i++) {
                 $this->writeBySize($address + $i, ($masked >> ($i * 8)) & 0xFF, 8);
             }
@@ -996,9 +1207,30 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         // Mode changes can alter instruction decoding/execution semantics
         if ($index === 0 && $previous !== $value) {
             $this->runtime->architectureProvider()->instructionExecutor()->invalidateCaches();
+        } elseif ($index === 4) {
+            $this->runtime->context()->cpu()->setLa57(($value & (1 << 12)) !== 0);
         }
     }
 
+    /**
+     * MOV to CRn with the architectural checks applied.
+     *
+     * @return int 0, or a packed fault ((vector << 16) | error code)
+     */
+    public function loadControlRegister(int $index, int $value): int
+    {
+        $previous = $this->ffiContext->memory_accessor_read_control_register($this->handle, $index);
+        $error = $this->ffiContext->memory_accessor_load_control_register($this->handle, $index, $value);
+
+        if ($error === 0 && $index === 0 && $previous !== $this->ffiContext->memory_accessor_read_control_register($this->handle, 0)) {
+            $this->runtime->architectureProvider()->instructionExecutor()->invalidateCaches();
+        } elseif ($error === 0 && $index === 4) {
+            $this->runtime->context()->cpu()->setLa57(($value & (1 << 12)) !== 0);
+        }
+
+        return $error;
+    }
+
     public function shouldZeroFlag(): bool
     {
         return $this->ffiContext->memory_accessor_zero_flag($this->handle);
@@ -1121,6 +1353,149 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         return $this->ffiContext->memory_accessor_read_efer($this->handle);
     }
 
+    /**
+     * Capture the registers in one call (memory_accessor_get_state).
+     */
+    public function cpuState(): array
+    {
+        $buffer = $this->cpuStateBuffer ??= $this->ffiContext->new('CpuState');
+        $this->ffiContext->memory_accessor_get_state($this->handle, FFI::addr($buffer));
+
+        $state = [];
+        foreach (self::CPU_STATE_GPRS as $i => $name) {
+            $state[$name] = $buffer->gprs[$i];
+        }
+        $state['rip'] = $buffer->rip;
+        $state['rflags'] = $buffer->rflags;
+        for ($i = 0; $i <= 8; $i++) {
+            $state['cr' . $i] = $buffer->control_registers[$i];
+        }
+        $state['efer'] = $buffer->efer;
+        foreach (self::CPU_STATE_SEGMENTS as $i => $name) {
+            $state[$name] = $buffer->segments[$i];
+        }
+
+        return $state;
+    }
+
+    /**
+     * Load a cpuState() snapshot in one call (memory_accessor_set_state).
+     * Control registers go through the MOV to CRn paths; selectors are
+     * loaded like MOV to Sreg in the resulting mode.
+     *
+     * @param array<string, int> $state
+     */
+    public function setCpuState(array $state): void
+    {
+        $buffer = $this->cpuStateBuffer ??= $this->ffiContext->new('CpuState');
+        foreach (self::CPU_STATE_GPRS as $i => $name) {
+            $buffer->gprs[$i] = $state[$name];
+        }
+        $buffer->rip = $state['rip'];
+        $buffer->rflags = $state['rflags'];
+        for ($i = 0; $i <= 8; $i++) {
+            $buffer->control_registers[$i] = $state['cr' . $i];
+        }
+        $buffer->efer = $state['efer'];
+        foreach (self::CPU_STATE_SEGMENTS as $i => $name) {
+            $buffer->segments[$i] = $state[$name] & 0xFFFF;
+        }
+
+        $this->ffiContext->memory_accessor_set_state($this->handle, FFI::addr($buffer));
+        $this->runtime->architectureProvider()->instructionExecutor()->invalidateCaches();
+        $this->runtime->context()->cpu()->setLa57(($state['cr4'] & (1 << 12)) !== 0);
+        $this->refreshDebugArmed();
+    }
+
+    /**
+     * MOV from DRn. Returns [value, error_code] where error_code is 0 or a
+     * packed #GP/#UD/#DB.
+     */
+    public function readDebugRegister(int $index): array
+    {
+        $resultValue = $this->resultValue ??= $this->ffiContext->new('uint64_t');
+        $resultError = $this->resultError ??= $this->ffiContext->new('uint32_t');
+
+        $this->ffiContext->memory_accessor_read_debug_register(
+            $this->handle,
+            $index,
+            FFI::addr($resultValue),
+            FFI::addr($resultError)
+        );
+
+        return [$resultValue->cdata, $resultError->cdata];
+    }
+
+    /**
+     * MOV to DRn. Returns 0 or a packed #GP/#UD/#DB.
+     */
+    public function writeDebugRegister(int $index, int $value): int
+    {
+        $error = $this->ffiContext->memory_accessor_write_debug_register($this->handle, $index, $value);
+        $this->refreshDebugArmed();
+
+        return $error;
+    }
+
+    /**
+     * An enabled DR7 breakpoint or EFLAGS.TF: the executor must bracket
+     * each instruction with debugInstructionBegin()/finishInstruction().
+     */
+    public function debugArmed(): bool
+    {
+        return $this->debugArmed;
+    }
+
+    private function refreshDebugArmed(): void
+    {
+        $this->debugArmed = $this->ffiContext->memory_accessor_debug_armed($this->handle);
+    }
+
+    /**
+     * Open the debug bracket of the instruction at $linear.
+     * Returns 0 or a packed #DB fault for a matching instruction breakpoint.
+     */
+    public function debugInstructionBegin(int $linear): int
+    {
+        return $this->ffiContext->memory_accessor_debug_instruction_begin($this->handle, $linear);
+    }
+
+    /**
+     * Close the debug bracket of a completed instruction and count it.
+     * Returns 0 or a packed #DB trap for data breakpoints or single-step.
+     */
+    public function finishInstruction(): int
+    {
+        return $this->ffiContext->memory_accessor_finish_instruction($this->handle);
+    }
+
+    /**
+     * RDMSR through the native MSR file. Returns [value, error_code] where
+     * error_code is 0 or a packed #GP(0).
+     */
+    public function readMsr(int $index): array
+    {
+        $resultValue = $this->resultValue ??= $this->ffiContext->new('uint64_t');
+        $resultError = $this->resultError ??= $this->ffiContext->new('uint32_t');
+
+        $this->ffiContext->memory_accessor_rdmsr(
+            $this->handle,
+            $index & 0xFFFFFFFF,
+            FFI::addr($resultValue),
+            FFI::addr($resultError)
+        );
+
+        return [$resultValue->cdata, $resultError->cdata];
+    }
+
+    /**
+     * WRMSR through the native MSR file. Returns 0 or a packed #GP(0).
+     */
+    public function writeMsr(int $index, int $value): int
+    {
+        return $this->ffiContext->memory_accessor_wrmsr($this->handle, $index & 0xFFFFFFFF, $value);
+    }
+
     /**
      * Write a raw byte to memory.
      */
@@ -1245,6 +1620,11 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         return [$resultPhysical->cdata, $resultError->cdata];
     }
 
+    public function invlpg(int $linear): void
+    {
+        $this->ffiContext->memory_accessor_invlpg($this->handle, $linear);
+    }
+
     /**
      * Compute stack linear address honoring segment base/limit and cached descriptors.
      */
@@ -1253,7 +1633,7 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         $cpu = $this->runtime->context()->cpu();
         $ssSelector = $this->fetch(RegisterType::SS)->asByte();
         $mask = $this->stackPointerMask($stackAddrSize);
-        $linearMask = $cpu->isLongMode() ? 0x0000FFFFFFFFFFFF : ($cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF);
+        $linearMask = $this->runtime->context()->cpu()->linearMask();
         $isUser = $cpu->cpl() === 3;
         $pagingEnabled = $cpu->isPagingEnabled();
 
@@ -1361,88 +1741,42 @@ class RustMemoryAccessor implements MemoryAccessorInterface
      */
     private function segmentDescriptor(int $selector): ?array
     {
-        $ti = ($selector >> 2) & 0x1;
-        if ($ti === 1) {
-            $ldtr = $this->runtime->context()->cpu()->ldtr();
-            $base = $ldtr['base'] ?? 0;
-            $limit = $ldtr['limit'] ?? 0;
-            if (($ldtr['selector'] ?? 0) === 0) {
-                return null;
-            }
-        } else {
-            $gdtr = $this->runtime->context()->cpu()->gdtr();
-            $base = $gdtr['base'] ?? 0;
-            $limit = $gdtr['limit'] ?? 0;
-        }
-
-        $index = ($selector >> 3) & 0x1FFF;
-        $offset = $base + ($index * 8);
-
-        if ($offset + 7 > $base + $limit) {
-            return null;
-        }
+        $cpu = $this->runtime->context()->cpu();
+        $gdtr = $cpu->gdtr();
+        $ldtr = $cpu->ldtr();
 
-        $linearMask = $this->runtime->context()->cpu()->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF;
-        $isUser = $this->runtime->context()->cpu()->cpl() === 3;
-        $pagingEnabled = $this->runtime->context()->cpu()->isPagingEnabled();
+        $this->descriptorResult ??= $this->ffiContext->new('SegmentDescriptor');
 
-        // Fast path: fetch full 64-bit descriptor in one FFI call.
-        $desc64 = $this->ffiContext->new('uint64_t');
-        $descErr = $this->ffiContext->new('uint32_t');
-        $this->ffiContext->memory_accessor_read_memory_64(
+        // This only refreshes the hidden cache; the instruction's own descriptor
+        // read reports faults, so the peek leaves CR2 alone.
+        $found = $this->ffiContext->memory_accessor_peek_segment_descriptor(
             $this->handle,
-            $offset,
-            $isUser,
-            $pagingEnabled,
-            $linearMask,
-            FFI::addr($desc64),
-            FFI::addr($descErr)
+            $gdtr['base'] ?? 0,
+            $gdtr['limit'] ?? 0,
+            $ldtr['selector'] ?? 0,
+            $ldtr['base'] ?? 0,
+            $ldtr['limit'] ?? 0,
+            $selector & 0xFFFF,
+            FFI::addr($this->descriptorResult)
         );
 
-        if ($descErr->cdata === 0) {
-            $val = $desc64->cdata;
-            $b0 = $val & 0xFF;
-            $b1 = ($val >> 8) & 0xFF;
-            $b2 = ($val >> 16) & 0xFF;
-            $b3 = ($val >> 24) & 0xFF;
-            $b4 = ($val >> 32) & 0xFF;
-            $b5 = ($val >> 40) & 0xFF;
-            $b6 = ($val >> 48) & 0xFF;
-            $b7 = ($val >> 56) & 0xFF;
-        } else {
-            // Fallback: byte-by-byte read (should rarely happen).
-            $b0 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset);
-            $b1 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset + 1);
-            $b2 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset + 2);
-            $b3 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset + 3);
-            $b4 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset + 4);
-            $b5 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset + 5);
-            $b6 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset + 6);
-            $b7 = $this->ffiContext->memory_accessor_read_from_memory($this->handle, $offset + 7);
-        }
-
-        $limitLow = $b0 | ($b1 << 8);
-        $limitHigh = $b6 & 0x0F;
-        $fullLimit = $limitLow | ($limitHigh << 16);
-        if (($b6 & 0x80) !== 0) {
-            $fullLimit = ($fullLimit << 12) | 0xFFF;
-        }
-
-        $baseAddr = $b2 | ($b3 << 8) | ($b4 << 16) | ($b7 << 24);
-        $present = ($b5 & 0x80) !== 0;
-        $dpl = ($b5 >> 5) & 0x3;
-        $type = $b5 & 0x0F;
-        $executable = ($type & 0x08) !== 0;
-        $default = ($b6 & 0x40) !== 0 ? 32 : 16;
+        if (!$found) {
+            return null;
+        }
+
+        $descriptor = $this->descriptorResult;
+        $type = $descriptor->descriptor_type & 0x0F;
 
         return [
-            'base' => $baseAddr & 0xFFFFFFFF,
-            'limit' => $fullLimit & 0xFFFFFFFF,
-            'present' => $present,
-            'dpl' => $dpl,
+            'base' => $descriptor->base & 0xFFFFFFFF,
+            'limit' => $descriptor->limit & 0xFFFFFFFF,
+            'present' => $descriptor->present,
+            'dpl' => $descriptor->dpl,
             'type' => $type,
-            'executable' => $executable,
-            'default' => $default,
+            'executable' => ($type & 0x08) !== 0,
+            'default' => ($descriptor->attributes & 0x4000) !== 0 ? 32 : 16,
+            'long' => ($descriptor->attributes & 0x2000) !== 0,
+            'granularity' => ($descriptor->attributes & 0x8000) !== 0,
         ];
     }
 
@@ -1454,6 +1788,35 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         return $this->ffiContext->memory_accessor_is_mmio_address($address);
     }
 
+    /**
+     * Fetch up to $maxLength (at most 15) opcode bytes at $linear in one call.
+     * Returns [bytes, fault]: fault is a packed fault or 0xFFFFFFFF (MMIO) for
+     * the byte after the last one returned, or 0 when every byte was fetched.
+     * Only a fault on the first byte sets CR2.
+     *
+     * @return array{list<int>, int}
+     */
+    public function fetchCode(int $linear, int $maxLength, bool $isUser, bool $pagingEnabled, int $linearMask): array
+    {
+        $window = $this->codeWindow ??= $this->ffiContext->new('CodeWindow');
+        $this->ffiContext->memory_accessor_fetch_code(
+            $this->handle,
+            $linear,
+            $maxLength,
+            $isUser,
+            $pagingEnabled,
+            $linearMask,
+            FFI::addr($window)
+        );
+
+        $bytes = [];
+        for ($i = 0, $length = $window->length; $i < $length; $i++) {
+            $bytes[] = $window->bytes[$i];
+        }
+
+        return [$bytes, $window->fault];
+    }
+
     /**
      * Read 8-bit memory with linear address translation.
      * Returns [value, error_code].
@@ -1662,4 +2025,312 @@ class RustMemoryAccessor implements MemoryAccessorInterface
         $this->ffiContext->memory_accessor_write_physical_16($this->handle, $address, $value & 0xFFFF);
         $this->invalidateInstructionCachesOnWrite($address, 2);
     }
+
+    // ========================================
+    // Hardware task switching
+    // ========================================
+
+    /**
+     * Read GDTR, IDTR, LDTR or TR (RuntimeCPUContextObserverInterface::TABLE_*).
+     *
+     * @return array{base: int, limit: int, selector: int}
+     */
+    public function descriptorTable(int $table): array
+    {
+        $result = $this->ffiContext->new('DescriptorTableRegister');
+        $this->ffiContext->memory_accessor_descriptor_table($this->handle, $table, FFI::addr($result));
+
+        return [
+            'base' => $result->base,
+            'limit' => $result->limit,
+            'selector' => $result->selector,
+        ];
+    }
+
+    public function readEflags(): int
+    {
+        return $this->ffiContext->memory_accessor_read_eflags($this->handle);
+    }
+
+    public function writeEflags(int $value): void
+    {
+        $this->ffiContext->memory_accessor_write_eflags($this->handle, $value & 0xFFFFFFFF);
+        $this->refreshDebugArmed();
+    }
+
+    /**
+     * EFLAGS.TF, RF and AC; PHP does not track these bits itself.
+     */
+    public function systemFlags(): int
+    {
+        return $this->readEflags() & self::EFLAGS_NATIVE_ONLY;
+    }
+
+    /**
+     * Load EFLAGS.TF, RF and AC from a flags image, leaving the other bits alone.
+     */
+    public function writeSystemFlags(int $flags): void
+    {
+        $this->ffiContext->memory_accessor_write_system_flags($this->handle, $flags & 0xFFFFFFFF);
+        $this->refreshDebugArmed();
+    }
+
+    /**
+     * JMP/CALL (TASK_SWITCH_*) to a TSS or task gate.
+     * Returns [new_eip, error_code]; see completeTaskSwitch() in TaskSwitchTrait.
+     */
+    public function taskSwitch(int $selector, int $reason, int $returnEip): array
+    {
+        [$eip, $error] = $this->taskSwitchResults();
+        $this->ffiContext->memory_accessor_task_switch(
+            $this->handle,
+            $selector & 0xFFFF,
+            $reason,
+            $returnEip & 0xFFFFFFFF,
+            FFI::addr($eip),
+            FFI::addr($error)
+        );
+        $this->refreshDebugArmed();
+
+        return [$eip->cdata, $error->cdata];
+    }
+
+    /**
+     * Deliver $vector through its IDT task gate. Returns [new_eip, error_code].
+     */
+    public function interruptTaskSwitch(int $vector, int $returnEip, ?int $errorCode): array
+    {
+        [$eip, $error] = $this->taskSwitchResults();
+        $this->ffiContext->memory_accessor_interrupt_task_switch(
+            $this->handle,
+            $vector & 0xFF,
+            $returnEip & 0xFFFFFFFF,
+            $errorCode !== null,
+            ($errorCode ?? 0) & 0xFFFFFFFF,
+            FFI::addr($eip),
+            FFI::addr($error)
+        );
+        $this->refreshDebugArmed();
+
+        return [$eip->cdata, $error->cdata];
+    }
+
+    /**
+     * IRET with NT set: return to the task in the back-link. Returns [new_eip, error_code].
+     */
+    public function iretTaskSwitch(int $returnEip): array
+    {
+        [$eip, $error] = $this->taskSwitchResults();
+        $this->ffiContext->memory_accessor_iret_task_switch(
+            $this->handle,
+            $returnEip & 0xFFFFFFFF,
+            FFI::addr($eip),
+            FFI::addr($error)
+        );
+        $this->refreshDebugArmed();
+
+        return [$eip->cdata, $error->cdata];
+    }
+
+    /**
+     * @return array{FFI\CData, FFI\CData}
+     */
+    private function taskSwitchResults(): array
+    {
+        $this->taskEip ??= $this->ffiContext->new('uint32_t');
+        $this->resultError ??= $this->ffiContext->new('uint32_t');
+
+        return [$this->taskEip, $this->resultError];
+    }
+
+    // ========================================
+    // Record and replay
+    // ========================================
+
+    public function replayMode(): int
+    {
+        return $this->replayMode;
+    }
+
+    /**
+     * Start recording nondeterministic inputs; the instruction count restarts at 0.
+     */
+    public function startRecording(): void
+    {
+        $this->ffiContext->memory_accessor_replay_record($this->handle);
+        $this->replayMode = self::REPLAY_RECORDING;
+        $this->useReplayClock(true);
+    }
+
+    /**
+     * Start replaying the log file at $path. Returns a REPLAY_LOG_* status (0 = ok).
+     */
+    public function startReplay(string $path): int
+    {
+        $status = $this->ffiContext->memory_accessor_replay_load($this->handle, $path);
+        $this->replayMode = $this->ffiContext->memory_accessor_replay_mode($this->handle);
+        $this->useReplayClock($this->replayMode === self::REPLAY_REPLAYING);
+
+        return $status;
+    }
+
+    /**
+     * Write the recorded log to $path. Returns a REPLAY_LOG_* status (0 = ok).
+     */
+    public function saveReplay(string $path): int
+    {
+        return $this->ffiContext->memory_accessor_replay_save($this->handle, $path);
+    }
+
+    /**
+     * Stop recording or replaying; a recorded log stays available to saveReplay().
+     */
+    public function stopReplay(): void
+    {
+        $this->ffiContext->memory_accessor_replay_stop($this->handle);
+        $this->replayMode = self::REPLAY_OFF;
+        $this->useReplayClock(false);
+    }
+
+    /**
+     * Time-driven devices would otherwise fire at host-clock moments that
+     * differ between the recording and the replay, so both count guest time
+     * from retired instructions.
+     */
+    private function useReplayClock(bool $enabled): void
+    {
+        $clock = $enabled
+            ? fn (): float => $this->ffiContext->memory_accessor_instruction_count($this->handle) / self::REPLAY_INSTRUCTIONS_PER_SECOND
+            : null;
+        $cpu = $this->runtime->context()->cpu();
+        $cpu->pit()->useClock($clock);
+        $cpu->apicState()->useClock($clock);
+    }
+
+    /**
+     * Count one completed instruction; recorded inputs are tagged with this count.
+     */
+    public function retireInstruction(): void
+    {
+        $this->ffiContext->memory_accessor_retire_instruction($this->handle);
+    }
+
+    /**
+     * Pass a nondeterministic input through the recorder and return the
+     * value the guest observes: the live value, or the logged one while replaying.
+     */
+    public function replayInput(int $kind, int $address, int $size, int $value): int
+    {
+        $this->replayValue ??= $this->ffiContext->new('uint64_t');
+        $status = $this->ffiContext->memory_accessor_replay_input(
+            $this->handle,
+            $kind,
+            $address,
+            $size,
+            $value,
+            FFI::addr($this->replayValue)
+        );
+        if ($status !== self::REPLAY_OK) {
+            $this->runtime->option()->logger()->warning(sprintf(
+                'REPLAY: %s at instruction %d (kind=%d address=0x%X)',
+                $status === self::REPLAY_DIVERGED ? 'diverged' : 'log exhausted',
+                $this->ffiContext->memory_accessor_instruction_count($this->handle),
+                $kind,
+                $address,
+            ));
+        }
+
+        return $this->replayValue->cdata;
+    }
+
+    /**
+     * While replaying, the vector of the interrupt logged for this
+     * instruction boundary, or null.
+     */
+    public function replayInterrupt(): ?int
+    {
+        $vector = $this->ffiContext->memory_accessor_replay_interrupt($this->handle);
+
+        return $vector < 0 ? null : $vector;
+    }
+
+    /**
+     * Record an interrupt about to be delivered (no-op unless recording).
+     */
+    public function recordInterrupt(int $vector): void
+    {
+        $this->ffiContext->memory_accessor_record_interrupt($this->handle, $vector & 0xFF);
+    }
+
+    /**
+     * RDTSC through the recorder.
+     */
+    public function readTsc(): int
+    {
+        return $this->ffiContext->memory_accessor_read_tsc($this->handle);
+    }
+
+    /**
+     * Start the native memory tracer on $path for the MemoryAccessDebugConfig::TRACE_*
+     * types in $accessMask, replacing any running trace. False if the log could not be created.
+     */
+    public function startTrace(string $path, int $accessMask): bool
+    {
+        return $this->ffiContext->memory_accessor_trace_start($this->handle, $path, $accessMask & 0xFFFF);
+    }
+
+    /**
+     * Restrict the running trace to linear $start..$end (inclusive); ranges accumulate.
+     */
+    public function addTraceRange(int $start, int $end): void
+    {
+        $this->ffiContext->memory_accessor_trace_add_range($this->handle, $start, $end);
+    }
+
+    /**
+     * Flush and close the trace. False if a write failed or no trace was running.
+     */
+    public function stopTrace(): bool
+    {
+        return $this->ffiContext->memory_accessor_trace_stop($this->handle);
+    }
+
+    private function startMemoryTraceIfRequested(): void
+    {
+        $debug = $this->runtime->logicBoard()->debug();
+        $log = $debug->trace()->memoryTraceLog;
+        if ($log === null) {
+            return;
+        }
+
+        $memoryAccess = $debug->memoryAccess();
+        $logger = $this->runtime->option()->logger();
+        if (!$this->startTrace($log, $memoryAccess->traceAccessMask)) {
+            $logger->warning(sprintf('MEMTRACE: disabled (cannot create %s)', $log));
+            return;
+        }
+        foreach ($memoryAccess->traceRanges as $range) {
+            $this->addTraceRange($range['start'], $range['end']);
+        }
+        $logger->warning(sprintf(
+            'MEMTRACE: enabled log=%s access=0x%X ranges=%d',
+            $log,
+            $memoryAccess->traceAccessMask,
+            count($memoryAccess->traceRanges),
+        ));
+
+        $text = $debug->trace()->memoryTraceText;
+        $this->runtime->shutdown(function () use ($log, $text, $logger): void {
+            if (!$this->stopTrace()) {
+                $logger->warning(sprintf('MEMTRACE: %s is incomplete (write failed)', $log));
+            }
+            if ($text === null) {
+                return;
+            }
+            $records = $this->ffiContext->memory_trace_convert($log, $text);
+            $logger->warning($records < 0
+                ? sprintf('MEMTRACE: failed to convert %s', $log)
+                : sprintf('MEMTRACE: wrote %d records to %s', $records, $text));
+        });
+    }
 }
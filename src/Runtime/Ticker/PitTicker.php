@@ -44,8 +44,10 @@ class PitTicker implements TickerInterface
 
         // Advance BIOS tick counter in (approximate) real time so bootloader timeouts
         // behave correctly even when the emulator executes slowly in PHP.
-        $now = microtime(true);
-        if ($this->lastTickTimeSec <= 0.0) {
+        // Uses the PIT's clock, which counts instructions while recording or replaying.
+        $now = $this->pit->now();
+        if ($this->lastTickTimeSec <= 0.0 || $now < $this->lastTickTimeSec) {
+            // First tick, or the PIT switched clocks.
             $this->lastTickTimeSec = $now;
             return;
         }
@@ -10,6 +10,7 @@ use PHPMachineEmulator\Instruction\Stream\ModRegRMInterface;
 use PHPMachineEmulator\Instruction\Stream\SIB;
 use PHPMachineEmulator\Instruction\Stream\SIBInterface;
 use PHPMachineEmulator\Runtime\RuntimeInterface;
+use PHPMachineEmulator\Runtime\RustMemoryAccessor;
 use PHPMachineEmulator\Stream\RustMemoryStream;
 
 /**
@@ -120,6 +121,38 @@ final class PagedMemoryStream implements MemoryStreamInterface
         return $value;
     }
 
+    /**
+     * Read up to $maxLength opcode bytes at the offset in one native call.
+     * Fewer bytes come back when a later page cannot be fetched or is MMIO;
+     * byte() then raises the fault if the decoder needs them.
+     *
+     * @return list<int>
+     */
+    public function fetchCode(int $maxLength): array
+    {
+        $ma = $this->runtime->memoryAccessor();
+        if (!$ma instanceof RustMemoryAccessor) {
+            $bytes = [];
+            for ($i = 0; $i < $maxLength && !$this->isEOF(); $i++) {
+                $bytes[] = $this->byte();
+            }
+            return $bytes;
+        }
+
+        $cpu = $this->runtime->context()->cpu();
+        $mask = $this->linearMask();
+        [$bytes, $fault] = $ma->fetchCode($this->offset & $mask, $maxLength, $cpu->cpl() === 3, $cpu->isPagingEnabled(), $mask);
+        if ($bytes === [] && $fault !== 0) {
+            if ($fault !== 0xFFFFFFFF) {
+                $this->throwPageFault($fault);
+            }
+            return [$this->byte()];
+        }
+
+        $this->offset += count($bytes);
+        return $bytes;
+    }
+
     public function signedByte(): int
     {
         $byte = $this->byte();
@@ -269,11 +302,7 @@ final class PagedMemoryStream implements MemoryStreamInterface
 
     private function linearMask(): int
     {
-        $cpu = $this->runtime->context()->cpu();
-        if ($cpu->isLongMode()) {
-            return 0x0000FFFFFFFFFFFF;
-        }
-        return $cpu->isA20Enabled() ? 0xFFFFFFFF : 0xFFFFF;
+        return $this->runtime->context()->cpu()->linearMask();
     }
 
     private function syncTranslationContext(int $mask, bool $pagingEnabled, bool $isUser): void
@@ -331,7 +360,7 @@ final class PagedMemoryStream implements MemoryStreamInterface
         );
 
         if ($error !== 0 && $error !== 0xFFFFFFFF) {
-            $this->throwPageFault($linearMasked, $error);
+            $this->throwPageFault($error);
         }
 
         if ($error === 0) {
@@ -370,7 +399,7 @@ final class PagedMemoryStream implements MemoryStreamInterface
         }
 
         if ($error !== 0) {
-            $this->throwPageFault($linearMasked, $error);
+            $this->throwPageFault($error);
         }
 
         return $value;
@@ -399,7 +428,7 @@ final class PagedMemoryStream implements MemoryStreamInterface
         }
 
         if ($error !== 0) {
-            $this->throwPageFault($linearMasked, $error);
+            $this->throwPageFault($error);
         }
     }
 
@@ -429,22 +458,11 @@ final class PagedMemoryStream implements MemoryStreamInterface
         $this->physical->setOffset($saved);
     }
 
-    private function throwPageFault(int $linear, int $error): void
+    /**
+     * The native walker has already set CR2 for a #PF.
+     */
+    private function throwPageFault(int $error): void
     {
-        $vector = ($error >> 16) & 0xFF;
-        $errorCode = $error & 0xFFFF;
-        $cpu = $this->runtime->context()->cpu();
-
-        if ($cpu->isLongMode() && !$cpu->isCompatibilityMode()) {
-            $masked = $linear & 0x0000FFFFFFFFFFFF;
-            $canonical = ($masked & 0x0000800000000000) !== 0
-                ? ($masked | (-1 << 48))
-                : $masked;
-            $this->runtime->memoryAccessor()->writeControlRegister(2, $canonical);
-        } else {
-            $this->runtime->memoryAccessor()->writeControlRegister(2, $linear & 0xFFFFFFFF);
-        }
-
-        throw new FaultException($vector, $errorCode, 'Page fault');
+        throw new FaultException(($error >> 16) & 0xFF, $error & 0xFFFF, 'Page fault');
     }
 }
@@ -73,6 +73,9 @@ use FFI;
  * @method void memory_accessor_set_instruction_fetch(\FFI\CData $accessor, bool $value)
  * @method int memory_accessor_read_control_register(\FFI\CData $accessor, int $index)
  * @method void memory_accessor_write_control_register(\FFI\CData $accessor, int $index, int $value)
+ * @method int memory_accessor_load_control_register(\FFI\CData $accessor, int $index, int $value)
+ * @method int memory_accessor_task_priority(\FFI\CData $accessor)
+ * @method void memory_accessor_set_task_priority(\FFI\CData $accessor, int $tpr)
  * @method int memory_accessor_read_efer(\FFI\CData $accessor)
  * @method void memory_accessor_write_efer(\FFI\CData $accessor, int $value)
  * @method int memory_accessor_read_from_memory(\FFI\CData $accessor, int $address)
@@ -96,6 +99,58 @@ use FFI;
  * @method int memory_accessor_write_memory_32(\FFI\CData $accessor, int $linear, int $value, bool $is_user, bool $paging_enabled, int $linear_mask)
  * @method int memory_accessor_write_memory_64(\FFI\CData $accessor, int $linear, int $value, bool $is_user, bool $paging_enabled, int $linear_mask)
  * @method void memory_accessor_write_physical_16(\FFI\CData $accessor, int $address, int $value)
+ * @method bool memory_accessor_segment_cache(\FFI\CData $accessor, int $segment, \FFI\CData $result)
+ * @method bool memory_accessor_load_segment_cache(\FFI\CData $accessor, int $segment, int $selector, int $base, int $limit, int $attributes)
+ * @method void memory_accessor_segmented_linear(\FFI\CData $accessor, int $segment, int $offset, int $size, int $kind, \FFI\CData $result_linear, \FFI\CData $result_error)
+ * @method void memory_accessor_read_segment_descriptor(\FFI\CData $accessor, int $gdtr_base, int $gdtr_limit, int $ldtr_selector, int $ldtr_base, int $ldtr_limit, int $selector, bool $set_accessed, \FFI\CData $result, \FFI\CData $result_error)
+ * @method bool memory_accessor_peek_segment_descriptor(\FFI\CData $accessor, int $gdtr_base, int $gdtr_limit, int $ldtr_selector, int $ldtr_base, int $ldtr_limit, int $selector, \FFI\CData $result)
+ * @method void memory_accessor_read_gate_descriptor(\FFI\CData $accessor, int $idtr_base, int $idtr_limit, int $vector, \FFI\CData $result, \FFI\CData $result_error)
+ * @method int memory_accessor_push(\FFI\CData $accessor, int $value, int $size, bool $is_user, bool $paging_enabled, int $linear_mask)
+ * @method void memory_accessor_pop(\FFI\CData $accessor, int $size, bool $is_user, bool $paging_enabled, int $linear_mask, \FFI\CData $result_value, \FFI\CData $result_error)
+ * @method bool memory_accessor_descriptor_table(\FFI\CData $accessor, int $table, \FFI\CData $result)
+ * @method bool memory_accessor_load_descriptor_table(\FFI\CData $accessor, int $table, int $base, int $limit, int $selector)
+ * @method int memory_accessor_read_eflags(\FFI\CData $accessor)
+ * @method void memory_accessor_write_eflags(\FFI\CData $accessor, int $value)
+ * @method void memory_accessor_write_system_flags(\FFI\CData $accessor, int $value)
+ * @method void memory_accessor_task_switch(\FFI\CData $accessor, int $selector, int $reason, int $return_eip, \FFI\CData $result_eip, \FFI\CData $result_error)
+ * @method void memory_accessor_interrupt_task_switch(\FFI\CData $accessor, int $vector, int $return_eip, bool $has_error_code, int $error_code, \FFI\CData $result_eip, \FFI\CData $result_error)
+ * @method void memory_accessor_iret_task_switch(\FFI\CData $accessor, int $return_eip, \FFI\CData $result_eip, \FFI\CData $result_error)
+ * @method void memory_accessor_rdmsr(\FFI\CData $accessor, int $index, \FFI\CData $result_value, \FFI\CData $result_error)
+ * @method int memory_accessor_wrmsr(\FFI\CData $accessor, int $index, int $value)
+ * @method void memory_accessor_read_debug_register(\FFI\CData $accessor, int $index, \FFI\CData $result_value, \FFI\CData $result_error)
+ * @method int memory_accessor_write_debug_register(\FFI\CData $accessor, int $index, int $value)
+ * @method int memory_accessor_debug_instruction_begin(\FFI\CData $accessor, int $linear)
+ * @method int memory_accessor_finish_instruction(\FFI\CData $accessor)
+ * @method bool memory_accessor_debug_armed(\FFI\CData $accessor)
+ * @method void memory_accessor_invlpg(\FFI\CData $accessor, int $linear)
+ * @method int memory_accessor_invpcid(\FFI\CData $accessor, int $kind, int $descriptor_pcid, int $linear)
+ * @method void memory_accessor_walk(\FFI\CData $accessor, int $linear, \FFI\CData $result)
+ * @method int memory_accessor_page_table_dump(\FFI\CData $accessor, \FFI\CData|null $buffer, int $buffer_len)
+ * @method int memory_accessor_physical_to_linear(\FFI\CData $accessor, int $physical, \FFI\CData|null $result, int $capacity)
+ * @method void memory_accessor_get_state(\FFI\CData $accessor, \FFI\CData $result)
+ * @method void memory_accessor_set_state(\FFI\CData $accessor, \FFI\CData $state)
+ * @method int memory_accessor_save_state(\FFI\CData $accessor, string $path)
+ * @method int memory_accessor_load_state(\FFI\CData $accessor, string $path)
+ * @method void memory_accessor_replay_record(\FFI\CData $accessor)
+ * @method int memory_accessor_replay_load(\FFI\CData $accessor, string $path)
+ * @method int memory_accessor_replay_save(\FFI\CData $accessor, string $path)
+ * @method void memory_accessor_replay_stop(\FFI\CData $accessor)
+ * @method int memory_accessor_replay_mode(\FFI\CData $accessor)
+ * @method void memory_accessor_retire_instruction(\FFI\CData $accessor)
+ * @method int memory_accessor_instruction_count(\FFI\CData $accessor)
+ * @method int memory_accessor_replay_input(\FFI\CData $accessor, int $kind, int $address, int $size, int $value, \FFI\CData $result)
+ * @method void memory_accessor_record_interrupt(\FFI\CData $accessor, int $vector)
+ * @method int memory_accessor_replay_interrupt(\FFI\CData $accessor)
+ * @method int memory_accessor_read_tsc(\FFI\CData $accessor)
+ * @method bool memory_accessor_trace_start(\FFI\CData $accessor, string $path, int $accessMask)
+ * @method void memory_accessor_trace_add_range(\FFI\CData $accessor, int $start, int $end)
+ * @method bool memory_accessor_trace_stop(\FFI\CData $accessor)
+ * @method int memory_trace_convert(string $input, string $output)
+ * @method void memory_accessor_fetch_code(\FFI\CData $accessor, int $linear, int $maxLen, bool $isUser, bool $pagingEnabled, int $linearMask, \FFI\CData $result)
+ * @method void x86_resolve_memory_operand(\FFI\CData $instruction, bool $is_64bit)
+ * @method int memory_accessor_effective_offset(\FFI\CData $accessor, \FFI\CData $instruction, int $nextIp)
+ * @method void memory_accessor_effective_address(\FFI\CData $accessor, \FFI\CData $instruction, int $nextIp, int $size, int $kind, \FFI\CData $result)
+ * @method void memory_accessor_set_a20(\FFI\CData $accessor, bool $enabled)
  * @method bool uint64_from_decimal(string $value, \FFI\CData $out_low, \FFI\CData $out_high)
  * @method int uint64_to_i64(int $low, int $high)
  * @method bool uint64_to_decimal(int $low, int $high, \FFI\CData $buffer, int $buffer_len)
@@ -258,6 +313,9 @@ void memory_accessor_set_instruction_fetch(void* accessor, bool value);
 // Control registers
 int64_t memory_accessor_read_control_register(const void* accessor, size_t index);
 void memory_accessor_write_control_register(void* accessor, size_t index, int64_t value);
+uint32_t memory_accessor_load_control_register(void* accessor, size_t index, uint64_t value);
+uint8_t memory_accessor_task_priority(const void* accessor);
+void memory_accessor_set_task_priority(void* accessor, uint8_t tpr);
 
 // EFER
 uint64_t memory_accessor_read_efer(const void* accessor);
@@ -288,6 +346,187 @@ uint32_t memory_accessor_write_memory_32(void* accessor, uint64_t linear, uint32
 uint32_t memory_accessor_write_memory_64(void* accessor, uint64_t linear, uint64_t value, bool is_user, bool paging_enabled, uint64_t linear_mask);
 void memory_accessor_write_physical_16(void* accessor, size_t address, uint16_t value);
 
+// Segment descriptor caches (0=ES, 1=CS, 2=SS, 3=DS, 4=FS, 5=GS)
+typedef struct {
+    uint16_t selector;
+    uint16_t attributes;
+    uint32_t limit;
+    uint64_t base;
+} SegmentCache;
+bool memory_accessor_segment_cache(const void* accessor, size_t segment, SegmentCache* result);
+bool memory_accessor_load_segment_cache(void* accessor, size_t segment, uint16_t selector, uint64_t base, uint32_t limit, uint16_t attributes);
+void memory_accessor_segmented_linear(const void* accessor, size_t segment, uint64_t offset, uint32_t size, uint32_t kind, uint64_t* result_linear, uint32_t* result_error);
+
+// Descriptor tables (kind: 0=invalid, 1=code, 2=data, 3=LDT, 4=TSS available,
+// 5=TSS busy, 6=call gate, 7=interrupt gate, 8=trap gate, 9=task gate)
+typedef struct {
+    uint64_t base;
+    uint64_t offset;
+    uint32_t limit;
+    uint32_t kind;
+    uint16_t selector;
+    uint16_t attributes;
+    uint8_t descriptor_type;
+    uint8_t dpl;
+    bool present;
+    uint8_t default_size;
+    uint8_t param_count;
+    uint8_t ist;
+} SegmentDescriptor;
+void memory_accessor_read_segment_descriptor(void* accessor, uint64_t gdtr_base, uint32_t gdtr_limit, uint16_t ldtr_selector, uint64_t ldtr_base, uint32_t ldtr_limit, uint16_t selector, bool set_accessed, SegmentDescriptor* result, uint32_t* result_error);
+bool memory_accessor_peek_segment_descriptor(void* accessor, uint64_t gdtr_base, uint32_t gdtr_limit, uint16_t ldtr_selector, uint64_t ldtr_base, uint32_t ldtr_limit, uint16_t selector, SegmentDescriptor* result);
+void memory_accessor_read_gate_descriptor(void* accessor, uint64_t idtr_base, uint32_t idtr_limit, uint8_t vector, SegmentDescriptor* result, uint32_t* result_error);
+
+// Stack operations
+uint32_t memory_accessor_push(void* accessor, uint64_t value, uint32_t size, bool is_user, bool paging_enabled, uint64_t linear_mask);
+void memory_accessor_pop(void* accessor, uint32_t size, bool is_user, bool paging_enabled, uint64_t linear_mask, uint64_t* result_value, uint32_t* result_error);
+
+// Descriptor-table registers (0=GDTR, 1=IDTR, 2=LDTR, 3=TR) and EFLAGS
+typedef struct {
+    uint64_t base;
+    uint32_t limit;
+    uint16_t selector;
+} DescriptorTableRegister;
+bool memory_accessor_descriptor_table(const void* accessor, size_t table, DescriptorTableRegister* result);
+bool memory_accessor_load_descriptor_table(void* accessor, size_t table, uint64_t base, uint32_t limit, uint16_t selector);
+uint32_t memory_accessor_read_eflags(const void* accessor);
+void memory_accessor_write_eflags(void* accessor, uint32_t value);
+void memory_accessor_write_system_flags(void* accessor, uint32_t value);
+
+// Task switching (reason: 0=JMP, 1=CALL, 2=IRET, 3=interrupt)
+void memory_accessor_task_switch(void* accessor, uint16_t selector, uint32_t reason, uint32_t return_eip, uint32_t* result_eip, uint32_t* result_error);
+void memory_accessor_interrupt_task_switch(void* accessor, uint8_t vector, uint32_t return_eip, bool has_error_code, uint32_t error_code, uint32_t* result_eip, uint32_t* result_error);
+void memory_accessor_iret_task_switch(void* accessor, uint32_t return_eip, uint32_t* result_eip, uint32_t* result_error);
+
+// Model-specific registers (result_error: 0 or #GP(0) as vector << 16)
+void memory_accessor_rdmsr(void* accessor, uint32_t index, uint64_t* result_value, uint32_t* result_error);
+uint32_t memory_accessor_wrmsr(void* accessor, uint32_t index, uint64_t value);
+
+// Debug registers and hardware breakpoints (errors packed as vector << 16 | error_code)
+void memory_accessor_read_debug_register(void* accessor, size_t index, uint64_t* result_value, uint32_t* result_error);
+uint32_t memory_accessor_write_debug_register(void* accessor, size_t index, uint64_t value);
+uint32_t memory_accessor_debug_instruction_begin(void* accessor, uint64_t linear);
+uint32_t memory_accessor_finish_instruction(void* accessor);
+bool memory_accessor_debug_armed(const void* accessor);
+
+// TLB
+void memory_accessor_invlpg(void* accessor, uint64_t linear);
+uint32_t memory_accessor_invpcid(void* accessor, uint64_t kind, uint64_t descriptor_pcid, uint64_t linear);
+
+// Page-table introspection (status: 0=mapped, 1=not present, 2=reserved bit,
+// 3=non-canonical, 4=paging disabled; flags: 1=W, 2=U, 4=XD, 8=G, 16=A, 32=D)
+typedef struct {
+    uint64_t entry_address;
+    uint64_t entry;
+} PageWalkLevel;
+typedef struct {
+    PageWalkLevel levels[5];
+    uint32_t level_count;
+    uint32_t status;
+    uint64_t physical;
+    uint64_t page_size;
+    uint32_t flags;
+    uint32_t fault;
+} PageWalk;
+void memory_accessor_walk(const void* accessor, uint64_t linear, PageWalk* result);
+size_t memory_accessor_page_table_dump(const void* accessor, char* buffer, size_t buffer_len);
+size_t memory_accessor_physical_to_linear(const void* accessor, uint64_t physical, uint64_t* result, size_t capacity);
+
+// Bulk CPU state (gprs: RAX..RDI then R8..R15; segments: ES, CS, SS, DS, FS, GS)
+typedef struct {
+    uint64_t gprs[16];
+    uint64_t rip;
+    uint64_t rflags;
+    uint64_t control_registers[9];
+    uint64_t efer;
+    uint16_t segments[6];
+} CpuState;
+void memory_accessor_get_state(const void* accessor, CpuState* result);
+void memory_accessor_set_state(void* accessor, const CpuState* state);
+
+// Save states (status: 0=ok, 1=bad magic, 2=unsupported version, 3=bad checksum,
+// 4=truncated, 5=memory size mismatch, 6=I/O error)
+uint32_t memory_accessor_save_state(const void* accessor, const char* path);
+uint32_t memory_accessor_load_state(void* accessor, const char* path);
+
+// Record/replay of nondeterministic inputs (kind: 0=MMIO read, 1=TSC, 2=interrupt;
+// mode: 0=off, 1=recording, 2=replaying; input status: 0=ok, 1=diverged, 2=exhausted;
+// log status: 0=ok, 1=bad magic, 2=unsupported version, 3=bad checksum, 4=truncated, 5=I/O error)
+void memory_accessor_replay_record(void* accessor);
+uint32_t memory_accessor_replay_load(void* accessor, const char* path);
+uint32_t memory_accessor_replay_save(const void* accessor, const char* path);
+void memory_accessor_replay_stop(void* accessor);
+uint32_t memory_accessor_replay_mode(const void* accessor);
+void memory_accessor_retire_instruction(void* accessor);
+uint64_t memory_accessor_instruction_count(const void* accessor);
+uint32_t memory_accessor_replay_input(void* accessor, uint32_t kind, uint64_t address, uint32_t size, uint64_t value, uint64_t* result);
+void memory_accessor_record_interrupt(void* accessor, uint8_t vector);
+int32_t memory_accessor_replay_interrupt(void* accessor);
+uint64_t memory_accessor_read_tsc(void* accessor);
+
+// Memory-access tracing (access mask: 1=read, 2=write, 4=execute)
+bool memory_accessor_trace_start(void* accessor, const char* path, uint16_t access_mask);
+void memory_accessor_trace_add_range(void* accessor, uint64_t start, uint64_t end);
+bool memory_accessor_trace_stop(void* accessor);
+int64_t memory_trace_convert(const char* input, const char* output);
+
+// Instruction fetch window (fault: packed fault for byte `length`, 0xFFFFFFFF for MMIO)
+typedef struct {
+    uint8_t bytes[16];
+    uint32_t length;
+    uint32_t fault;
+} CodeWindow;
+void memory_accessor_fetch_code(void* accessor, uint64_t linear, size_t max_len, bool is_user, bool paging_enabled, uint64_t linear_mask, CodeWindow* result);
+
+// Decoded instruction, filled in by x86_resolve_memory_operand for ModRM memory operands
+typedef struct {
+    uint8_t kind;
+    uint8_t register_class;
+    uint8_t reg;  // `register` in Rust; a C keyword
+    uint8_t size;
+} DecodedOperand;
+typedef struct {
+    int64_t displacement;
+    uint64_t immediate;
+    uint64_t immediate2;
+    DecodedOperand operands[4];
+    uint8_t status;
+    uint8_t map;
+    uint8_t opcode;
+    uint8_t length;
+    uint8_t prefixes;
+    uint8_t rex;
+    uint8_t mandatory_prefix;
+    uint8_t segment;
+    uint8_t operand_size;
+    uint8_t address_size;
+    bool has_modrm;
+    uint8_t modrm;
+    bool has_sib;
+    uint8_t sib;
+    uint8_t reg;
+    uint8_t rm;
+    uint8_t base;
+    uint8_t index;
+    uint8_t scale;
+    uint8_t displacement_size;
+    uint8_t immediate_size;
+    uint8_t immediate2_size;
+} DecodedInstruction;
+void x86_resolve_memory_operand(DecodedInstruction* instruction, bool is_64bit);
+
+// Effective addresses of decoded memory operands (kind: 0=read, 1=write, 2=execute; size in bits)
+typedef struct {
+    uint64_t offset;
+    uint64_t linear;
+    uint32_t segment;
+    uint32_t fault;
+} EffectiveAddress;
+uint64_t memory_accessor_effective_offset(void* accessor, const DecodedInstruction* instruction, uint64_t next_ip);
+void memory_accessor_effective_address(void* accessor, const DecodedInstruction* instruction, uint64_t next_ip, uint32_t size, uint32_t kind, EffectiveAddress* result);
+
+void memory_accessor_set_a20(void* accessor, bool enabled);
+
 // UInt64 helpers
 bool uint64_from_decimal(const char* value, uint32_t* out_low, uint32_t* out_high);
 int64_t uint64_to_i64(uint32_t low, uint32_t high);
@@ -1071,6 +1071,7 @@ trait UEFIEnvironmentKernelFastBoot
             'executable' => true,
             'dpl' => 0,
             'default' => 32,
+            'granularity' => true,
         ]);
 
         if ($this->linuxKernelGdtLogCount < 5) {
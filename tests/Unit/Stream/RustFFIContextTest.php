@@ -0,0 +1,28 @@
+<?php
+
+declare(strict_types=1);
+
+namespace Tests\Unit\Stream;
+
+use PHPMachineEmulator\Stream\RustFFIContext;
+use PHPUnit\Framework\TestCase;
+
+/**
+ * FFI::cdef parses the whole header and binds every declared function, so a
+ * C syntax error or a declaration without a native export fails here instead
+ * of at the first emulator boot.
+ */
+class RustFFIContextTest extends TestCase
+{
+    public function testHeaderParsesAndBindsAgainstTheNativeLibrary(): void
+    {
+        if (!extension_loaded('ffi')) {
+            $this->markTestSkipped('FFI extension is not loaded');
+        }
+
+        $context = new RustFFIContext();
+
+        $this->assertInstanceOf(\FFI::class, $context->ffi());
+        $this->assertGreaterThan(0, \FFI::sizeof($context->new('DecodedInstruction')));
+    }
+}
@@ -13,6 +13,7 @@ use PHPMachineEmulator\Instruction\RegisterType;
 use PHPMachineEmulator\Runtime\IterationContext;
 use PHPMachineEmulator\Runtime\IterationContextInterface;
 use PHPMachineEmulator\Runtime\RuntimeCPUContextInterface;
+use PHPMachineEmulator\Runtime\RuntimeCPUContextObserverInterface;
 use PHPMachineEmulator\Util\UInt64;
 
 class TestCPUContext implements RuntimeCPUContextInterface
@@ -233,6 +234,7 @@ class TestCPUContext implements RuntimeCPUContextInterface
     public function setGdtr(int $base, int $limit): void
     {
         $this->gdtr = ['base' => $base, 'limit' => $limit];
+        $this->observer?->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_GDTR, $base, $limit, 0);
     }
 
     public function gdtr(): array
@@ -243,6 +245,7 @@ class TestCPUContext implements RuntimeCPUContextInterface
     public function setIdtr(int $base, int $limit): void
     {
         $this->idtr = ['base' => $base, 'limit' => $limit];
+        $this->observer?->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_IDTR, $base, $limit, 0);
     }
 
     public function idtr(): array
@@ -253,6 +256,7 @@ class TestCPUContext implements RuntimeCPUContextInterface
     public function enableA20(bool $enabled = true): void
     {
         $this->a20Enabled = $enabled;
+        $this->observer?->a20Changed($enabled);
     }
 
     public function isA20Enabled(): bool
@@ -308,6 +312,12 @@ class TestCPUContext implements RuntimeCPUContextInterface
             'base' => $baseValue,
             'limit' => $limit & 0xFFFFFFFF,
         ];
+        $this->observer?->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_TR,
+            $this->taskRegister['base'],
+            $this->taskRegister['limit'],
+            $this->taskRegister['selector'],
+        );
     }
 
     public function taskRegister(): array
@@ -323,6 +333,12 @@ class TestCPUContext implements RuntimeCPUContextInterface
             'base' => $baseValue,
             'limit' => $limit & 0xFFFFFFFF,
         ];
+        $this->observer?->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_LDTR,
+            $this->ldtr['base'],
+            $this->ldtr['limit'],
+            $this->ldtr['selector'],
+        );
     }
 
     public function ldtr(): array
@@ -557,10 +573,37 @@ class TestCPUContext implements RuntimeCPUContextInterface
     // ========================================
 
     private array $segmentDescriptorCache = [];
+    private ?RuntimeCPUContextObserverInterface $observer = null;
 
     public function cacheSegmentDescriptor(RegisterType $segment, array $descriptor): void
     {
         $this->segmentDescriptorCache[$segment->name] = $descriptor;
+        $this->observer?->segmentCached($segment, $descriptor);
+    }
+
+    public function attachObserver(RuntimeCPUContextObserverInterface $observer): void
+    {
+        $this->observer = $observer;
+        $observer->a20Changed($this->a20Enabled);
+        $this->replayDescriptorTables($observer);
+    }
+
+    private function replayDescriptorTables(RuntimeCPUContextObserverInterface $observer): void
+    {
+        $observer->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_GDTR, $this->gdtr['base'], $this->gdtr['limit'], 0);
+        $observer->descriptorTableLoaded(RuntimeCPUContextObserverInterface::TABLE_IDTR, $this->idtr['base'], $this->idtr['limit'], 0);
+        $observer->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_LDTR,
+            $this->ldtr['base'],
+            $this->ldtr['limit'],
+            $this->ldtr['selector'],
+        );
+        $observer->descriptorTableLoaded(
+            RuntimeCPUContextObserverInterface::TABLE_TR,
+            $this->taskRegister['base'],
+            $this->taskRegister['limit'],
+            $this->taskRegister['selector'],
+        );
     }
 
     public function getCachedSegmentDescriptor(RegisterType $segment): ?array